@@ -0,0 +1,53 @@
+use crate::history::Command;
+use crate::CanvasState;
+use eframe::egui::{Color32, Pos2, Rect, Vec2};
+
+/// World-space height of a note's drag strip.
+pub const NOTE_HEADER: f32 = 20.0;
+pub const MIN_NOTE_SIZE: Vec2 = Vec2::new(120.0, 80.0);
+/// Tints offered in a note's context menu; the first is the default.
+pub const NOTE_COLORS: [(Color32, &str); 4] = [
+    (Color32::from_rgb(240, 200, 60), "Yellow"),
+    (Color32::from_rgb(240, 120, 150), "Pink"),
+    (Color32::from_rgb(110, 200, 120), "Green"),
+    (Color32::from_rgb(100, 160, 240), "Blue"),
+];
+
+/// A sticky note. Notes sit behind the nodes and stay out of the pipeline: no edges, no physics, and no export text unless asked for.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Annotation {
+    pub id: u64,
+    pub rect: Rect,
+    pub text: String,
+    pub color: Color32,
+}
+
+impl Annotation {
+    pub fn header(&self) -> Rect { Rect::from_min_size(self.rect.min, Vec2::new(self.rect.width(), NOTE_HEADER)) }
+}
+
+impl CanvasState {
+    pub fn add_annotation(&mut self, center: Pos2) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.execute(Command::AddAnnotation(Annotation { id, rect: Rect::from_center_size(center, Vec2::new(200.0, 140.0)), text: String::new(), color: NOTE_COLORS[0].0 }));
+        id
+    }
+
+    pub fn remove_annotation(&mut self, id: u64) {
+        if let Some(index) = self.annotations.iter().position(|a| a.id == id) {
+            let note = self.annotations[index].clone();
+            self.execute(Command::RemoveAnnotation(index, note));
+        }
+    }
+
+    pub fn set_annotation_color(&mut self, id: u64, color: Color32) {
+        let Some(before) = self.annotations.iter().find(|a| a.id == id).cloned() else { return };
+        if before.color != color { self.execute(Command::EditAnnotation { after: Annotation { color, ..before.clone() }, before }); }
+    }
+
+    /// Topmost note containing the world position.
+    pub fn annotation_at(&self, world: Pos2) -> Option<u64> {
+        self.annotations.iter().rev().find(|a| a.rect.contains(world)).map(|a| a.id)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{CanvasState, Instant, NodeData};
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+/// Voices the TTS provider knows; the first is the default.
+pub const VOICES: [&str; 6] = ["alloy", "echo", "fable", "onyx", "nova", "shimmer"];
+
+pub fn default_voice() -> String { VOICES[0].to_string() }
+
+/// Length of a PCM WAV clip from its header; `None` for anything that isn't one.
+pub fn wav_duration(bytes: &[u8]) -> Option<Duration> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" { return None; }
+    let u32_at = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    let (mut at, mut byte_rate) = (12, None);
+    while let (Some(tag), Some(size)) = (bytes.get(at..at + 4), u32_at(at + 4)) {
+        match tag {
+            b"fmt " => byte_rate = u32_at(at + 16).filter(|&r| r > 0),
+            // Streamed WAVs leave the size at its maximum; what arrived is what plays.
+            b"data" => return Some(Duration::from_secs_f64(size.min(bytes.len() - at - 8) as f64 / byte_rate? as f64)),
+            _ => {}
+        }
+        at = at.saturating_add(8 + size % 2).saturating_add(size);
+    }
+    None
+}
+
+/// "0:12" for a clip, "?" when its length can't be read.
+pub fn duration_label(bytes: &[u8]) -> String {
+    wav_duration(bytes).map_or("?".to_string(), crate::refresh::countdown)
+}
+
+impl CanvasState {
+    /// Result of the closest Agnostic AI node reachable by walking edges backwards from `id`, breadth first.
+    pub fn upstream_ai_result(&self, id: u64) -> Option<String> {
+        let mut queue = VecDeque::from([id]);
+        let mut seen = HashSet::from([id]);
+        while let Some(current) = queue.pop_front() {
+            for edge in self.edges.iter().filter(|e| e.to == current && self.edge_active(e)) {
+                if !seen.insert(edge.from) { continue; }
+                match self.nodes.get(&edge.from).map(|n| &n.data) {
+                    Some(NodeData::AgnosticAI { result: Some(text), .. }) if !text.trim().is_empty() => return Some(text.clone()),
+                    Some(_) => queue.push_back(edge.from),
+                    None => {}
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Players tried in order on native builds; the clip is handed over as a temporary WAV file.
+#[cfg(all(not(target_arch = "wasm32"), target_os = "macos"))]
+const PLAYERS: &[(&str, &[&str])] = &[("afplay", &[])];
+#[cfg(all(not(target_arch = "wasm32"), target_os = "windows"))]
+const PLAYERS: &[(&str, &[&str])] = &[("powershell", &["-NoProfile", "-Command", "(New-Object Media.SoundPlayer $args[0]).PlaySync()"])];
+#[cfg(all(not(target_arch = "wasm32"), not(any(target_os = "macos", target_os = "windows"))))]
+const PLAYERS: &[(&str, &[&str])] = &[("paplay", &[]), ("aplay", &["-q"]), ("ffplay", &["-nodisp", "-autoexit", "-loglevel", "quiet"])];
+
+/// The one clip playing at a time: through the system's command-line player on native, an `HtmlAudioElement` on a blob URL in the
+/// browser.
+#[derive(Default)]
+pub struct Player {
+    /// Node whose clip is playing and when it started.
+    playing: Option<(u64, Instant)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    child: Option<std::process::Child>,
+    #[cfg(target_arch = "wasm32")]
+    element: Option<(web_sys::HtmlAudioElement, String)>,
+}
+
+impl Player {
+    /// How far into node `id`'s clip playback is, while it plays.
+    pub fn position(&self, id: u64) -> Option<Duration> {
+        self.playing.filter(|(node, _)| *node == id).map(|(_, started)| started.elapsed())
+    }
+
+    pub fn is_playing(&self) -> bool { self.playing.is_some() }
+
+    /// Stops whatever is playing and starts `bytes` for node `id`.
+    pub fn play(&mut self, id: u64, bytes: &[u8]) -> Result<(), String> {
+        self.stop();
+        self.start(id, bytes)?;
+        self.playing = Some((id, Instant::now()));
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start(&mut self, id: u64, bytes: &[u8]) -> Result<(), String> {
+        use std::process::{Command, Stdio};
+        let path = std::env::temp_dir().join(format!("storyboard_narration_{}.wav", id));
+        std::fs::write(&path, bytes).map_err(|e| format!("Couldn't write the clip: {}", e))?;
+        for (program, args) in PLAYERS {
+            if let Ok(child) = Command::new(program).args(*args).arg(&path).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+                self.child = Some(child);
+                return Ok(());
+            }
+        }
+        Err(format!("No audio player found (tried {})", PLAYERS.iter().map(|(p, _)| *p).collect::<Vec<_>>().join(", ")))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn start(&mut self, _id: u64, bytes: &[u8]) -> Result<(), String> {
+        let parts = js_sys::Array::new();
+        parts.push(&js_sys::Uint8Array::from(bytes));
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type("audio/wav");
+        let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).map_err(|_| "Couldn't wrap the clip".to_string())?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(|_| "Couldn't wrap the clip".to_string())?;
+        let element = web_sys::HtmlAudioElement::new_with_src(&url).map_err(|_| "The browser can't play audio".to_string())?;
+        let _ = element.play();
+        self.element = Some((element, url));
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(mut child) = self.child.take() { let _ = child.kill(); let _ = child.wait(); }
+        #[cfg(target_arch = "wasm32")]
+        if let Some((element, url)) = self.element.take() { let _ = element.pause(); let _ = web_sys::Url::revoke_object_url(&url); }
+    }
+
+    /// Notices a clip that played to the end. Call once per frame.
+    pub fn poll(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let finished = self.child.as_mut().is_none_or(|c| !matches!(c.try_wait(), Ok(None)));
+        #[cfg(target_arch = "wasm32")]
+        let finished = self.element.as_ref().is_none_or(|(e, _)| e.ended());
+        if finished && self.playing.is_some() { self.stop(); }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) { self.stop(); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Node, NodeKind};
+
+    /// A mono 16-bit clip of `samples` samples at 8 kHz.
+    fn wav(samples: usize) -> Vec<u8> {
+        let data = samples * 2;
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        for v in [16u32, 1 | (1 << 16), 8000, 16000, 2 | (16 << 16)] { out.extend_from_slice(&v.to_le_bytes()); }
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&(data as u32).to_le_bytes());
+        out.resize(out.len() + data, 0);
+        out
+    }
+
+    #[test]
+    fn reads_clip_length_from_the_header() {
+        assert_eq!(wav_duration(&wav(24_000)), Some(Duration::from_secs(3)));
+        assert_eq!(duration_label(&wav(8000 * 75)), "1:15");
+        assert_eq!(wav_duration(b"ID3 not a wav at all"), None);
+        let mut truncated = wav(8000);
+        truncated.truncate(44 + 8000);
+        assert_eq!(wav_duration(&truncated), Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn narration_comes_from_the_nearest_ai_result() {
+        let mut state = CanvasState::default();
+        let ai = |result: &str| NodeData::AgnosticAI { model: String::new(), prompt: String::new(), result: Some(result.to_string()), is_loading: false, auto_run: false, provider: Default::default() };
+        state.nodes.insert(1, Node::new(1, Default::default(), ai("outline")));
+        state.nodes.insert(2, Node::new(2, Default::default(), ai("script")));
+        state.nodes.insert(3, Node::new(3, Default::default(), NodeKind::Merge.default_data()));
+        state.nodes.insert(4, Node::new(4, Default::default(), NodeKind::Audio.default_data()));
+        state.link_all(&[(1, 2), (2, 3), (3, 4)]);
+        assert_eq!(state.upstream_ai_result(4).as_deref(), Some("script"));
+        assert_eq!(state.upstream_ai_result(1), None);
+    }
+}
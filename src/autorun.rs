@@ -0,0 +1,29 @@
+use crate::{templates, NodeData, StoryBoardApp};
+use eframe::egui;
+
+pub const AUTO_RUN_HINT: &str = "Generate as soon as a parent finishes, with the parent's output linked in";
+
+impl StoryBoardApp {
+    /// Called when node `parent` gets a text result: starts each direct child with "Auto-run" ticked, once, after pulling in its
+    /// parents' output the way "Link Parent" does. Children caught in a cycle with `parent`, or already running, are left alone,
+    /// and nothing fires while a pipeline run is driving the board.
+    pub(crate) fn auto_run_children(&mut self, parent: u64, ctx: &egui::Context) {
+        if self.pipeline.is_some() || self.state.nodes.get(&parent).and_then(|n| n.data.output()).is_none() { return; }
+        let mut children: Vec<u64> = self.state.edges.iter().filter(|e| e.from == parent && self.state.edge_active(e)).map(|e| e.to).collect();
+        children.sort();
+        children.dedup();
+        for child in children {
+            if self.state.descendants(child).contains(&parent) { continue; }
+            let input = self.state.parent_output(child);
+            let Some(node) = self.state.nodes.get_mut(&child) else { continue };
+            match &mut node.data {
+                NodeData::AgnosticAI { prompt, is_loading: false, auto_run: true, .. } | NodeData::Visual { prompt, is_loading: false, auto_run: true, .. } => {
+                    if let Some(text) = input.filter(|_| !templates::has_placeholders(prompt)) { *prompt = text; }
+                }
+                _ => continue,
+            }
+            self.retries.remove(&child);
+            self.run_node(child, ctx);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{CanvasState, Node, Project, StoryBoardApp};
+use eframe::egui;
+
+/// A named canvas. The active board's canvas lives in `StoryBoardApp::state`; its slot here holds an empty placeholder until another board is opened.
+pub struct Board {
+    pub name: String,
+    pub state: CanvasState,
+}
+
+/// Which nodes of a merged project belong to which board.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct BoardSummary {
+    pub name: String,
+    pub nodes: Vec<u64>,
+}
+
+impl Board {
+    pub fn new(name: impl Into<String>) -> Self { Self { name: name.into(), state: CanvasState::default() } }
+}
+
+impl StoryBoardApp {
+    /// Makes board `index` the live canvas. Ids keep counting from the highest handed out on any board, so responses can find their node wherever it lives.
+    pub(crate) fn switch_board(&mut self, index: usize) {
+        if index == self.active_board || index >= self.boards.len() { return; }
+        let next_id = self.state.next_id;
+        std::mem::swap(&mut self.state, &mut self.boards[self.active_board].state);
+        std::mem::swap(&mut self.state, &mut self.boards[index].state);
+        self.state.next_id = self.state.next_id.max(next_id);
+        self.active_board = index;
+        self.reset_transient_ui();
+    }
+
+    pub(crate) fn add_board(&mut self) {
+        let mut board = Board::new(format!("Board {}", self.boards.len() + 1));
+        board.state.next_id = self.state.next_id;
+        self.boards.push(board);
+        self.switch_board(self.boards.len() - 1);
+    }
+
+    /// Drops board `index` and everything on it; the last remaining board can't be closed.
+    pub(crate) fn close_board(&mut self, index: usize) {
+        if self.boards.len() < 2 || index >= self.boards.len() { return; }
+        if index == self.active_board { self.switch_board(if index == 0 { 1 } else { index - 1 }); }
+        self.boards.remove(index);
+        if index < self.active_board { self.active_board -= 1; }
+    }
+
+    /// Node `id` on whichever board owns it.
+    pub(crate) fn node_mut(&mut self, id: u64) -> Option<&mut Node> {
+        if self.state.nodes.contains_key(&id) { return self.state.nodes.get_mut(&id); }
+        self.boards.iter_mut().enumerate().filter(|(i, _)| *i != self.active_board).find_map(|(_, b)| b.state.nodes.get_mut(&id))
+    }
+
+    /// Every board merged into one project, with `boards` recording which nodes each one holds.
+    pub(crate) fn to_project(&self) -> Project {
+        let mut project = Project { name: self.project_name.clone(), nodes: Vec::new(), edges: Vec::new(), groups: Vec::new(), annotations: Vec::new(), draw_order: Vec::new(), boards: Vec::new() };
+        for (i, board) in self.boards.iter().enumerate() {
+            let state = if i == self.active_board { &self.state } else { &board.state };
+            let part = state.to_project(&board.name);
+            project.boards.push(BoardSummary { name: board.name.clone(), nodes: part.nodes.iter().map(|n| n.id).collect() });
+            project.nodes.extend(part.nodes);
+            project.edges.extend(part.edges);
+            project.groups.extend(part.groups);
+            project.annotations.extend(part.annotations);
+            project.draw_order.extend(part.draw_order);
+        }
+        project
+    }
+
+    /// Tab strip above the canvas: click to switch, double-click or right-click to rename, ➕ for a new board.
+    pub(crate) fn draw_board_tabs(&mut self, ctx: &egui::Context) {
+        let mut switch_to = None;
+        let mut close = None;
+        let mut add = false;
+        let count = self.boards.len();
+        egui::TopBottomPanel::top("board_tabs").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for (i, board) in self.boards.iter_mut().enumerate() {
+                    if let Some((_, draft)) = self.renaming_board.as_mut().filter(|(r, _)| *r == i) {
+                        let r = ui.add(egui::TextEdit::singleline(draft).desired_width(120.0));
+                        if r.lost_focus() {
+                            let name = draft.trim().to_string();
+                            if !ui.input(|i| i.key_pressed(egui::Key::Escape)) && !name.is_empty() { board.name = name; }
+                            self.renaming_board = None;
+                        } else if !r.has_focus() { r.request_focus(); }
+                        continue;
+                    }
+                    let nodes = if i == self.active_board { self.state.nodes.len() } else { board.state.nodes.len() };
+                    let r = ui.selectable_label(i == self.active_board, &board.name).on_hover_text("Double-click to rename");
+                    if r.clicked() { switch_to = Some(i); }
+                    if r.double_clicked() { self.renaming_board = Some((i, board.name.clone())); }
+                    r.context_menu(|ui| {
+                        if ui.button("✏ Rename").clicked() { self.renaming_board = Some((i, board.name.clone())); ui.close_menu(); }
+                        if ui.add_enabled(count > 1, egui::Button::new(format!("🗑 Close board ({} nodes)", nodes))).clicked() { close = Some(i); ui.close_menu(); }
+                    });
+                }
+                if ui.small_button("➕").on_hover_text("New board").clicked() { add = true; }
+            });
+        });
+        if let Some(i) = switch_to { self.switch_board(i); }
+        if let Some(i) = close { self.close_board(i); }
+        if add { self.add_board(); }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::history::Command;
+use crate::{requests, CanvasState, Edge, NodeData, StoryBoardApp};
+use eframe::egui;
+use std::collections::HashSet;
+
+/// Which way out of a Branch node an edge goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum BranchRole { True, False }
+
+impl BranchRole {
+    pub const ALL: [BranchRole; 2] = [Self::True, Self::False];
+
+    pub fn of(outcome: bool) -> Self { if outcome { Self::True } else { Self::False } }
+
+    pub fn label(self) -> &'static str {
+        match self { Self::True => "true", Self::False => "false" }
+    }
+}
+
+/// "true", "false" or "not evaluated" for a Branch node's last outcome.
+pub fn outcome_text(outcome: Option<bool>) -> &'static str {
+    outcome.map_or("not evaluated", |o| BranchRole::of(o).label())
+}
+
+pub fn default_branch_model() -> String { "google/gemini-flash-1.5".to_string() }
+
+/// Keyword check: `keyword` appears in `text`, ignoring case. An empty keyword matches nothing.
+pub fn keyword_matches(text: &str, keyword: &str) -> bool {
+    let keyword = keyword.trim().to_lowercase();
+    !keyword.is_empty() && text.to_lowercase().contains(&keyword)
+}
+
+/// Prompt for the AI check: the question, then the parents' text.
+pub fn ai_check_prompt(question: &str, text: &str) -> String {
+    format!("Answer with just \"yes\" or \"no\". {}\n\n---\n{}", question.trim(), text)
+}
+
+/// Reads the AI check's answer; anything but a leading "yes" counts as no.
+pub fn parse_yes_no(answer: &str) -> bool {
+    answer.trim_start().trim_start_matches(|c: char| !c.is_alphanumeric()).to_lowercase().starts_with("yes")
+}
+
+impl Edge {
+    /// Text drawn on the edge: its label, prefixed with the branch role if it has one.
+    pub fn display_label(&self) -> Option<String> {
+        match (&self.label, self.role) {
+            (Some(label), Some(role)) => Some(format!("{}: {}", role.label(), label)),
+            (Some(label), None) => Some(label.clone()),
+            (None, Some(role)) => Some(role.label().to_string()),
+            (None, None) => None,
+        }
+    }
+}
+
+impl CanvasState {
+    /// False for an edge leaving a Branch node on the side its last evaluation didn't take; everything else carries data.
+    pub fn edge_active(&self, edge: &Edge) -> bool {
+        match (self.nodes.get(&edge.from).map(|n| &n.data), edge.role) {
+            (Some(NodeData::Branch { outcome: Some(outcome), .. }), Some(role)) => role == BranchRole::of(*outcome),
+            _ => true,
+        }
+    }
+
+    /// Nodes a pipeline run leaves out: every way into them runs through a branch not taken. Nodes without parents always run.
+    pub fn skipped_by_branches(&self) -> HashSet<u64> {
+        let mut skipped = HashSet::new();
+        let Ok(order) = self.pipeline_order() else { return skipped };
+        for id in order {
+            let mut incoming = self.edges.iter().filter(|e| e.to == id && self.nodes.contains_key(&e.from)).peekable();
+            if incoming.peek().is_none() { continue; }
+            if incoming.all(|e| !self.edge_active(e) || skipped.contains(&e.from)) { skipped.insert(id); }
+        }
+        skipped
+    }
+
+    /// Role for a new edge out of `from`: the first one its other edges don't use yet, for Branch nodes only.
+    pub fn next_branch_role(&self, from: u64, taken: &[BranchRole]) -> Option<BranchRole> {
+        if !matches!(self.nodes.get(&from).map(|n| &n.data), Some(NodeData::Branch { .. })) { return None; }
+        BranchRole::ALL.into_iter().find(|role| !taken.contains(role) && !self.edges.iter().any(|e| e.from == from && e.role == Some(*role)))
+    }
+
+    pub fn set_edge_role(&mut self, id: u64, role: Option<BranchRole>) {
+        let Some(before) = self.edges.iter().find(|e| e.id == id).map(|e| e.role) else { return };
+        if before != role { self.execute(Command::EdgeRole { id, before, after: role }); }
+    }
+}
+
+impl StoryBoardApp {
+    /// Evaluates Branch node `id` against its parents' text: a keyword is checked on the spot, an AI check goes out as a yes/no
+    /// question and lands as a `TextResponse`.
+    pub(crate) fn evaluate_branch(&mut self, id: u64, ctx: &egui::Context) {
+        let text = self.state.parent_output(id).unwrap_or_default();
+        let Some(node) = self.state.nodes.get_mut(&id) else { return };
+        let NodeData::Branch { condition, use_ai, model, outcome, input, is_loading } = &mut node.data else { return };
+        *input = text.clone();
+        if !*use_ai {
+            *outcome = Some(keyword_matches(&text, condition));
+            *is_loading = false;
+            self.auto_run_children(id, ctx);
+            return;
+        }
+        *is_loading = true;
+        let body = requests::agnostic_ai_body(model, &ai_check_prompt(condition, &text));
+        self.post_json(Some(id), "/api/agnostic-ai", body, ctx.clone(), move |result| Some(crate::reply(id, result, |r| crate::AppMessage::TextResponse(id, r.text().unwrap_or_default().to_string()))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Node, NodeKind};
+
+    fn branch_board(outcome: Option<bool>) -> CanvasState {
+        // 1 → Branch 2 →true→ 3 → 5, 2 →false→ 4 → 5, and 4 also fed by the unrelated 6
+        let mut state = CanvasState::default();
+        for id in [1, 3, 4, 5, 6] { state.nodes.insert(id, Node::new(id, Default::default(), NodeKind::Concept.default_data())); }
+        let mut branch = NodeKind::Branch.default_data();
+        if let NodeData::Branch { outcome: o, .. } = &mut branch { *o = outcome; }
+        state.nodes.insert(2, Node::new(2, Default::default(), branch));
+        state.link_all(&[(1, 2), (2, 3), (2, 4), (3, 5), (4, 5)]);
+        state.edges[1].role = Some(BranchRole::True);
+        state.edges[2].role = Some(BranchRole::False);
+        state
+    }
+
+    #[test]
+    fn only_the_branch_not_taken_is_skipped() {
+        let mut skipped: Vec<u64> = branch_board(Some(true)).skipped_by_branches().into_iter().collect();
+        skipped.sort();
+        assert_eq!(skipped, vec![4], "5 still has a live parent in 3");
+        let mut state = branch_board(Some(false));
+        state.edges.retain(|e| e.from != 4);
+        let mut skipped: Vec<u64> = state.skipped_by_branches().into_iter().collect();
+        skipped.sort();
+        assert_eq!(skipped, vec![3, 5]);
+        assert!(branch_board(None).skipped_by_branches().is_empty(), "nothing is skipped before the branch is evaluated");
+    }
+
+    #[test]
+    fn new_edges_out_of_a_branch_take_the_free_role() {
+        let mut state = branch_board(None);
+        state.edges.retain(|e| e.role != Some(BranchRole::True));
+        assert_eq!(state.next_branch_role(2, &[]), Some(BranchRole::True));
+        assert_eq!(state.next_branch_role(2, &[BranchRole::True]), None);
+        assert_eq!(state.next_branch_role(1, &[]), None);
+    }
+
+    #[test]
+    fn conditions() {
+        assert!(keyword_matches("The Mars base is ready", "mars"));
+        assert!(!keyword_matches("anything", "  "));
+        assert!(parse_yes_no("Yes, it does.") && parse_yes_no("**yes**") && !parse_yes_no("No") && !parse_yes_no("Maybe yes"));
+    }
+}
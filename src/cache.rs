@@ -0,0 +1,143 @@
+use crate::queue::OnResult;
+use crate::runlog::{self, LogEntry};
+use crate::{Instant, StoryBoardApp};
+use eframe::egui;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+pub fn default_cache_mb() -> usize { 64 }
+pub fn default_cache_ttl_mins() -> u32 { 60 }
+
+/// Identifies a request by endpoint and body; object keys are hashed in sorted order so field order doesn't matter.
+pub fn request_key(endpoint: &str, body: &Value) -> u64 {
+    fn hash_value(value: &Value, h: &mut DefaultHasher) {
+        match value {
+            Value::Object(map) => {
+                0u8.hash(h);
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                for key in keys { key.hash(h); hash_value(&map[key], h); }
+            }
+            Value::Array(items) => { 1u8.hash(h); items.len().hash(h); for item in items { hash_value(item, h); } }
+            other => { 2u8.hash(h); other.to_string().hash(h); }
+        }
+    }
+    let mut hasher = DefaultHasher::new();
+    endpoint.hash(&mut hasher);
+    hash_value(body, &mut hasher);
+    hasher.finish()
+}
+
+struct CachedResponse {
+    response: ehttp::Response,
+    stored: Instant,
+    /// `ResponseCache::tick` when last stored or read, for LRU eviction.
+    used: u64,
+}
+
+/// Successful responses by `request_key`, within a byte budget; the least recently used go first.
+#[derive(Default)]
+pub struct ResponseCache {
+    entries: HashMap<u64, CachedResponse>,
+    tick: u64,
+    bytes: usize,
+}
+
+impl ResponseCache {
+    /// The stored response, unless it's older than `ttl`.
+    pub fn get(&mut self, key: u64, ttl: Duration) -> Option<ehttp::Response> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(&key)?;
+        if entry.stored.elapsed() >= ttl { self.remove(key); return None; }
+        entry.used = self.tick;
+        Some(entry.response.clone())
+    }
+
+    /// Stores `response`, evicting the least recently used entries until everything fits in `budget` bytes. Responses bigger than
+    /// the whole budget aren't kept.
+    pub fn insert(&mut self, key: u64, response: ehttp::Response, budget: usize) {
+        self.remove(key);
+        if response.bytes.len() > budget { return; }
+        while self.bytes + response.bytes.len() > budget {
+            let Some(oldest) = self.entries.iter().min_by_key(|(_, e)| e.used).map(|(&k, _)| k) else { break };
+            self.remove(oldest);
+        }
+        self.tick += 1;
+        self.bytes += response.bytes.len();
+        self.entries.insert(key, CachedResponse { response, stored: Instant::now(), used: self.tick });
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some(old) = self.entries.remove(&key) { self.bytes -= old.response.bytes.len(); }
+    }
+
+    pub fn clear(&mut self) { self.entries.clear(); self.bytes = 0; }
+    pub fn len(&self) -> usize { self.entries.len() }
+    pub fn bytes(&self) -> usize { self.bytes }
+}
+
+impl StoryBoardApp {
+    /// Answers node `node`'s request from the cache when an identical one succeeded within the TTL: `on_result` runs right away
+    /// and the node is flagged as cached. Gives `on_result` back on a miss.
+    pub(crate) fn answer_from_cache(&mut self, node: u64, key: u64, endpoint: &str, on_result: OnResult) -> Result<(), OnResult> {
+        let ttl = Duration::from_secs(u64::from(self.settings.cache_ttl_mins) * 60);
+        if self.settings.cache_mb == 0 || self.bypass_cache.contains(&node) { return Err(on_result); }
+        let Some(response) = self.cache.get(key, ttl) else { return Err(on_result) };
+        let pipeline = self.pipeline.as_ref().is_some_and(|run| run.order[..run.next].contains(&node));
+        let Some(n) = self.node_mut(node) else { return Err(on_result) };
+        n.cached = true;
+        let entry = LogEntry { at: runlog::now(), node, kind: n.data.kind(), endpoint: endpoint.to_string(), duration: Duration::ZERO, bytes: response.bytes.len(), error: None, pipeline, cached: true };
+        runlog::push_entry(&mut self.run_log, entry);
+        if let Some(msg) = on_result(Ok(response)) { let _ = self.http_tx.send(msg); }
+        Ok(())
+    }
+
+    /// "Force refresh": runs node `id` again, skipping the cache and replacing what it held.
+    pub(crate) fn force_refresh(&mut self, id: u64, ctx: &egui::Context) {
+        self.bypass_cache.insert(id);
+        self.retry_now(id, ctx);
+        self.bypass_cache.remove(&id);
+    }
+
+    pub(crate) fn store_response(&mut self, key: u64, response: ehttp::Response) {
+        if self.settings.cache_mb > 0 { self.cache.insert(key, response, self.settings.cache_mb * 1024 * 1024); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response(size: usize) -> ehttp::Response {
+        ehttp::Response { url: String::new(), ok: true, status: 200, status_text: "OK".to_string(), headers: Default::default(), bytes: vec![0; size] }
+    }
+
+    #[test]
+    fn keys_ignore_field_order_but_not_values() {
+        let a = request_key("/api/visualize", &json!({"prompt": "x", "seed": 1}));
+        assert_eq!(a, request_key("/api/visualize", &serde_json::from_str(r#"{"seed": 1, "prompt": "x"}"#).unwrap()));
+        assert_ne!(a, request_key("/api/visualize", &json!({"prompt": "x", "seed": 2})));
+        assert_ne!(a, request_key("/api/research", &json!({"prompt": "x", "seed": 1})));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_to_fit_the_budget() {
+        let mut cache = ResponseCache::default();
+        let ttl = Duration::from_secs(60);
+        cache.insert(1, response(40), 100);
+        cache.insert(2, response(40), 100);
+        assert!(cache.get(1, ttl).is_some());
+        cache.insert(3, response(40), 100);
+        assert!(cache.get(2, ttl).is_none(), "2 was used least recently");
+        assert!(cache.get(1, ttl).is_some() && cache.get(3, ttl).is_some());
+        assert_eq!(cache.bytes(), 80);
+        cache.insert(4, response(101), 100);
+        assert!(cache.get(4, ttl).is_none(), "bigger than the whole budget");
+        assert!(cache.get(1, Duration::ZERO).is_none(), "expired");
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{CanvasState, NodeData};
+use eframe::egui::{self, Vec2};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Largest portrait drawn in a Character node.
+const PORTRAIT: Vec2 = Vec2::new(72.0, 96.0);
+
+impl CanvasState {
+    /// Character nodes linked into `id`, once each in link order.
+    pub fn character_parents(&self, id: u64) -> Vec<u64> {
+        let mut seen = HashSet::new();
+        self.edges.iter().filter(|e| e.to == id && self.edge_active(e) && seen.insert(e.from)).filter(|e| matches!(self.nodes.get(&e.from).map(|n| &n.data), Some(NodeData::Character { .. }))).map(|e| e.from).collect()
+    }
+
+    /// One "Name: description" line per character linked into `id`, in link order; characters without a description are left out.
+    pub fn character_sheet(&self, id: u64) -> Option<String> {
+        let lines: Vec<String> = self.character_parents(id).into_iter().filter_map(|c| match &self.nodes[&c].data {
+            NodeData::Character { name, description, .. } if !description.trim().is_empty() => Some(if name.trim().is_empty() { description.trim().to_string() } else { format!("{}: {}", name.trim(), description.trim()) }),
+            _ => None,
+        }).collect();
+        if lines.is_empty() { None } else { Some(lines.join("\n")) }
+    }
+
+    /// Image a Visual node with "Use parent image" starts from: a parent's image, or else the first linked character's portrait.
+    pub fn img2img_source(&self, id: u64) -> Option<&[u8]> {
+        self.parent_image(id).or_else(|| self.character_parents(id).into_iter().find_map(|c| match &self.nodes[&c].data { NodeData::Character { reference_image: Some(bytes), .. } => Some(&bytes[..]), _ => None }))
+    }
+}
+
+/// Name, description and portrait of a Character node. The portrait comes from a linked image parent or an image file dropped on
+/// the node. Returns true when anything was edited.
+pub fn character_ui(ui: &mut egui::Ui, state: &CanvasState, id: u64, name: &mut String, description: &mut String, reference_image: &mut Option<Arc<[u8]>>, focus_body: bool) -> bool {
+    let mut changed = ui.add(egui::TextEdit::singleline(name).hint_text("Name").desired_width(f32::INFINITY)).changed();
+    let r = ui.add(egui::TextEdit::multiline(description).hint_text("Looks, clothing, age… added to every linked Visual prompt").desired_rows(3).desired_width(f32::INFINITY));
+    if focus_body { r.request_focus(); }
+    changed |= r.changed();
+    ui.horizontal(|ui| {
+        match reference_image.as_deref().and_then(|bytes| crate::cached_texture(ui.ctx(), id, bytes)) {
+            Some(texture) => { ui.add(egui::Image::new(&texture).max_size(PORTRAIT).rounding(4.0)); }
+            None => { ui.weak(if reference_image.is_some() { "⚠ Unreadable image" } else { "No portrait; drop an image here or link one in" }); }
+        }
+        ui.vertical(|ui| {
+            let parent = state.parent_image(id);
+            if ui.add_enabled(parent.is_some(), egui::Button::new("🖼 Use parent image")).on_hover_text("Take the linked image as this character's portrait").clicked() {
+                *reference_image = parent.map(Arc::from);
+                changed = true;
+            }
+            if reference_image.is_some() && ui.small_button("🗑 Remove").clicked() { *reference_image = None; changed = true; }
+        });
+    });
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CanvasState, Edge, Node, NodeData, NodeKind};
+
+    fn character(name: &str, description: &str, image: Option<Vec<u8>>) -> NodeData {
+        NodeData::Character { name: name.to_string(), description: description.to_string(), reference_image: image.map(Into::into) }
+    }
+
+    fn board() -> CanvasState {
+        // Characters 1 and 2 and Concept 3 feed Visual 4.
+        let mut state = CanvasState::default();
+        state.nodes.insert(1, Node::new(1, Default::default(), character("Ada", "keeper in a red coat", None)));
+        state.nodes.insert(2, Node::new(2, Default::default(), character("Bo", "small grey dog", Some(vec![7, 7]))));
+        state.nodes.insert(3, Node::new(3, Default::default(), NodeData::Concept { text: "storm at the lighthouse".to_string() }));
+        state.nodes.insert(4, Node::new(4, Default::default(), NodeKind::Visual.default_data()));
+        for from in [1, 3, 2] { state.edges.push(Edge { id: 10 + from, from, to: 4, label: None, role: None }); }
+        state
+    }
+
+    #[test]
+    fn linked_characters_lead_the_visual_prompt() {
+        let state = board();
+        assert_eq!(state.render_prompt(4, "{{parent}}, wide shot"), "Ada: keeper in a red coat\nBo: small grey dog\n\nstorm at the lighthouse, wide shot");
+        assert_eq!(state.parent_output(4).as_deref(), Some("storm at the lighthouse"), "characters aren't part of Link Parent's text");
+        assert_eq!(state.render_prompt(3, "plain"), "plain");
+        assert!(state.link_mismatch(1, 4).is_none() && state.link_mismatch(1, 3).is_some());
+    }
+
+    #[test]
+    fn a_portrait_stands_in_for_a_missing_parent_image() {
+        let mut state = board();
+        assert_eq!(state.img2img_source(4), Some(&[7u8, 7][..]));
+        let mut visual = NodeKind::Visual.default_data();
+        if let NodeData::Visual { image, .. } = &mut visual { *image = Some(vec![1]); }
+        state.nodes.insert(5, Node::new(5, Default::default(), visual));
+        state.edges.push(Edge { id: 20, from: 5, to: 4, label: None, role: None });
+        assert_eq!(state.img2img_source(4), Some(&[1u8][..]));
+    }
+}
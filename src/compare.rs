@@ -0,0 +1,76 @@
+use crate::{CanvasState, NodeData};
+use std::collections::HashSet;
+
+impl CanvasState {
+    /// The two parents of Compare node `id` as (A, B) in link order, or `None` unless it has exactly two.
+    pub fn compare_pair(&self, id: u64) -> Option<(u64, u64)> {
+        match self.text_parents(id)[..] {
+            [a, b] => Some((a, b)),
+            _ => None,
+        }
+    }
+
+    /// What Compare node `id` hands downstream: the picked parent's current output, or nothing until one is picked.
+    pub(crate) fn compared_text(&self, id: u64, picked: Option<u64>) -> String {
+        let Some((a, b)) = self.compare_pair(id) else { return String::new() };
+        picked.filter(|p| *p == a || *p == b).and_then(|p| self.nodes[&p].data.output()).unwrap_or_default().to_string()
+    }
+
+    /// Parents that lost a comparison or weren't starred in a Select node, and feed nothing else; left out of the PDF export.
+    pub fn rejected_by_picks(&self) -> HashSet<u64> {
+        let mut losers = HashSet::new();
+        for (&id, node) in &self.nodes {
+            match node.data {
+                NodeData::Compare { picked: Some(picked), .. } => {
+                    let Some((a, b)) = self.compare_pair(id) else { continue };
+                    if picked == a { losers.insert(b); } else if picked == b { losers.insert(a); }
+                }
+                NodeData::Select { chosen: Some(chosen), .. } => {
+                    let candidates = self.select_candidates(id);
+                    if candidates.contains(&chosen) { losers.extend(candidates.into_iter().filter(|&c| c != chosen)); }
+                }
+                _ => {}
+            }
+        }
+        losers.retain(|&loser| self.edges.iter().filter(|e| e.from == loser).all(|e| match self.nodes.get(&e.to).map(|n| &n.data) {
+            Some(NodeData::Compare { picked: Some(p), .. }) | Some(NodeData::Select { chosen: Some(p), .. }) => *p != loser,
+            _ => false,
+        }));
+        losers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CanvasState, Edge, Node, NodeData, NodeKind};
+
+    fn board(parents: &[u64], picked: Option<u64>) -> CanvasState {
+        let mut state = CanvasState::default();
+        for &id in parents {
+            state.nodes.insert(id, Node::new(id, Default::default(), NodeData::Concept { text: format!("text {}", id) }));
+            state.edges.push(Edge { id: 100 + id, from: id, to: 9, label: None, role: None });
+        }
+        state.nodes.insert(9, Node::new(9, Default::default(), NodeData::Compare { picked, output: String::new(), paired: false }));
+        state.recompute_outputs();
+        state
+    }
+
+    #[test]
+    fn picked_parent_becomes_the_output() {
+        let state = board(&[1, 2], Some(2));
+        assert_eq!(state.nodes[&9].data.output(), Some("text 2"));
+        assert_eq!(state.rejected_by_picks().into_iter().collect::<Vec<_>>(), vec![1]);
+        assert!(board(&[1, 2], None).nodes[&9].data.output().is_none());
+    }
+
+    #[test]
+    fn needs_exactly_two_parents() {
+        for parents in [&[1][..], &[1, 2, 3][..]] {
+            let state = board(parents, Some(1));
+            assert!(matches!(state.nodes[&9].data, NodeData::Compare { paired: false, .. }));
+            assert!(state.nodes[&9].data.output().is_none());
+        }
+        assert!(matches!(board(&[1, 2], None).nodes[&9].data, NodeData::Compare { paired: true, .. }));
+        assert_eq!(NodeKind::Compare.default_data().kind(), NodeKind::Compare);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::history::Command;
+use crate::{cached_texture, CanvasState, NodeData, StoryBoardApp};
+use eframe::egui::{self, Sense, Stroke, Vec2};
+
+/// Shot types offered in a Frame node's dropdown.
+pub const SHOT_TYPES: [&str; 7] = ["Wide", "Medium", "Close-up", "Extreme close-up", "Over the shoulder", "POV", "Insert"];
+
+pub fn default_shot() -> String { SHOT_TYPES[0].to_string() }
+
+/// Largest image drawn in a Frame node.
+const FRAME_IMAGE: Vec2 = Vec2::new(240.0, 135.0);
+/// Size of one thumbnail in the timeline strip.
+const THUMBNAIL: Vec2 = Vec2::new(128.0, 72.0);
+
+/// "Frame 3 · Close-up", the heading of a frame in the timeline and exports.
+pub fn frame_label(sequence_index: u32, shot_type: &str) -> String {
+    if shot_type.trim().is_empty() { format!("Frame {}", sequence_index) } else { format!("Frame {} · {}", sequence_index, shot_type.trim()) }
+}
+
+/// `order` with `moved` taken out and put where `target` was, as dropping one thumbnail onto another does.
+pub fn reorder(order: &[u64], moved: u64, target: u64) -> Vec<u64> {
+    let mut order = order.to_vec();
+    let (Some(from), Some(to)) = (order.iter().position(|&id| id == moved), order.iter().position(|&id| id == target)) else { return order };
+    order.remove(from);
+    order.insert(to, moved);
+    order
+}
+
+impl CanvasState {
+    /// Frame nodes in storyboard order: by sequence index, then id for frames sharing one.
+    pub fn frames_in_sequence(&self) -> Vec<u64> {
+        let mut frames: Vec<(u32, u64)> = self.nodes.values().filter_map(|n| match n.data { NodeData::Frame { sequence_index, .. } => Some((sequence_index, n.id)), _ => None }).collect();
+        frames.sort();
+        frames.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Sequence index a new frame takes: one past the last.
+    pub fn next_frame_index(&self) -> u32 {
+        self.nodes.values().filter_map(|n| match n.data { NodeData::Frame { sequence_index, .. } => Some(sequence_index), _ => None }).max().map_or(1, |i| i + 1)
+    }
+
+    /// Puts the frames among `ids` into storyboard order, leaving every other node where it is.
+    pub fn sequence_frames(&self, ids: &mut [u64]) {
+        let slots: Vec<usize> = (0..ids.len()).filter(|&i| matches!(self.nodes.get(&ids[i]).map(|n| &n.data), Some(NodeData::Frame { .. }))).collect();
+        let mut frames: Vec<u64> = slots.iter().map(|&i| ids[i]).collect();
+        frames.sort_by_key(|id| match self.nodes[id].data { NodeData::Frame { sequence_index, .. } => (sequence_index, *id), _ => (0, *id) });
+        for (slot, frame) in slots.into_iter().zip(frames) { ids[slot] = frame; }
+    }
+
+    /// The image Frame node `id` shows: its own upload, or else its Visual parent's.
+    pub fn frame_image(&self, id: u64) -> Option<&[u8]> {
+        match &self.nodes.get(&id)?.data {
+            NodeData::Frame { image: Some(bytes), .. } => Some(bytes.as_slice()),
+            NodeData::Frame { .. } => self.parent_image(id),
+            _ => None,
+        }
+    }
+
+    /// One undo step numbering the frames 1, 2, 3… in `order`; `None` when they already are.
+    pub fn renumber_frames(&self, order: &[u64]) -> Option<Command> {
+        let edits: Vec<Command> = order.iter().zip(1..).filter_map(|(&id, index)| {
+            let before = self.nodes.get(&id)?.data.clone();
+            let mut after = before.clone();
+            match &mut after { NodeData::Frame { sequence_index, .. } if *sequence_index != index => *sequence_index = index, _ => return None }
+            Some(Command::EditData { id, before, after })
+        }).collect();
+        if edits.is_empty() { None } else { Some(Command::Batch(edits)) }
+    }
+}
+
+/// Image, shot, sequence number and caption of a Frame node. Returns true when anything was edited.
+pub fn frame_ui(ui: &mut egui::Ui, state: &CanvasState, id: u64, data: &mut NodeData, focus_body: bool) -> bool {
+    let NodeData::Frame { sequence_index, shot_type, caption, image } = data else { return false };
+    let mut changed = false;
+    match state.frame_image(id).and_then(|bytes| cached_texture(ui.ctx(), id, bytes)) {
+        Some(texture) => { ui.vertical_centered(|ui| ui.add(egui::Image::new(&texture).max_size(FRAME_IMAGE).rounding(4.0))); }
+        None => { ui.weak(if image.is_some() { "⚠ Unreadable image" } else { "No image; drop one here or link a Visual node in" }); }
+    }
+    ui.horizontal(|ui| {
+        ui.label("#");
+        changed |= ui.add(egui::DragValue::new(sequence_index).range(1..=9999)).on_hover_text("Place on the timeline").changed();
+        egui::ComboBox::from_id_salt(("shot_type", id)).selected_text(shot_type.as_str()).width(110.0).show_ui(ui, |ui| {
+            for option in SHOT_TYPES { changed |= ui.selectable_value(shot_type, option.to_string(), option).changed(); }
+        });
+        if image.is_some() && ui.small_button("🗑 Image").on_hover_text("Remove the uploaded image and show the linked one").clicked() { *image = None; changed = true; }
+    });
+    let r = ui.add(egui::TextEdit::multiline(caption).hint_text("Caption: what happens in this shot").desired_rows(2).desired_width(f32::INFINITY));
+    if focus_body { r.request_focus(); }
+    let parent = state.parent_output(id);
+    if ui.add_enabled(parent.is_some(), egui::Button::new("🔗 Caption from parent")).clicked() { if let Some(text) = parent { *caption = text; changed = true; } }
+    changed | r.changed()
+}
+
+impl StoryBoardApp {
+    /// Bottom strip of every Frame node's thumbnail in sequence order. Dropping a thumbnail onto another moves it there and
+    /// renumbers the frames; clicking one selects and centers its node.
+    pub(crate) fn draw_timeline(&mut self, ctx: &egui::Context) {
+        let order = self.state.frames_in_sequence();
+        let (mut focus, mut moved) = (None, None);
+        egui::TopBottomPanel::bottom("timeline").resizable(false).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong("🎞 Timeline");
+                ui.weak(if order.is_empty() { "Add Frame nodes to lay out the storyboard".to_string() } else { format!("{} frames · drag to reorder", order.len()) });
+            });
+            if order.is_empty() { return; }
+            egui::ScrollArea::horizontal().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for &id in &order {
+                        let node = &self.state.nodes[&id];
+                        let NodeData::Frame { sequence_index, shot_type, caption, .. } = &node.data else { continue };
+                        let card = ui.dnd_drag_source(egui::Id::new(("timeline_frame", id)), id, |ui| {
+                            ui.vertical(|ui| {
+                                ui.set_width(THUMBNAIL.x);
+                                let (rect, response) = ui.allocate_exact_size(THUMBNAIL, Sense::click());
+                                match self.state.frame_image(id).and_then(|bytes| cached_texture(ui.ctx(), id, bytes)) {
+                                    Some(texture) => { egui::Image::new(&texture).fit_to_exact_size(THUMBNAIL).rounding(3.0).paint_at(ui, rect); }
+                                    None => { ui.painter().rect_stroke(rect, 3.0, Stroke::new(1.0, ui.visuals().weak_text_color())); }
+                                }
+                                if node.selected { ui.painter().rect_stroke(rect, 3.0, Stroke::new(2.0, self.settings.theme.palette().selection)); }
+                                ui.small(frame_label(*sequence_index, shot_type));
+                                ui.weak(crate::truncate(&caption.replace('\n', " "), 40));
+                                response
+                            }).inner
+                        });
+                        if card.inner.on_hover_text("Click to select and center; drag onto another frame to move it there").clicked() { focus = Some(id); }
+                        if let Some(dragged) = card.response.dnd_hover_payload::<u64>().filter(|d| **d != id) {
+                            let side = if order.iter().position(|&o| o == *dragged) < order.iter().position(|&o| o == id) { card.response.rect.right() } else { card.response.rect.left() } - 3.0;
+                            ui.painter().vline(side, card.response.rect.y_range(), Stroke::new(2.0, self.settings.theme.palette().selection));
+                        }
+                        if let Some(dragged) = card.response.dnd_release_payload::<u64>() { moved = Some((*dragged, id)); }
+                    }
+                });
+            });
+        });
+        if let Some((dragged, target)) = moved {
+            if let Some(command) = self.state.renumber_frames(&reorder(&order, dragged, target)) { self.state.execute(command); }
+        }
+        if let Some(id) = focus { self.state.select_only(id); self.focus_camera_on(id); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Edge, Node};
+
+    fn frame(index: u32) -> NodeData {
+        NodeData::Frame { sequence_index: index, shot_type: default_shot(), caption: format!("shot {}", index), image: None }
+    }
+
+    #[test]
+    fn reordering_renumbers_in_one_undo_step() {
+        let mut state = CanvasState::default();
+        for (id, index) in [(1, 2), (2, 1), (3, 3)] { state.nodes.insert(id, Node::new(id, Default::default(), frame(index))); }
+        state.nodes.insert(4, Node::new(4, Default::default(), NodeData::Concept { text: "x".to_string() }));
+        assert_eq!(state.frames_in_sequence(), vec![2, 1, 3]);
+        assert_eq!(state.next_frame_index(), 4);
+        assert_eq!(reorder(&[2, 1, 3], 2, 3), vec![1, 3, 2]);
+        assert_eq!(reorder(&[2, 1, 3], 3, 2), vec![3, 2, 1]);
+        let command = state.renumber_frames(&[1, 3, 2]).unwrap();
+        state.execute(command);
+        assert_eq!(state.frames_in_sequence(), vec![1, 3, 2]);
+        assert!(state.renumber_frames(&[1, 3, 2]).is_none());
+        state.undo();
+        assert_eq!(state.frames_in_sequence(), vec![2, 1, 3]);
+        let mut ids = vec![4, 3, 1, 2];
+        state.sequence_frames(&mut ids);
+        assert_eq!(ids, vec![4, 2, 1, 3], "frames take the slots frames had, in order");
+    }
+
+    #[test]
+    fn a_frame_shows_its_visual_parent_until_an_image_is_uploaded() {
+        let mut state = CanvasState::default();
+        let mut visual = crate::NodeKind::Visual.default_data();
+        if let NodeData::Visual { image, .. } = &mut visual { *image = Some(vec![1]); }
+        state.nodes.insert(1, Node::new(1, Default::default(), visual));
+        state.nodes.insert(2, Node::new(2, Default::default(), frame(1)));
+        assert_eq!(state.frame_image(2), None);
+        state.edges.push(Edge { id: 3, from: 1, to: 2, label: None, role: None });
+        assert_eq!(state.frame_image(2), Some(&[1u8][..]));
+        if let NodeData::Frame { image, .. } = &mut state.nodes.get_mut(&2).unwrap().data { *image = Some(vec![9]); }
+        assert_eq!(state.frame_image(2), Some(&[9u8][..]));
+        assert_eq!(frame_label(2, " Close-up "), "Frame 2 · Close-up");
+    }
+}
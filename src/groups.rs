@@ -0,0 +1,66 @@
+use crate::history::Command;
+use crate::CanvasState;
+use eframe::egui::{Pos2, Rect, Vec2};
+
+/// World-space height of a group's title bar, which is also its drag handle.
+pub const GROUP_HEADER: f32 = 32.0;
+pub const MIN_GROUP_SIZE: Vec2 = Vec2::new(200.0, 120.0);
+/// Space left around the selection when a group is created from it.
+const GROUP_PADDING: f32 = 30.0;
+
+/// A labeled frame drawn behind the nodes. Nodes whose centers lie inside it move with it.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Group {
+    pub id: u64,
+    pub title: String,
+    pub rect: Rect,
+}
+
+/// A group being dragged by its title bar, with its rect and its members' positions when the drag began.
+#[derive(Clone, Debug)]
+pub struct GroupDrag {
+    pub id: u64,
+    pub before: Rect,
+    pub members: Vec<(u64, Pos2)>,
+}
+
+impl Group {
+    pub fn header(&self) -> Rect { Rect::from_min_size(self.rect.min, Vec2::new(self.rect.width(), GROUP_HEADER)) }
+}
+
+impl CanvasState {
+    /// Wraps the given nodes in a new group, or drops an empty one at the camera center when `ids` is empty.
+    pub fn add_group(&mut self, ids: &[u64]) -> u64 {
+        let rect = match ids.iter().filter_map(|id| self.nodes.get(id)).map(|n| n.bounds()).reduce(|a, b| a.union(b)) {
+            Some(bounds) => Rect::from_min_max(bounds.min - Vec2::new(GROUP_PADDING, GROUP_PADDING + GROUP_HEADER), bounds.max + Vec2::splat(GROUP_PADDING)),
+            None => Rect::from_center_size(self.camera_offset.to_pos2(), Vec2::new(500.0, 350.0)),
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+        self.execute(Command::AddGroup(Group { id, title: format!("Group {}", id), rect }));
+        id
+    }
+
+    /// Deletes the frame only; its member nodes stay where they are.
+    pub fn remove_group(&mut self, id: u64) {
+        if let Some(index) = self.groups.iter().position(|g| g.id == id) {
+            let group = self.groups[index].clone();
+            self.execute(Command::RemoveGroup(index, group));
+        }
+    }
+
+    pub fn set_group_title(&mut self, id: u64, title: String) {
+        let Some(before) = self.groups.iter().find(|g| g.id == id).map(|g| g.title.clone()) else { return };
+        if before != title { self.execute(Command::GroupTitle { id, before, after: title }); }
+    }
+
+    pub fn group_members(&self, id: u64) -> Vec<u64> {
+        let Some(group) = self.groups.iter().find(|g| g.id == id) else { return Vec::new() };
+        self.nodes.values().filter(|n| group.rect.contains(n.bounds().center())).map(|n| n.id).collect()
+    }
+
+    /// Topmost group whose title bar contains the world position.
+    pub fn group_header_at(&self, world: Pos2) -> Option<u64> {
+        self.groups.iter().rev().find(|g| g.header().contains(world)).map(|g| g.id)
+    }
+}
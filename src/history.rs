@@ -0,0 +1,298 @@
+use crate::annotations::Annotation;
+use crate::branch::BranchRole;
+use crate::groups::Group;
+use crate::pipeline::creates_cycle;
+use crate::{CanvasState, Edge, Node, NodeData};
+use eframe::egui::{Pos2, Rect, Vec2};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+#[cfg(target_arch = "wasm32")]
+use web_time::{Duration, Instant};
+
+/// Maximum number of undo steps kept per session.
+const MAX_DEPTH: usize = 100;
+/// Consecutive text edits to the same node within this window collapse into one undo step.
+const EDIT_COALESCE: Duration = Duration::from_millis(1000);
+
+/// A reversible mutation of the canvas graph.
+#[derive(Clone, Debug)]
+pub enum Command {
+    AddNode(Node),
+    /// Removed nodes plus every edge that touched them, with the edge's original index in `CanvasState.edges`.
+    RemoveNodes { nodes: Vec<Node>, edges: Vec<(usize, Edge)> },
+    AddEdge(Edge),
+    /// An edge removed from the given index in `CanvasState.edges`.
+    RemoveEdge(usize, Edge),
+    MoveNodes(Vec<(u64, Pos2, Pos2)>),
+    ResizeNode { id: u64, before: Vec2, after: Vec2 },
+    EdgeLabel { id: u64, before: Option<String>, after: Option<String> },
+    EdgeRole { id: u64, before: Option<BranchRole>, after: Option<BranchRole> },
+    NodeTitle { id: u64, before: Option<String>, after: Option<String> },
+    EditData { id: u64, before: NodeData, after: NodeData },
+    AddGroup(Group),
+    /// A group removed from the given index in `CanvasState.groups`.
+    RemoveGroup(usize, Group),
+    GroupRect { id: u64, before: Rect, after: Rect },
+    GroupTitle { id: u64, before: String, after: String },
+    AddAnnotation(Annotation),
+    /// A note removed from the given index in `CanvasState.annotations`.
+    RemoveAnnotation(usize, Annotation),
+    /// Any change to a note: moved, resized, retyped or recolored.
+    EditAnnotation { before: Annotation, after: Annotation },
+    /// Several commands that undo and redo together.
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    fn apply(&self, state: &mut CanvasState) {
+        match self {
+            Self::AddNode(node) => { state.nodes.insert(node.id, node.clone()); }
+            Self::RemoveNodes { nodes, edges } => {
+                for node in nodes { state.nodes.remove(&node.id); }
+                let removed: Vec<u64> = edges.iter().map(|(_, e)| e.id).collect();
+                state.edges.retain(|e| !removed.contains(&e.id));
+            }
+            Self::AddEdge(edge) => state.edges.push(edge.clone()),
+            Self::RemoveEdge(_, edge) => state.edges.retain(|e| e.id != edge.id),
+            Self::MoveNodes(moves) => { for &(id, _, to) in moves { if let Some(n) = state.nodes.get_mut(&id) { n.position = to; n.velocity = Vec2::ZERO; } } }
+            Self::ResizeNode { id, after, .. } => { if let Some(n) = state.nodes.get_mut(id) { n.size = *after; } }
+            Self::EdgeLabel { id, after, .. } => { if let Some(e) = state.edges.iter_mut().find(|e| e.id == *id) { e.label = after.clone(); } }
+            Self::EdgeRole { id, after, .. } => { if let Some(e) = state.edges.iter_mut().find(|e| e.id == *id) { e.role = *after; } }
+            Self::NodeTitle { id, after, .. } => { if let Some(n) = state.nodes.get_mut(id) { n.title = after.clone(); } }
+            Self::EditData { id, before, after } => { if let Some(n) = state.nodes.get_mut(id) { n.data = restored(&n.data, after, before); } }
+            Self::AddGroup(group) => state.groups.push(group.clone()),
+            Self::RemoveGroup(_, group) => state.groups.retain(|g| g.id != group.id),
+            Self::GroupRect { id, after, .. } => { if let Some(g) = state.groups.iter_mut().find(|g| g.id == *id) { g.rect = *after; } }
+            Self::GroupTitle { id, after, .. } => { if let Some(g) = state.groups.iter_mut().find(|g| g.id == *id) { g.title = after.clone(); } }
+            Self::AddAnnotation(note) => state.annotations.push(note.clone()),
+            Self::RemoveAnnotation(_, note) => state.annotations.retain(|a| a.id != note.id),
+            Self::EditAnnotation { after, .. } => { if let Some(a) = state.annotations.iter_mut().find(|a| a.id == after.id) { *a = after.clone(); } }
+            Self::Batch(cmds) => { for cmd in cmds { cmd.apply(state); } }
+        }
+    }
+
+    fn revert(&self, state: &mut CanvasState) {
+        match self {
+            Self::AddNode(node) => { state.nodes.remove(&node.id); }
+            Self::RemoveNodes { nodes, edges } => {
+                for node in nodes { state.nodes.insert(node.id, node.clone()); }
+                for (index, edge) in edges { state.edges.insert((*index).min(state.edges.len()), edge.clone()); }
+            }
+            Self::AddEdge(edge) => state.edges.retain(|e| e.id != edge.id),
+            Self::RemoveEdge(index, edge) => state.edges.insert((*index).min(state.edges.len()), edge.clone()),
+            Self::MoveNodes(moves) => { for &(id, from, _) in moves { if let Some(n) = state.nodes.get_mut(&id) { n.position = from; n.velocity = Vec2::ZERO; } } }
+            Self::ResizeNode { id, before, .. } => { if let Some(n) = state.nodes.get_mut(id) { n.size = *before; } }
+            Self::EdgeLabel { id, before, .. } => { if let Some(e) = state.edges.iter_mut().find(|e| e.id == *id) { e.label = before.clone(); } }
+            Self::EdgeRole { id, before, .. } => { if let Some(e) = state.edges.iter_mut().find(|e| e.id == *id) { e.role = *before; } }
+            Self::NodeTitle { id, before, .. } => { if let Some(n) = state.nodes.get_mut(id) { n.title = before.clone(); } }
+            Self::EditData { id, before, after } => { if let Some(n) = state.nodes.get_mut(id) { n.data = restored(&n.data, before, after); } }
+            Self::AddGroup(group) => state.groups.retain(|g| g.id != group.id),
+            Self::RemoveGroup(index, group) => state.groups.insert((*index).min(state.groups.len()), group.clone()),
+            Self::GroupRect { id, before, .. } => { if let Some(g) = state.groups.iter_mut().find(|g| g.id == *id) { g.rect = *before; } }
+            Self::GroupTitle { id, before, .. } => { if let Some(g) = state.groups.iter_mut().find(|g| g.id == *id) { g.title = before.clone(); } }
+            Self::AddAnnotation(note) => state.annotations.retain(|a| a.id != note.id),
+            Self::RemoveAnnotation(index, note) => state.annotations.insert((*index).min(state.annotations.len()), note.clone()),
+            Self::EditAnnotation { before, .. } => { if let Some(a) = state.annotations.iter_mut().find(|a| a.id == before.id) { *a = before.clone(); } }
+            Self::Batch(cmds) => { for cmd in cmds.iter().rev() { cmd.revert(state); } }
+        }
+    }
+}
+
+/// `target` as undoing or redoing an edit brings it back, keeping the live node's loading flag and, unless the edit was to the
+/// result itself (`other` being the other side of it), the live result: a request answered since the edit stays answered.
+fn restored(live: &NodeData, target: &NodeData, other: &NodeData) -> NodeData {
+    let mut data = target.clone();
+    if target.same_result(other) { data.adopt_result(live); }
+    if let Some(flag) = data.loading_flag() { *flag = live.is_loading(); }
+    data
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    last_edit: Option<(u64, Instant)>,
+}
+
+impl History {
+    pub fn can_undo(&self) -> bool { !self.undo.is_empty() }
+    pub fn can_redo(&self) -> bool { !self.redo.is_empty() }
+
+    /// Records a command that has already been applied to the state.
+    pub fn push(&mut self, cmd: Command) {
+        self.redo.clear();
+        if let Command::EditData { id, after, .. } = &cmd {
+            let now = Instant::now();
+            let coalesce = matches!(self.last_edit, Some((last_id, at)) if last_id == *id && now - at < EDIT_COALESCE);
+            self.last_edit = Some((*id, now));
+            if coalesce {
+                if let Some(Command::EditData { id: top_id, after: top_after, .. }) = self.undo.last_mut() {
+                    if top_id == id { *top_after = after.clone(); return; }
+                }
+            }
+        } else {
+            self.last_edit = None;
+        }
+        self.undo.push(cmd);
+        if self.undo.len() > MAX_DEPTH { self.undo.remove(0); }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_edit = None;
+    }
+}
+
+impl CanvasState {
+    /// Applies `cmd` and makes it undoable.
+    pub fn execute(&mut self, cmd: Command) {
+        cmd.apply(self);
+        self.graph_version += 1;
+        self.history.push(cmd);
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(cmd) = self.history.undo.pop() {
+            cmd.revert(self);
+            self.graph_version += 1;
+            self.history.redo.push(cmd);
+            self.history.last_edit = None;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(cmd) = self.history.redo.pop() {
+            cmd.apply(self);
+            self.graph_version += 1;
+            self.history.undo.push(cmd);
+            self.history.last_edit = None;
+        }
+    }
+
+    /// Removes the given nodes and every edge touching them as a single undo step.
+    pub fn remove_nodes(&mut self, ids: &[u64]) {
+        let nodes: Vec<Node> = ids.iter().filter_map(|id| self.nodes.get(id).cloned()).collect();
+        if nodes.is_empty() { return; }
+        let edges: Vec<(usize, Edge)> = self.edges.iter().enumerate().filter(|(_, e)| ids.contains(&e.from) || ids.contains(&e.to)).map(|(i, e)| (i, e.clone())).collect();
+        self.execute(Command::RemoveNodes { nodes, edges });
+    }
+
+    /// Inserts a copy of node `id` beside the original and makes it the only selected node.
+    /// Results and images are kept; an in-flight request is not, since its response targets the original id.
+    pub fn duplicate_node(&mut self, id: u64) -> Option<u64> {
+        let original = self.nodes.get(&id)?;
+        let mut copy = original.clone();
+        copy.id = self.next_id;
+        copy.position += Vec2::new(40.0, 40.0);
+        copy.velocity = Vec2::ZERO;
+        if let Some(flag) = copy.data.loading_flag() { *flag = false; }
+        self.next_id += 1;
+        for node in self.nodes.values_mut() { node.selected = false; }
+        copy.selected = true;
+        let new_id = copy.id;
+        self.execute(Command::AddNode(copy));
+        Some(new_id)
+    }
+
+    /// Removes every edge touching node `id` as a single undo step.
+    pub fn disconnect_node(&mut self, id: u64) {
+        self.disconnect_nodes(&[id]);
+    }
+
+    pub fn remove_edge(&mut self, id: u64) {
+        if let Some(index) = self.edges.iter().position(|e| e.id == id) {
+            let edge = self.edges[index].clone();
+            self.execute(Command::RemoveEdge(index, edge));
+        }
+        if self.selected_edge == Some(id) { self.selected_edge = None; }
+    }
+
+    pub fn set_edge_label(&mut self, id: u64, label: Option<String>) {
+        let Some(before) = self.edges.iter().find(|e| e.id == id).map(|e| e.label.clone()) else { return };
+        if before != label { self.execute(Command::EdgeLabel { id, before, after: label }); }
+    }
+
+    pub fn set_node_title(&mut self, id: u64, title: Option<String>) {
+        let Some(before) = self.nodes.get(&id).map(|n| n.title.clone()) else { return };
+        if before != title { self.execute(Command::NodeTitle { id, before, after: title }); }
+    }
+
+    /// Returns false when the edge was refused: incompatible ports or a cycle.
+    pub fn add_edge(&mut self, from: u64, to: u64) -> bool {
+        self.add_edges(&[(from, to)]).is_empty()
+    }
+
+    /// Adds one edge per `(from, to)` pair as a single undo step, skipping and returning the pairs whose ports don't match or that would create a cycle.
+    pub fn add_edges(&mut self, pairs: &[(u64, u64)]) -> Vec<(u64, u64)> {
+        let (cmd, rejected) = self.edge_commands(pairs);
+        if let Some(cmd) = cmd { self.execute(cmd); }
+        rejected
+    }
+
+    /// The not yet applied command `add_edges` would run, for callers folding it into a bigger undo step, and the pairs refused.
+    pub fn edge_commands(&mut self, pairs: &[(u64, u64)]) -> (Option<Command>, Vec<(u64, u64)>) {
+        let mut accepted: Vec<(u64, u64)> = Vec::new();
+        let mut rejected = Vec::new();
+        for &(from, to) in pairs {
+            if self.link_mismatch(from, to).is_some() || creates_cycle(self.edges.iter().map(|e| (e.from, e.to)).chain(accepted.iter().copied()), from, to) { rejected.push((from, to)); } else { accepted.push((from, to)); }
+        }
+        let mut cmds: Vec<Command> = Vec::new();
+        let mut roles: Vec<(u64, BranchRole)> = Vec::new();
+        for &(from, to) in &accepted {
+            // Edges out of a Branch node take "true", then "false", counting those added earlier in this batch.
+            let taken: Vec<BranchRole> = roles.iter().filter(|(f, _)| *f == from).map(|&(_, r)| r).collect();
+            let role = self.next_branch_role(from, &taken);
+            if let Some(role) = role { roles.push((from, role)); }
+            cmds.push(Command::AddEdge(Edge { id: self.next_id, from, to, label: None, role }));
+            self.next_id += 1;
+        }
+        let cmd = match cmds.len() {
+            0 => None,
+            1 => Some(cmds.remove(0)),
+            _ => Some(Command::Batch(cmds)),
+        };
+        (cmd, rejected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ai(prompt: &str, result: Option<&str>) -> NodeData {
+        NodeData::AgnosticAI { model: String::new(), prompt: prompt.to_string(), result: result.map(str::to_string), is_loading: false, auto_run: false, provider: Default::default() }
+    }
+
+    #[test]
+    fn undoing_an_edit_keeps_the_answer_that_arrived_since() {
+        let mut state = CanvasState::default();
+        state.nodes.insert(1, Node::new(1, Default::default(), ai("a", None)));
+        state.execute(Command::EditData { id: 1, before: ai("a", None), after: ai("b", None) });
+        state.nodes.get_mut(&1).unwrap().data = ai("b", Some("answer"));
+        state.undo();
+        assert_eq!(state.nodes[&1].data, ai("a", Some("answer")));
+        if let Some(flag) = state.nodes.get_mut(&1).unwrap().data.loading_flag() { *flag = true; }
+        state.redo();
+        assert!(state.nodes[&1].data.is_loading(), "a request in flight stays in flight");
+        assert_eq!(state.nodes[&1].data.primary_text(), Some("b"));
+        state.clear_results(&[1]);
+        assert_eq!(state.nodes[&1].data.output(), None);
+        state.undo();
+        assert_eq!(state.nodes[&1].data.output(), Some("answer"), "undoing a clear brings the result back");
+    }
+
+    #[test]
+    fn a_drop_that_links_undoes_in_one_step() {
+        let mut state = CanvasState::graph(&[(1, crate::NodeKind::Concept), (2, crate::NodeKind::AgnosticAI)], &[]);
+        let (link, rejected) = state.edge_commands(&[(1, 2), (2, 1)]);
+        assert!(state.edges.is_empty(), "nothing is linked until the command runs");
+        assert_eq!(rejected, vec![(2, 1)]);
+        state.execute(Command::Batch(vec![Command::MoveNodes(vec![(1, Pos2::ZERO, Pos2::new(50.0, 0.0))]), link.unwrap()]));
+        assert_eq!((state.edges.len(), state.nodes[&1].position), (1, Pos2::new(50.0, 0.0)));
+        state.undo();
+        assert_eq!((state.edges.len(), state.nodes[&1].position), (0, Pos2::ZERO));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::history::Command;
+use crate::CanvasState;
+use eframe::egui::{Pos2, Rect, Vec2};
+use std::collections::HashMap;
+
+/// Horizontal gap between layer columns, vertical gap between stacked nodes, and gap between component blocks.
+const LAYER_GAP: f32 = 120.0;
+const NODE_GAP: f32 = 40.0;
+const COMPONENT_GAP: f32 = 200.0;
+/// Fraction of the remaining distance covered per frame while animating towards the layout.
+const EASE: f32 = 0.25;
+
+/// Node positions being eased towards a computed layout, committed as one undo step when they arrive.
+pub struct LayoutAnimation {
+    targets: HashMap<u64, Pos2>,
+    origins: Vec<(u64, Pos2)>,
+}
+
+impl LayoutAnimation {
+    /// Stops animating `id`, e.g. because the user grabbed it.
+    pub fn release(&mut self, id: u64) {
+        self.targets.remove(&id);
+        self.origins.retain(|&(n, _)| n != id);
+    }
+
+    /// Advances one frame. Returns false once every node has arrived.
+    pub fn step(&mut self, state: &mut CanvasState) -> bool {
+        let mut moving = false;
+        for (id, target) in &self.targets {
+            let Some(node) = state.nodes.get_mut(id) else { continue };
+            node.velocity = Vec2::ZERO;
+            if node.position.distance(*target) < 0.5 { node.position = *target; } else { node.position += (*target - node.position) * EASE; moving = true; }
+        }
+        if !moving {
+            let moves: Vec<(u64, Pos2, Pos2)> = self.origins.iter().filter_map(|&(id, from)| state.nodes.get(&id).map(|n| (id, from, n.position))).filter(|(_, from, to)| from != to).collect();
+            if !moves.is_empty() { state.history.push(Command::MoveNodes(moves)); }
+        }
+        moving
+    }
+}
+
+impl CanvasState {
+    /// Left-to-right layered layout of the pipeline: one column per topological layer, one block of columns per connected component.
+    /// Nodes on a cycle are placed one layer after their already-placed parents.
+    pub fn layered_layout(&self) -> HashMap<u64, Pos2> {
+        let mut ids: Vec<u64> = self.nodes.keys().copied().collect();
+        ids.sort();
+        let edges: Vec<(u64, u64)> = self.edges.iter().filter(|e| e.from != e.to && self.nodes.contains_key(&e.from) && self.nodes.contains_key(&e.to)).map(|e| (e.from, e.to)).collect();
+
+        let mut parent: HashMap<u64, u64> = ids.iter().map(|&id| (id, id)).collect();
+        fn root(parent: &mut HashMap<u64, u64>, mut id: u64) -> u64 {
+            while parent[&id] != id { let up = parent[&parent[&id]]; parent.insert(id, up); id = up; }
+            id
+        }
+        for &(a, b) in &edges { let (ra, rb) = (root(&mut parent, a), root(&mut parent, b)); if ra != rb { parent.insert(ra.max(rb), ra.min(rb)); } }
+        let mut components: Vec<Vec<u64>> = Vec::new();
+        let mut component_of: HashMap<u64, usize> = HashMap::new();
+        for &id in &ids {
+            let r = root(&mut parent, id);
+            let index = *component_of.entry(r).or_insert_with(|| { components.push(Vec::new()); components.len() - 1 });
+            components[index].push(id);
+        }
+
+        let origin = self.nodes.values().map(|n| n.position).reduce(|a, b| a.min(b)).unwrap_or(Pos2::ZERO);
+        let mut positions = HashMap::new();
+        let mut x = origin.x;
+        for component in components {
+            let parents = |id: u64| edges.iter().filter(move |&&(_, to)| to == id).map(|&(from, _)| from);
+            let mut in_degree: HashMap<u64, usize> = component.iter().map(|&id| (id, parents(id).count())).collect();
+            let mut layer: HashMap<u64, usize> = HashMap::new();
+            let mut ready: Vec<u64> = component.iter().copied().filter(|id| in_degree[id] == 0).collect();
+            loop {
+                while let Some(id) = ready.pop() {
+                    layer.insert(id, parents(id).filter_map(|p| layer.get(&p)).map(|l| l + 1).max().unwrap_or(0));
+                    for &(_, child) in edges.iter().filter(|&&(from, _)| from == id) {
+                        if layer.contains_key(&child) || ready.contains(&child) { continue; }
+                        let d = in_degree.get_mut(&child).unwrap();
+                        *d -= 1;
+                        if *d == 0 { ready.push(child); }
+                    }
+                }
+                // Whatever is left sits on a cycle: break it at the lowest id.
+                let Some(&stuck) = component.iter().find(|id| !layer.contains_key(id) && !ready.contains(id)) else { break };
+                in_degree.insert(stuck, 0);
+                ready.push(stuck);
+            }
+
+            let depth = layer.values().max().map_or(0, |l| l + 1);
+            let mut columns: Vec<Vec<u64>> = vec![Vec::new(); depth];
+            for &id in &component { columns[layer[&id]].push(id); }
+            for l in 1..depth {
+                // Order each column by the average row of its parents to reduce crossings.
+                let prev = columns[l - 1].clone();
+                let row = |id: u64| { let rows: Vec<f32> = parents(id).filter_map(|p| prev.iter().position(|&q| q == p)).map(|r| r as f32).collect(); if rows.is_empty() { f32::MAX } else { rows.iter().sum::<f32>() / rows.len() as f32 } };
+                columns[l].sort_by(|&a, &b| row(a).total_cmp(&row(b)).then(a.cmp(&b)));
+            }
+            for column in &columns {
+                let mut y = origin.y;
+                let width = column.iter().map(|id| self.nodes[id].size.x).fold(0.0, f32::max);
+                for id in column {
+                    positions.insert(*id, Pos2::new(x, y));
+                    y += self.nodes[id].size.y + NODE_GAP;
+                }
+                x += width + LAYER_GAP;
+            }
+            x += COMPONENT_GAP - LAYER_GAP;
+        }
+        positions
+    }
+
+    /// Starts easing every node towards `layered_layout`.
+    pub fn auto_layout(&mut self) -> LayoutAnimation {
+        let targets = self.layered_layout();
+        let origins = targets.keys().map(|id| (*id, self.nodes[id].position)).collect();
+        for node in self.nodes.values_mut() { node.velocity = Vec2::ZERO; }
+        LayoutAnimation { targets, origins }
+    }
+}
+
+/// Arrange operations on a multi-selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arrange { AlignLeft, AlignVerticalCenters, DistributeHorizontally }
+
+impl Arrange {
+    pub const ALL: [Arrange; 3] = [Self::AlignLeft, Self::AlignVerticalCenters, Self::DistributeHorizontally];
+
+    pub fn label(self) -> &'static str {
+        match self { Self::AlignLeft => "⇤ Align left edges", Self::AlignVerticalCenters => "↔ Align vertical centers", Self::DistributeHorizontally => "⇹ Distribute horizontally" }
+    }
+}
+
+impl CanvasState {
+    /// Applies `op` to the given nodes as one undo step. Needs at least two nodes.
+    pub fn arrange(&mut self, ids: &[u64], op: Arrange) {
+        let mut nodes: Vec<(u64, Pos2, Vec2)> = ids.iter().filter_map(|id| self.nodes.get(id)).map(|n| (n.id, n.position, n.size)).collect();
+        if nodes.len() < 2 { return; }
+        let targets: Vec<(u64, Pos2)> = match op {
+            Arrange::AlignLeft => {
+                let left = nodes.iter().map(|(_, p, _)| p.x).fold(f32::INFINITY, f32::min);
+                nodes.iter().map(|&(id, p, _)| (id, Pos2::new(left, p.y))).collect()
+            }
+            Arrange::AlignVerticalCenters => {
+                let top = nodes.iter().map(|(_, p, _)| p.y).fold(f32::INFINITY, f32::min);
+                let bottom = nodes.iter().map(|(_, p, s)| p.y + s.y).fold(f32::NEG_INFINITY, f32::max);
+                let center = (top + bottom) / 2.0;
+                nodes.iter().map(|&(id, p, s)| (id, Pos2::new(p.x, center - s.y / 2.0))).collect()
+            }
+            Arrange::DistributeHorizontally => {
+                // Keep the current left-to-right order and the outermost nodes in place.
+                nodes.sort_by(|a, b| a.1.x.total_cmp(&b.1.x).then(a.0.cmp(&b.0)));
+                let (first, last) = (nodes[0], nodes[nodes.len() - 1]);
+                let span = last.1.x + last.2.x - first.1.x;
+                let gap = (span - nodes.iter().map(|(_, _, s)| s.x).sum::<f32>()) / (nodes.len() - 1) as f32;
+                let mut x = first.1.x;
+                nodes.iter().map(|&(id, p, s)| { let target = Pos2::new(x, p.y); x += s.x + gap; (id, target) }).collect()
+            }
+        };
+        let moves: Vec<(u64, Pos2, Pos2)> = targets.into_iter().filter_map(|(id, to)| self.nodes.get(&id).map(|n| (id, n.position, to))).filter(|(_, from, to)| from != to).collect();
+        // Repulsion would slowly push the row apart again, so arranged nodes get pinned.
+        for (id, ..) in &nodes { if let Some(n) = self.nodes.get_mut(id) { n.pinned = true; } }
+        if !moves.is_empty() { self.execute(Command::MoveNodes(moves)); }
+    }
+}
+
+impl CanvasState {
+    /// How far `id` should shift to line its left/center/right or top/center/bottom up with a visible node that isn't
+    /// being moved, if one is within `threshold`, plus world-space guide segments through the matched lines.
+    pub fn alignment_snap(&self, id: u64, moving: impl Fn(u64) -> bool, visible: Rect, threshold: f32) -> (Vec2, Vec<[Pos2; 2]>) {
+        let Some(a) = self.nodes.get(&id).map(|n| n.bounds()) else { return (Vec2::ZERO, Vec::new()) };
+        let others: Vec<Rect> = self.nodes.values().filter(|n| !moving(n.id)).map(|n| n.bounds()).filter(|b| visible.intersects(*b)).collect();
+        let xs = |r: Rect| [r.left(), r.center().x, r.right()];
+        let ys = |r: Rect| [r.top(), r.center().y, r.bottom()];
+        // Closest (distance, shift, other rect) on one axis.
+        let best = |lines: &dyn Fn(Rect) -> [f32; 3]| others.iter().flat_map(|b| lines(a).into_iter().flat_map(move |p| lines(*b).into_iter().map(move |q| ((q - p).abs(), q - p, *b)))).filter(|(d, ..)| *d <= threshold).min_by(|x, y| x.0.total_cmp(&y.0));
+        let (best_x, best_y) = (best(&xs), best(&ys));
+        let shift = Vec2::new(best_x.map_or(0.0, |(_, dx, _)| dx), best_y.map_or(0.0, |(_, dy, _)| dy));
+        let a = a.translate(shift);
+        let mut guides = Vec::new();
+        if let Some((_, _, b)) = best_x {
+            for x in xs(a).into_iter().filter(|x| xs(b).iter().any(|q| (q - x).abs() < 0.5)) { guides.push([Pos2::new(x, a.top().min(b.top())), Pos2::new(x, a.bottom().max(b.bottom()))]); }
+        }
+        if let Some((_, _, b)) = best_y {
+            for y in ys(a).into_iter().filter(|y| ys(b).iter().any(|q| (q - y).abs() < 0.5)) { guides.push([Pos2::new(a.left().min(b.left()), y), Pos2::new(a.right().max(b.right()), y)]); }
+        }
+        (shift, guides)
+    }
+}
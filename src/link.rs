@@ -0,0 +1,25 @@
+use crate::{truncate, CanvasState, Node};
+use eframe::egui;
+
+/// Characters of each parent's output shown in the Link menu.
+const LINK_PREVIEW_CHARS: usize = 50;
+
+/// The "🔗 Link" button of Research, AI and Visual nodes; returns the text to pull in. With one parent it is a single click
+/// (`parent_output`); when several parents have text it opens a menu to take one of them, or all of them combined.
+pub fn link_parent_button(ui: &mut egui::Ui, state: &CanvasState, id: u64, named: &dyn Fn(egui::Response, egui::WidgetType, &str) -> egui::Response) -> Option<String> {
+    let candidates: Vec<&Node> = state.text_parents(id).iter().map(|p| &state.nodes[p]).filter(|n| n.data.output().is_some()).collect();
+    if candidates.len() < 2 {
+        return if named(ui.button("🔗 Link"), egui::WidgetType::Button, "Link parent button").clicked() { state.parent_output(id) } else { None };
+    }
+    let mut picked = None;
+    let menu = ui.menu_button("🔗 Link ▾", |ui| {
+        if ui.button("All (combined)").clicked() { picked = state.parent_output(id); ui.close_menu(); }
+        ui.separator();
+        for parent in candidates {
+            let text = parent.data.output().unwrap_or_default();
+            if ui.button(format!("{} #{} · {}", parent.data.kind().icon(), parent.id, truncate(&text.replace('\n', " "), LINK_PREVIEW_CHARS))).clicked() { picked = Some(text.to_string()); ui.close_menu(); }
+        }
+    });
+    named(menu.response, egui::WidgetType::Button, "Link parent menu").on_hover_text("Choose which parent to pull text from");
+    picked
+}
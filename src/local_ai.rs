@@ -0,0 +1,109 @@
+use crate::{AppMessage, StoryBoardApp};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// Where an Agnostic AI node's prompt goes: OpenRouter, or the Ollama running on the server's machine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Provider {
+    #[default]
+    OpenRouter,
+    Ollama,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 2] = [Self::OpenRouter, Self::Ollama];
+
+    pub fn label(self) -> &'static str {
+        match self { Self::OpenRouter => "☁ OpenRouter", Self::Ollama => "🖥 Ollama (local)" }
+    }
+
+    pub fn endpoint(self) -> &'static str {
+        match self { Self::OpenRouter => "/api/agnostic-ai", Self::Ollama => "/api/local-ai" }
+    }
+
+    /// Model a node switched to this provider starts on.
+    pub fn default_model(self) -> &'static str {
+        match self { Self::OpenRouter => "google/gemini-flash-1.5", Self::Ollama => "llama3.2" }
+    }
+}
+
+/// The local Ollama's models, as `/api/local-ai/models` last listed them.
+#[derive(Default)]
+pub enum LocalModels {
+    /// Not asked yet; the first Ollama node shown asks.
+    #[default]
+    Unknown,
+    Loading,
+    Listed(Vec<String>),
+    Failed(String),
+}
+
+/// Provider dropdown. Switching provider also switches the model to that provider's default, or to the first local model listed.
+/// Returns true when the provider changed.
+pub fn provider_picker(ui: &mut egui::Ui, id: u64, provider: &mut Provider, model: &mut String, models: &LocalModels) -> bool {
+    let before = *provider;
+    egui::ComboBox::from_id_salt(("provider", id)).selected_text(provider.label()).width(140.0).show_ui(ui, |ui| {
+        for option in Provider::ALL { ui.selectable_value(provider, option, option.label()); }
+    });
+    if *provider == before { return false; }
+    *model = match (*provider, models) { (Provider::Ollama, LocalModels::Listed(names)) if !names.is_empty() => names[0].clone(), _ => provider.default_model().to_string() };
+    true
+}
+
+/// Dropdown of the local models with a button to list them again, or the reason they couldn't be listed. Returns whether the model
+/// changed and whether the list should be fetched.
+pub fn local_model_picker(ui: &mut egui::Ui, id: u64, model: &mut String, models: &LocalModels) -> (bool, bool) {
+    let (mut changed, mut refresh) = (false, matches!(models, LocalModels::Unknown));
+    ui.horizontal(|ui| {
+        match models {
+            LocalModels::Listed(names) if !names.is_empty() => {
+                egui::ComboBox::from_id_salt(("local_model", id)).selected_text(model.as_str()).width(140.0).show_ui(ui, |ui| {
+                    for name in names { changed |= ui.selectable_value(model, name.clone(), name).changed(); }
+                });
+            }
+            _ => { changed |= ui.add(egui::TextEdit::singleline(model).hint_text("Model").desired_width(140.0)).changed(); }
+        }
+        if matches!(models, LocalModels::Loading) { ui.spinner(); }
+        else if ui.small_button("⟳").on_hover_text("List the models Ollama has pulled").clicked() { refresh = true; }
+    });
+    match models {
+        LocalModels::Failed(err) => { ui.colored_label(ui.visuals().error_fg_color, format!("✗ {}", err)); }
+        LocalModels::Listed(names) if names.is_empty() => { ui.weak("Ollama has no models yet; `ollama pull llama3.2` adds one"); }
+        LocalModels::Listed(names) if !names.contains(model) => { ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ Ollama doesn't have {}", model)); }
+        _ => {}
+    }
+    (changed, refresh)
+}
+
+/// Model names from the `/api/local-ai/models` JSON array.
+pub fn parse_models(body: &[u8]) -> Result<Vec<String>, String> {
+    serde_json::from_slice(body).map_err(|_| "The server's model list wasn't readable".to_string())
+}
+
+impl StoryBoardApp {
+    /// Asks the server which models the local Ollama has; the answer lands as `AppMessage::LocalModels`.
+    pub(crate) fn refresh_local_models(&mut self, ctx: &egui::Context) {
+        if matches!(self.local_models, LocalModels::Loading) { return; }
+        self.local_models = LocalModels::Loading;
+        self.post_json(None, "/api/local-ai/models", serde_json::json!({}), ctx.clone(), |result| Some(AppMessage::LocalModels(match result {
+            Ok(response) if response.ok => parse_models(&response.bytes),
+            Ok(response) => Err(crate::response_error(&response)),
+            Err(err) => Err(err),
+        })));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn providers_route_and_list_models() {
+        assert_eq!(Provider::default().endpoint(), "/api/agnostic-ai");
+        assert_eq!(Provider::Ollama.endpoint(), "/api/local-ai");
+        assert_eq!(parse_models(br#"["llama3.2:latest","mistral:7b"]"#).unwrap(), vec!["llama3.2:latest", "mistral:7b"]);
+        assert!(parse_models(b"Ollama isn't running").is_err());
+        let saved: crate::NodeData = serde_json::from_str(r#"{"AgnosticAI":{"model":"m","prompt":"p","result":null,"is_loading":false}}"#).unwrap();
+        assert!(matches!(saved, crate::NodeData::AgnosticAI { provider: Provider::OpenRouter, .. }), "boards saved before providers stay on OpenRouter");
+    }
+}
@@ -1,10 +1,12 @@
 use eframe::egui;
+#[cfg(target_arch = "wasm32")]
 use eframe::wasm_bindgen::JsCast;
 use egui::{
     Color32, Frame, Margin, Pos2, Rect, Rounding, Sense, Stroke, Vec2,
 };
-use std::collections::{HashMap, HashSet};
-use std::sync::mpsc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
 use std::fmt;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -13,23 +15,385 @@ use std::time::Instant;
 #[cfg(target_arch = "wasm32")]
 use web_time::Instant;
 
+mod annotations;
+mod audio;
+mod autorun;
+mod boards;
+mod branch;
+mod cache;
+mod character;
+mod compare;
+mod frame;
+mod groups;
+mod history;
+mod layout;
+mod link;
+mod local_ai;
+mod markdown;
+mod merge;
+mod notes;
+mod perf;
+mod pipeline;
+mod ports;
+mod presentation;
+mod presets;
+mod queue;
+mod refresh;
+mod requests;
+mod retry;
+mod runlog;
+mod script;
+mod seeds;
+mod select;
+mod selection;
+mod shortcuts;
+mod stale;
+mod templates;
+mod theme;
+mod transcribe;
+mod transform;
+mod translate;
+mod validate;
+mod variants;
+mod versions;
+mod webfetch;
+
+use annotations::{Annotation, MIN_NOTE_SIZE, NOTE_COLORS};
+use autorun::AUTO_RUN_HINT;
+use boards::{Board, BoardSummary};
+use branch::BranchRole;
+use groups::{Group, GroupDrag, MIN_GROUP_SIZE};
+use history::{Command, History};
+use layout::{Arrange, LayoutAnimation};
+use perf::{Phase, PerfStats};
+use pipeline::{PipelineRun, PipelineSummary};
+use presentation::Presentation;
+use presets::{PipelineTemplate, TEMPLATES_KEY};
+use queue::{busy_indicator, QueuedRequest};
+use refresh::RefreshInterval;
+use retry::{Retry, MAX_RETRIES};
+use shortcuts::{Action, Shortcuts};
+use stale::STATUS_STALE;
+use theme::{Theme, ThemeKind};
+use variants::{Variant, MAX_VARIANTS};
+use versions::{ResultVersion, VersionView};
+
 #[derive(Clone, serde::Deserialize, serde::Serialize)]
 pub enum NodeData {
     Concept { text: String },
-    YouComResearch { query: String, result: Option<String>, is_loading: bool },
-    AgnosticAI { model: String, prompt: String, result: Option<String>, is_loading: bool },
-    Visual { prompt: String, #[serde(skip)] texture: Option<egui::TextureHandle>, is_loading: bool },
-    FoxitExport { status: String, is_loading: bool },
+    YouComResearch {
+        query: String,
+        result: Option<String>,
+        is_loading: bool,
+        /// Re-run the query on this schedule.
+        #[serde(default)] refresh: RefreshInterval,
+    },
+    AgnosticAI {
+        model: String,
+        prompt: String,
+        result: Option<String>,
+        is_loading: bool,
+        /// Generate as soon as a parent finishes, with its output linked in.
+        #[serde(default)] auto_run: bool,
+        #[serde(default)] provider: local_ai::Provider,
+    },
+    Visual {
+        prompt: String,
+        #[serde(skip)] texture: Option<egui::TextureHandle>,
+        #[serde(default, with = "base64_bytes")] image: Option<Vec<u8>>,
+        is_loading: bool,
+        /// Images requested per Generate click, 1 to `MAX_VARIANTS`.
+        #[serde(default = "variants::default_variant_count")] variant_count: usize,
+        /// Candidates from the last multi-image Generate.
+        #[serde(skip)] variants: Vec<Variant>,
+        /// Send a Visual parent's image along with the prompt (image-to-image).
+        #[serde(default)] use_parent_image: bool,
+        /// Generate as soon as a parent finishes, with its output linked in.
+        #[serde(default)] auto_run: bool,
+        /// Locked seed for reproducible images; `None` picks a random one per request.
+        #[serde(default)] seed: Option<u64>,
+        /// Seed the current image was generated with.
+        #[serde(default)] image_seed: Option<u64>,
+        /// Seeds of the requests in flight, by variant index.
+        #[serde(skip)] request_seeds: Vec<u64>,
+    },
+    FoxitExport {
+        status: String,
+        is_loading: bool,
+        /// Send every node on the board instead of only the ones feeding this export.
+        #[serde(default)] include_everything: bool,
+    },
+    /// Joins its parents' output. This and the other derived outputs (Compare, Select, Script, Transform, Markdown View) are rebuilt by `CanvasState::recompute_outputs` each frame rather than fetched.
+    Merge {
+        #[serde(default = "merge::default_separator")] separator: String,
+        /// Parents whose output is left out.
+        #[serde(default)] excluded: Vec<u64>,
+        #[serde(default)] output: String,
+    },
+    /// Two text parents side by side; the one picked is what it hands downstream.
+    Compare {
+        /// Parent whose text was picked.
+        #[serde(default)] picked: Option<u64>,
+        #[serde(default)] output: String,
+        /// Has exactly two parents; anything else is flagged with a red border.
+        #[serde(skip)] paired: bool,
+    },
+    /// Gathers its parents' results side by side; only the starred one goes downstream.
+    Select {
+        /// Parent whose result was starred, kept while it's unlinked so relinking it restores the pick.
+        #[serde(default)] chosen: Option<u64>,
+        #[serde(default)] output: String,
+        /// The chosen parent produces an image rather than text.
+        #[serde(skip)] chosen_image: bool,
+    },
+    /// Tests its parents' text and lets a pipeline run follow only its "true" or its "false" edges.
+    Branch {
+        /// Keyword to look for, or the yes/no question when `use_ai` is set.
+        condition: String,
+        #[serde(default)] use_ai: bool,
+        #[serde(default = "branch::default_branch_model")] model: String,
+        /// Result of the last evaluation; `None` until evaluated.
+        #[serde(default)] outcome: Option<bool>,
+        /// Parents' text as last evaluated, passed through unchanged.
+        #[serde(default)] input: String,
+        #[serde(default)] is_loading: bool,
+    },
+    /// Free-form scratch text that stays out of the story unless passed on.
+    Note {
+        title: String,
+        body: String,
+        /// Passed on: Link Parent and PDF exports include the body. Off by default so scratch notes stay private.
+        #[serde(default, alias = "pinned")] pass_on: bool,
+    },
+    /// Narration read aloud by the TTS provider behind `/api/tts`.
+    Audio {
+        #[serde(default = "audio::default_voice")] voice: String,
+        text: String,
+        /// The generated clip, WAV from the server. `Arc` so cloning the node for undo and templates doesn't copy the bytes.
+        #[serde(default, with = "base64_bytes")] audio: Option<Arc<[u8]>>,
+        #[serde(default)] is_loading: bool,
+    },
+    /// One screenplay scene, typed in or parsed from the parents' text by the AI.
+    Script {
+        /// "INT." or "EXT.", the start of the slugline.
+        heading: String,
+        location: String,
+        time_of_day: String,
+        action: String,
+        #[serde(default)] dialogue: Vec<script::DialogueLine>,
+        /// The scene laid out as a screenplay.
+        #[serde(default)] output: String,
+        #[serde(default)] is_loading: bool,
+    },
+    /// Someone who appears across frames; linked Visual nodes put the description ahead of their prompt.
+    Character {
+        name: String,
+        description: String,
+        /// Portrait sent as the starting image by linked Visual nodes with "Use parent image" on.
+        #[serde(default, with = "base64_bytes")] reference_image: Option<Arc<[u8]>>,
+    },
+    /// Its text in another language, translated by the AI.
+    Translate {
+        #[serde(default = "translate::default_language")] target_lang: String,
+        /// Text to translate, filled by Link Parent and pipeline runs.
+        #[serde(default)] text: String,
+        result: Option<String>,
+        #[serde(default)] is_loading: bool,
+    },
+    /// Readable text of a web page, fetched through `/api/fetch-url`.
+    WebFetch {
+        url: String,
+        result: Option<String>,
+        #[serde(default)] is_loading: bool,
+    },
+    /// The parents' text reshaped by a few local steps, no request involved.
+    Transform {
+        #[serde(default)] steps: Vec<transform::Step>,
+        /// The last step's text.
+        #[serde(default)] output: String,
+        /// Which step failed and why, in place of an output.
+        #[serde(skip)] error: Option<String>,
+    },
+    /// One panel of the storyboard, placed on the timeline by `sequence_index`.
+    Frame {
+        /// 1 for the first frame; frames sharing a number go in id order.
+        #[serde(default)] sequence_index: u32,
+        #[serde(default = "frame::default_shot")] shot_type: String,
+        caption: String,
+        /// Uploaded image; without one the frame shows its Visual parent's.
+        #[serde(default, with = "base64_bytes")] image: Option<Vec<u8>>,
+    },
+    /// Its parents' text rendered as markdown, read-only, for presenting a result.
+    MarkdownView {
+        /// What the parents hand over.
+        #[serde(default)] text: String,
+    },
+    /// A recorded voice note turned into text through `/api/transcribe`; the transcript can be corrected before it flows on.
+    Transcribe {
+        #[serde(default)] file_name: String,
+        /// The recording, kept so it can be transcribed again.
+        #[serde(default, with = "base64_bytes")] audio: Option<Arc<[u8]>>,
+        #[serde(default)] transcript: String,
+        #[serde(default)] is_loading: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKind { Concept, Research, AgnosticAI, Visual, FoxitExport, Merge, Compare, Branch, Select, Note, Audio, Script, Character, Translate, WebFetch, Transform, Frame, MarkdownView, Transcribe }
+
+impl NodeKind {
+    pub const ALL: [NodeKind; 19] = [Self::Concept, Self::Research, Self::AgnosticAI, Self::Visual, Self::FoxitExport, Self::Merge, Self::Compare, Self::Branch, Self::Select, Self::Note, Self::Audio, Self::Script, Self::Character, Self::Translate, Self::WebFetch, Self::Transform, Self::Frame, Self::MarkdownView, Self::Transcribe];
+
+    pub fn icon(self) -> &'static str {
+        match self { Self::Concept => "🧠", Self::Research => "🌐", Self::AgnosticAI => "🤖", Self::Visual => "🎨", Self::FoxitExport => "📄", Self::Merge => "🔀", Self::Compare => "⚖", Self::Branch => "🔱", Self::Select => "⭐", Self::Note => "🗒", Self::Audio => "🔊", Self::Script => "🎬", Self::Character => "🎭", Self::Translate => "🔤", Self::WebFetch => "📥", Self::Transform => "🔧", Self::Frame => "🎞", Self::MarkdownView => "📖", Self::Transcribe => "🎙" }
+    }
+
+    /// Heading shown on the node frame.
+    pub fn title(self) -> &'static str {
+        match self { Self::Concept => "Concept", Self::Research => "You.com Research", Self::AgnosticAI => "Agnostic AI", Self::Visual => "AI Visualizer", Self::FoxitExport => "Foxit Export", Self::Merge => "Merge", Self::Compare => "Compare", Self::Branch => "Branch", Self::Select => "Select", Self::Note => "Note", Self::Audio => "Text to Speech", Self::Script => "Script", Self::Character => "Character", Self::Translate => "Translate", Self::WebFetch => "Web Page", Self::Transform => "Transform", Self::Frame => "Frame", Self::MarkdownView => "Markdown View", Self::Transcribe => "Transcribe" }
+    }
+
+    /// Compact name for buttons.
+    pub fn short_label(self) -> &'static str {
+        match self { Self::Concept => "Concept", Self::Research => "Research", Self::AgnosticAI => "AI", Self::Visual => "Visual", Self::FoxitExport => "Export", Self::Merge => "Merge", Self::Compare => "Compare", Self::Branch => "Branch", Self::Select => "Select", Self::Note => "Note", Self::Audio => "Audio", Self::Script => "Script", Self::Character => "Character", Self::Translate => "Translate", Self::WebFetch => "Fetch", Self::Transform => "Transform", Self::Frame => "Frame", Self::MarkdownView => "View", Self::Transcribe => "Transcribe" }
+    }
+
+    pub fn default_data(self) -> NodeData {
+        match self {
+            Self::Concept => NodeData::Concept { text: "New Idea".to_string() },
+            Self::Research => NodeData::YouComResearch { query: "Topic".to_string(), result: None, is_loading: false, refresh: RefreshInterval::Off },
+            Self::AgnosticAI => NodeData::AgnosticAI { model: local_ai::Provider::OpenRouter.default_model().to_string(), prompt: "Prompt".to_string(), result: None, is_loading: false, auto_run: false, provider: local_ai::Provider::OpenRouter },
+            Self::Visual => NodeData::Visual { prompt: "Scene".to_string(), texture: None, image: None, is_loading: false, variant_count: 1, variants: Vec::new(), use_parent_image: false, auto_run: false, seed: None, image_seed: None, request_seeds: Vec::new() },
+            Self::FoxitExport => NodeData::FoxitExport { status: "Ready".to_string(), is_loading: false, include_everything: false },
+            Self::Merge => NodeData::Merge { separator: merge::default_separator(), excluded: Vec::new(), output: String::new() },
+            Self::Compare => NodeData::Compare { picked: None, output: String::new(), paired: false },
+            Self::Select => NodeData::Select { chosen: None, output: String::new(), chosen_image: false },
+            Self::Branch => NodeData::Branch { condition: "keyword".to_string(), use_ai: false, model: branch::default_branch_model(), outcome: None, input: String::new(), is_loading: false },
+            Self::Note => NodeData::Note { title: String::new(), body: String::new(), pass_on: false },
+            Self::Audio => NodeData::Audio { voice: audio::default_voice(), text: String::new(), audio: None, is_loading: false },
+            Self::Script => NodeData::Script { heading: "INT.".to_string(), location: String::new(), time_of_day: String::new(), action: String::new(), dialogue: Vec::new(), output: String::new(), is_loading: false },
+            Self::Character => NodeData::Character { name: String::new(), description: String::new(), reference_image: None },
+            Self::Translate => NodeData::Translate { target_lang: translate::default_language(), text: String::new(), result: None, is_loading: false },
+            Self::WebFetch => NodeData::WebFetch { url: String::new(), result: None, is_loading: false },
+            Self::Frame => NodeData::Frame { sequence_index: 1, shot_type: frame::default_shot(), caption: String::new(), image: None },
+            Self::Transform => NodeData::Transform { steps: vec![transform::Step::defaults()[0].clone()], output: String::new(), error: None },
+            Self::MarkdownView => NodeData::MarkdownView { text: String::new() },
+            Self::Transcribe => NodeData::Transcribe { file_name: String::new(), audio: None, transcript: String::new(), is_loading: false },
+        }
+    }
+}
+
+impl NodeData {
+    pub fn kind(&self) -> NodeKind {
+        match self {
+            Self::Concept { .. } => NodeKind::Concept,
+            Self::YouComResearch { .. } => NodeKind::Research,
+            Self::AgnosticAI { .. } => NodeKind::AgnosticAI,
+            Self::Visual { .. } => NodeKind::Visual,
+            Self::FoxitExport { .. } => NodeKind::FoxitExport,
+            Self::Merge { .. } => NodeKind::Merge,
+            Self::Compare { .. } => NodeKind::Compare,
+            Self::Select { .. } => NodeKind::Select,
+            Self::Branch { .. } => NodeKind::Branch,
+            Self::Note { .. } => NodeKind::Note,
+            Self::Audio { .. } => NodeKind::Audio,
+            Self::Script { .. } => NodeKind::Script,
+            Self::Character { .. } => NodeKind::Character,
+            Self::Translate { .. } => NodeKind::Translate,
+            Self::WebFetch { .. } => NodeKind::WebFetch,
+            Self::Transform { .. } => NodeKind::Transform,
+            Self::Frame { .. } => NodeKind::Frame,
+            Self::MarkdownView { .. } => NodeKind::MarkdownView,
+            Self::Transcribe { .. } => NodeKind::Transcribe,
+        }
+    }
+
+    /// The user-written text that best describes the node, if it has any.
+    pub fn primary_text(&self) -> Option<&str> {
+        match self {
+            Self::Concept { text } => Some(text),
+            Self::YouComResearch { query, .. } | Self::WebFetch { url: query, .. } => Some(query),
+            Self::AgnosticAI { prompt, .. } | Self::Visual { prompt, .. } => Some(prompt),
+            Self::Branch { condition, .. } => Some(condition),
+            Self::Note { body, .. } | Self::Audio { text: body, .. } | Self::Script { action: body, .. } | Self::Character { description: body, .. } | Self::Translate { text: body, .. } | Self::Frame { caption: body, .. } | Self::Transcribe { transcript: body, .. } => Some(body),
+            Self::FoxitExport { .. } | Self::Merge { .. } | Self::Compare { .. } | Self::Select { .. } | Self::Transform { .. } | Self::MarkdownView { .. } => None,
+        }
+    }
+
+    /// Per-type color used wherever nodes are drawn as plain shapes (minimap, overviews).
+    pub fn accent_color(&self) -> Color32 {
+        match self {
+            Self::Concept { .. } => Color32::from_rgb(170, 120, 255),
+            Self::YouComResearch { .. } => Color32::from_rgb(80, 200, 120),
+            Self::AgnosticAI { .. } => Color32::from_rgb(255, 170, 60),
+            Self::Visual { .. } => Color32::from_rgb(255, 100, 180),
+            Self::FoxitExport { .. } => Color32::from_rgb(230, 80, 80),
+            Self::Merge { .. } => Color32::from_rgb(90, 180, 230),
+            Self::Compare { .. } => Color32::from_rgb(200, 170, 90),
+            Self::Select { .. } => Color32::from_rgb(240, 210, 70),
+            Self::Branch { .. } => Color32::from_rgb(140, 200, 80),
+            Self::Note { .. } => Color32::from_rgb(150, 150, 140),
+            Self::Audio { .. } => Color32::from_rgb(90, 200, 200),
+            Self::Script { .. } => Color32::from_rgb(210, 150, 110),
+            Self::Character { .. } => Color32::from_rgb(120, 160, 255),
+            Self::Translate { .. } => Color32::from_rgb(230, 190, 120),
+            Self::WebFetch { .. } => Color32::from_rgb(160, 200, 230),
+            Self::Transform { .. } => Color32::from_rgb(170, 170, 185),
+            Self::Frame { .. } => Color32::from_rgb(240, 200, 90),
+            Self::MarkdownView { .. } => Color32::from_rgb(200, 200, 235),
+            Self::Transcribe { .. } => Color32::from_rgb(230, 130, 160),
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        matches!(self, Self::YouComResearch { is_loading: true, .. } | Self::AgnosticAI { is_loading: true, .. } | Self::Visual { is_loading: true, .. } | Self::FoxitExport { is_loading: true, .. } | Self::Branch { is_loading: true, .. } | Self::Audio { is_loading: true, .. } | Self::Script { is_loading: true, .. } | Self::Translate { is_loading: true, .. } | Self::WebFetch { is_loading: true, .. } | Self::Transcribe { is_loading: true, .. })
+    }
+
+    /// The `is_loading` flag of variants that talk to the server.
+    pub fn loading_flag(&mut self) -> Option<&mut bool> {
+        match self {
+            Self::Concept { .. } | Self::Merge { .. } | Self::Compare { .. } | Self::Select { .. } | Self::Note { .. } | Self::Character { .. } | Self::Transform { .. } | Self::Frame { .. } | Self::MarkdownView { .. } => None,
+            Self::YouComResearch { is_loading, .. } | Self::AgnosticAI { is_loading, .. } | Self::Visual { is_loading, .. } | Self::FoxitExport { is_loading, .. } | Self::Branch { is_loading, .. } | Self::Audio { is_loading, .. } | Self::Script { is_loading, .. } | Self::Translate { is_loading, .. } | Self::WebFetch { is_loading, .. } | Self::Transcribe { is_loading, .. } => Some(is_loading),
+        }
+    }
+}
+
+/// Serializes raw image and audio bytes, owned or shared, as a base64 string so projects stay plain JSON.
+mod base64_bytes {
+    use base64::{engine::general_purpose, Engine as _};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer, B: AsRef<[u8]>>(bytes: &Option<B>, s: S) -> Result<S::Ok, S::Error> {
+        match bytes { Some(b) => s.serialize_some(&general_purpose::STANDARD.encode(b)), None => s.serialize_none() }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, B: From<Vec<u8>>>(d: D) -> Result<Option<B>, D::Error> {
+        Option::<String>::deserialize(d)?.map(|b| general_purpose::STANDARD.decode(b).map(B::from).map_err(serde::de::Error::custom)).transpose()
+    }
 }
 
 impl fmt::Debug for NodeData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Concept { text } => f.debug_struct("Concept").field("text", text).finish(),
-            Self::YouComResearch { query, result, is_loading } => f.debug_struct("YouComResearch").field("query", query).field("result", result).field("is_loading", is_loading).finish(),
-            Self::AgnosticAI { model, prompt, result, is_loading } => f.debug_struct("AgnosticAI").field("model", model).field("prompt", prompt).field("result", result).field("is_loading", is_loading).finish(),
-            Self::Visual { prompt, is_loading, .. } => f.debug_struct("Visual").field("prompt", prompt).field("is_loading", is_loading).finish(),
-            Self::FoxitExport { status, is_loading } => f.debug_struct("FoxitExport").field("status", status).field("is_loading", is_loading).finish(),
+            Self::YouComResearch { query, result, is_loading, refresh } => f.debug_struct("YouComResearch").field("query", query).field("result", result).field("is_loading", is_loading).field("refresh", refresh).finish(),
+            Self::AgnosticAI { model, prompt, result, is_loading, auto_run, provider } => f.debug_struct("AgnosticAI").field("model", model).field("prompt", prompt).field("result", result).field("is_loading", is_loading).field("auto_run", auto_run).field("provider", provider).finish(),
+            Self::Visual { prompt, is_loading, variant_count, use_parent_image, auto_run, seed, .. } => f.debug_struct("Visual").field("prompt", prompt).field("is_loading", is_loading).field("variant_count", variant_count).field("use_parent_image", use_parent_image).field("auto_run", auto_run).field("seed", seed).finish(),
+            Self::FoxitExport { status, is_loading, include_everything } => f.debug_struct("FoxitExport").field("status", status).field("is_loading", is_loading).field("include_everything", include_everything).finish(),
+            Self::Merge { separator, excluded, output } => f.debug_struct("Merge").field("separator", separator).field("excluded", excluded).field("output", output).finish(),
+            Self::Compare { picked, output, .. } => f.debug_struct("Compare").field("picked", picked).field("output", output).finish(),
+            Self::Select { chosen, output, .. } => f.debug_struct("Select").field("chosen", chosen).field("output", output).finish(),
+            Self::Branch { condition, use_ai, model, outcome, input, is_loading } => f.debug_struct("Branch").field("condition", condition).field("use_ai", use_ai).field("model", model).field("outcome", outcome).field("input", input).field("is_loading", is_loading).finish(),
+            Self::Note { title, body, pass_on } => f.debug_struct("Note").field("title", title).field("body", body).field("pass_on", pass_on).finish(),
+            Self::Audio { voice, text, audio, is_loading } => f.debug_struct("Audio").field("voice", voice).field("text", text).field("audio_bytes", &audio.as_ref().map(|a| a.len())).field("is_loading", is_loading).finish(),
+            Self::Frame { sequence_index, shot_type, caption, image } => f.debug_struct("Frame").field("sequence_index", sequence_index).field("shot_type", shot_type).field("caption", caption).field("image", &image.as_ref().map(Vec::len)).finish(),
+            Self::MarkdownView { text } => f.debug_struct("MarkdownView").field("text", text).finish(),
+            Self::Transcribe { file_name, audio, transcript, is_loading } => f.debug_struct("Transcribe").field("file_name", file_name).field("audio_bytes", &audio.as_ref().map(|a| a.len())).field("transcript", transcript).field("is_loading", is_loading).finish(),
+            Self::Transform { steps, error, .. } => f.debug_struct("Transform").field("steps", steps).field("error", error).finish(),
+            Self::WebFetch { url, result, is_loading } => f.debug_struct("WebFetch").field("url", url).field("result", result).field("is_loading", is_loading).finish(),
+            Self::Translate { target_lang, text, result, is_loading } => f.debug_struct("Translate").field("target_lang", target_lang).field("text", text).field("result", result).field("is_loading", is_loading).finish(),
+            Self::Character { name, description, reference_image } => f.debug_struct("Character").field("name", name).field("description", description).field("reference_image_bytes", &reference_image.as_ref().map(|i| i.len())).finish(),
+            Self::Script { heading, location, time_of_day, action, dialogue, is_loading, .. } => f.debug_struct("Script").field("heading", heading).field("location", location).field("time_of_day", time_of_day).field("action", action).field("dialogue", dialogue).field("is_loading", is_loading).finish(),
         }
     }
 }
@@ -38,10 +402,24 @@ impl PartialEq for NodeData {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Concept { text: a }, Self::Concept { text: b }) => a == b,
-            (Self::YouComResearch { query: a, result: b, is_loading: c }, Self::YouComResearch { query: x, result: y, is_loading: z }) => a == x && b == y && c == z,
-            (Self::AgnosticAI { model: a, prompt: b, result: c, is_loading: d }, Self::AgnosticAI { model: w, prompt: x, result: y, is_loading: z }) => a == w && b == x && c == y && d == z,
-            (Self::Visual { prompt: a, is_loading: b, .. }, Self::Visual { prompt: x, is_loading: y, .. }) => a == x && b == y,
-            (Self::FoxitExport { status: a, is_loading: b }, Self::FoxitExport { status: x, is_loading: y }) => a == x && b == y,
+            (Self::YouComResearch { query: a, result: b, is_loading: c, refresh: d }, Self::YouComResearch { query: x, result: y, is_loading: z, refresh: w }) => a == x && b == y && c == z && d == w,
+            (Self::AgnosticAI { model: a, prompt: b, result: c, is_loading: d, auto_run: e, provider: f }, Self::AgnosticAI { model: w, prompt: x, result: y, is_loading: z, auto_run: v, provider: u }) => a == w && b == x && c == y && d == z && e == v && f == u,
+            (Self::Visual { prompt: a, is_loading: b, variant_count: c, use_parent_image: d, auto_run: e, seed: f, .. }, Self::Visual { prompt: x, is_loading: y, variant_count: z, use_parent_image: w, auto_run: v, seed: u, .. }) => a == x && b == y && c == z && d == w && e == v && f == u,
+            (Self::FoxitExport { status: a, is_loading: b, include_everything: c }, Self::FoxitExport { status: x, is_loading: y, include_everything: z }) => a == x && b == y && c == z,
+            (Self::Merge { separator: a, excluded: b, output: c }, Self::Merge { separator: x, excluded: y, output: z }) => a == x && b == y && c == z,
+            (Self::Compare { picked: a, output: b, .. }, Self::Compare { picked: x, output: y, .. }) => a == x && b == y,
+            (Self::Select { chosen: a, output: b, .. }, Self::Select { chosen: x, output: y, .. }) => a == x && b == y,
+            (Self::Branch { condition: a, use_ai: b, model: c, outcome: d, input: e, is_loading: f }, Self::Branch { condition: u, use_ai: v, model: w, outcome: x, input: y, is_loading: z }) => a == u && b == v && c == w && d == x && e == y && f == z,
+            (Self::Note { title: a, body: b, pass_on: c }, Self::Note { title: x, body: y, pass_on: z }) => a == x && b == y && c == z,
+            (Self::Audio { voice: a, text: b, audio: c, is_loading: d }, Self::Audio { voice: w, text: x, audio: y, is_loading: z }) => a == w && b == x && c == y && d == z,
+            (Self::Transform { steps: a, .. }, Self::Transform { steps: b, .. }) => a == b,
+            (Self::MarkdownView { text: a }, Self::MarkdownView { text: b }) => a == b,
+            (Self::Transcribe { file_name: a, audio: b, transcript: c, is_loading: d }, Self::Transcribe { file_name: w, audio: x, transcript: y, is_loading: z }) => a == w && b == x && c == y && d == z,
+            (Self::Frame { sequence_index: a, shot_type: b, caption: c, image: d }, Self::Frame { sequence_index: w, shot_type: x, caption: y, image: z }) => a == w && b == x && c == y && d == z,
+            (Self::WebFetch { url: a, result: b, is_loading: c }, Self::WebFetch { url: x, result: y, is_loading: z }) => a == x && b == y && c == z,
+            (Self::Translate { target_lang: a, text: b, result: c, is_loading: d }, Self::Translate { target_lang: w, text: x, result: y, is_loading: z }) => a == w && b == x && c == y && d == z,
+            (Self::Character { name: a, description: b, reference_image: c }, Self::Character { name: x, description: y, reference_image: z }) => a == x && b == y && c == z,
+            (Self::Script { heading: a, location: b, time_of_day: c, action: d, dialogue: e, is_loading: f, .. }, Self::Script { heading: u, location: v, time_of_day: w, action: x, dialogue: y, is_loading: z, .. }) => a == u && b == v && c == w && d == x && e == y && f == z,
             _ => false,
         }
     }
@@ -49,36 +427,341 @@ impl PartialEq for NodeData {
 
 pub enum AppMessage {
     TextResponse(u64, String),
-    ImageResponse(u64, Vec<u8>),
+    /// Image for a Visual node; the index is set for one slot of a multi-variant Generate.
+    ImageResponse(u64, Option<usize>, Vec<u8>),
     Error(u64, String),
+    /// One variant of a multi-variant Generate failed; the others carry on.
+    VariantError(u64, usize, String),
+    /// The rendered HTML report, or why the server couldn't produce it.
+    HtmlReport(Result<Vec<u8>, String>),
+    /// A node's request finished; recorded in the execution log before its result is handled.
+    Logged(runlog::LogEntry),
+    /// Narration clip for an Audio node.
+    AudioResponse(u64, Vec<u8>),
+    /// A successful node response to keep in the response cache under its request key.
+    CacheStore(u64, ehttp::Response),
+    /// The local Ollama's models, or why they couldn't be listed.
+    LocalModels(Result<Vec<String>, String>),
+    /// A recording picked for a Transcribe node: file name and bytes.
+    AudioFile(u64, String, Vec<u8>),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Node {
     pub id: u64,
     pub position: Pos2,
     pub size: Vec2,
     pub data: NodeData,
+    #[serde(skip)]
     pub selected: bool,
+    #[serde(skip)]
     pub velocity: Vec2,
+    #[serde(default)]
+    pub collapsed: bool,
+    /// Size to restore when a collapsed node is expanded again.
+    #[serde(default)]
+    pub expanded_size: Option<Vec2>,
+    /// User-chosen heading; `None` shows the type name.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Pinned nodes are left where they are by the physics simulation.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Height follows the content instead of the resize grip; width stays manual.
+    #[serde(default)]
+    pub auto_size: bool,
+    /// AI and research results are shown as typed instead of rendered as markdown.
+    #[serde(default)]
+    pub raw_markdown: bool,
+    /// Message from the last failed request; cleared when the next one starts.
+    #[serde(skip)]
+    pub error: Option<String>,
+    /// Set while a pipeline run is waiting to start this node.
+    #[serde(skip)]
+    pub queued: bool,
+    /// Something upstream changed since this node last produced a result.
+    #[serde(skip)]
+    pub stale: bool,
+    /// Left out of the last pipeline run because it sits behind a branch not taken.
+    #[serde(skip)]
+    pub skipped: bool,
+    /// The last result came from the response cache rather than the server.
+    #[serde(skip)]
+    pub cached: bool,
+    /// `NodeData::output_hash` when staleness was last checked.
+    #[serde(skip)]
+    pub output_hash: Option<u64>,
+    /// Earlier results, oldest first, kept when a new one replaces them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<ResultVersion>,
+}
+
+/// What the corner badge and border tint of a node show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeStatus { Idle, Queued, Loading, Done, Failed, Skipped }
+
+const STATUS_QUEUED: Color32 = Color32::from_rgb(230, 190, 60);
+const STATUS_RUNNING: Color32 = Color32::from_rgb(70, 140, 255);
+const STATUS_DONE: Color32 = Color32::from_rgb(60, 190, 90);
+const STATUS_FAILED: Color32 = Color32::from_rgb(220, 70, 70);
+const STATUS_SKIPPED: Color32 = Color32::from_rgb(140, 140, 140);
+
+impl NodeStatus {
+    pub fn label(self) -> &'static str {
+        match self { Self::Idle => "Idle", Self::Queued => "Queued", Self::Loading => "Running", Self::Done => "Done", Self::Failed => "Error", Self::Skipped => "Skipped" }
+    }
+
+    /// Border tint for the status; running nodes pulse.
+    pub fn color(self, theme: &Theme, time: f64) -> Color32 {
+        match self {
+            Self::Idle => theme.node_border,
+            Self::Queued => STATUS_QUEUED,
+            Self::Loading => STATUS_RUNNING.gamma_multiply(0.5 + 0.5 * (time * 4.0).sin().abs() as f32),
+            Self::Done => STATUS_DONE,
+            Self::Failed => STATUS_FAILED,
+            Self::Skipped => STATUS_SKIPPED,
+        }
+    }
 }
 
 impl Node {
     pub fn new(id: u64, position: Pos2, data: NodeData) -> Self {
         let size = match data {
             NodeData::AgnosticAI { .. } => Vec2::new(300.0, 450.0),
+            NodeData::Note { .. } => Vec2::new(240.0, 200.0),
+            NodeData::Script { .. } => Vec2::new(300.0, 380.0),
+            NodeData::Frame { .. } => Vec2::new(280.0, 320.0),
+            NodeData::MarkdownView { .. } => Vec2::new(320.0, 360.0),
+            NodeData::Transcribe { .. } => Vec2::new(280.0, 340.0),
             _ => Vec2::new(250.0, 300.0),
         };
-        Self { id, position, size, data, selected: false, velocity: Vec2::ZERO }
+        Self { id, position, size, data, selected: false, velocity: Vec2::ZERO, collapsed: false, expanded_size: None, title: None, pinned: false, auto_size: false, raw_markdown: false, error: None, queued: false, stale: false, skipped: false, cached: false, output_hash: None, versions: Vec::new() }
+    }
+    pub fn status(&self) -> NodeStatus {
+        if matches!(self.data, NodeData::Compare { paired: false, .. } | NodeData::Transform { error: Some(_), .. }) { return NodeStatus::Failed; }
+        if self.queued { return NodeStatus::Queued; }
+        if self.data.is_loading() { return NodeStatus::Loading; }
+        if self.error.is_some() { return NodeStatus::Failed; }
+        if self.skipped { return NodeStatus::Skipped; }
+        match &self.data {
+            NodeData::YouComResearch { result: Some(_), .. } | NodeData::AgnosticAI { result: Some(_), .. } | NodeData::Visual { texture: Some(_), .. } | NodeData::Branch { outcome: Some(_), .. } | NodeData::Select { chosen: Some(_), .. } | NodeData::Audio { audio: Some(_), .. } | NodeData::Translate { result: Some(_), .. } | NodeData::WebFetch { result: Some(_), .. } => NodeStatus::Done,
+            NodeData::Transcribe { transcript, .. } if !transcript.is_empty() => NodeStatus::Done,
+            _ => NodeStatus::Idle,
+        }
     }
+    /// World-space area that starts a drag: the header row when the node's widgets are shown, the whole node otherwise.
+    /// Widgets aren't scaled with the camera, so the header has a fixed screen height.
+    pub fn drag_handle(&self, zoom: f32) -> Rect {
+        if zoom < LOD_DETAIL_ZOOM || self.collapsed { return self.bounds(); }
+        Rect::from_min_size(self.position, Vec2::new(self.size.x, (NODE_HEADER_HEIGHT / zoom).min(self.size.y)))
+    }
+    pub fn display_title(&self) -> &str { self.title.as_deref().or(self.data.own_title()).unwrap_or(self.data.kind().title()) }
     pub fn bounds(&self) -> Rect { Rect::from_min_size(self.position, self.size) }
+    /// World-space anchor where outgoing edges leave the node.
+    pub fn output_port(&self) -> Pos2 { self.position + Vec2::new(self.size.x, self.size.y / 2.0) }
+    /// World-space anchor where incoming edges enter the node.
+    pub fn input_port(&self) -> Pos2 { self.position + Vec2::new(0.0, self.size.y / 2.0) }
+
+    pub fn toggle_collapsed(&mut self) {
+        if self.collapsed {
+            self.size = self.expanded_size.take().unwrap_or(self.size.max(MIN_NODE_SIZE));
+        } else {
+            self.expanded_size = Some(self.size);
+            self.size = COLLAPSED_SIZE;
+        }
+        self.collapsed = !self.collapsed;
+    }
+}
+
+/// Screen height of a node's title row, including the frame margin above it.
+const NODE_HEADER_HEIGHT: f32 = 40.0;
+pub const MIN_NODE_SIZE: Vec2 = Vec2::new(180.0, 120.0);
+pub const MAX_NODE_SIZE: Vec2 = Vec2::new(1200.0, 1600.0);
+/// Tallest an auto-sized node grows before its content has to scroll.
+const AUTO_SIZE_MAX_HEIGHT: f32 = 900.0;
+/// Fraction of the remaining height change an auto-sized node covers each frame.
+const AUTO_SIZE_EASE: f32 = 0.35;
+/// Result scroll areas get this much room in auto-sized nodes instead of their fixed cap.
+const AUTO_SIZE_RESULT_HEIGHT: f32 = 400.0;
+/// Header-only size of a collapsed node.
+pub const COLLAPSED_SIZE: Vec2 = Vec2::new(250.0, 44.0);
+/// Horizontal gap between an auto-connected node and its parent, and the step used to move it below earlier children.
+const AUTO_CONNECT_GAP: f32 = 60.0;
+/// Size of the generated-image thumbnail on Visual rows of the sidebar node list.
+const SIDEBAR_THUMBNAIL: Vec2 = Vec2::new(48.0, 28.0);
+/// Characters of a node's main text shown under its title in the sidebar.
+const SIDEBAR_PREVIEW_CHARS: usize = 40;
+/// Screen-space side length of the corner resize grip.
+const RESIZE_HANDLE: f32 = 14.0;
+
+/// Shortens `text` to at most `max` characters, ending in an ellipsis when cut.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() > max { format!("{}…", text.chars().take(max.saturating_sub(1)).collect::<String>()) } else { text.to_string() }
+}
+
+/// "Title (primary text…)" for tooltips.
+fn node_summary(node: &Node) -> String {
+    match node.data.primary_text().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(text) => format!("{} ({})", node.display_title(), truncate(text.lines().next().unwrap_or_default(), 24)),
+        None => node.display_title().to_string(),
+    }
+}
+
+/// Opacity of nodes and edges outside the Alt-hovered node's lineage.
+const DIM_OPACITY: f32 = 0.35;
+
+/// Below this zoom nodes are painted as a title and summary line instead of live widgets.
+const LOD_DETAIL_ZOOM: f32 = 0.4;
+/// Below this zoom nodes are painted as plain colored blocks.
+const LOD_BLOCK_ZOOM: f32 = 0.15;
+
+/// Widget-free rendering of a node for zoomed-out views. The border carries the node's status, with selection drawn just outside it.
+fn paint_node_lod(painter: &egui::Painter, theme: &Theme, node: &Node, rect: Rect, zoom: f32, time: f64) {
+    let accent = node.data.accent_color();
+    let status = node.status();
+    let status_stroke = (status != NodeStatus::Idle).then(|| Stroke::new(2.0, status.color(theme, time)));
+    if node.selected && status_stroke.is_some() { painter.rect_stroke(rect.expand(2.5), 4.0, Stroke::new(1.5, theme.selection)); }
+    if zoom < LOD_BLOCK_ZOOM {
+        let stroke = status_stroke.unwrap_or(if node.selected { Stroke::new(1.5, theme.selection) } else { Stroke::NONE });
+        painter.rect(rect, 2.0, accent.gamma_multiply(0.7), stroke);
+        return;
+    }
+    let stroke = status_stroke.unwrap_or(Stroke::new(1.0, if node.selected { theme.selection } else { theme.node_border }));
+    painter.rect(rect, 8.0 * zoom, if node.data.kind() == NodeKind::Note { notes::note_fill(theme) } else { theme.node_fill }, stroke);
+    painter.rect_filled(Rect::from_min_size(rect.min, Vec2::new(rect.width(), 4.0)), egui::Rounding { nw: 8.0 * zoom, ne: 8.0 * zoom, sw: 0.0, se: 0.0 }, accent);
+    let line = |text: &str, size: f32, color: Color32| {
+        let mut job = egui::text::LayoutJob::simple_singleline(text.to_string(), egui::FontId::proportional(size), color);
+        job.wrap = egui::text::TextWrapping { max_width: (rect.width() - 12.0).max(0.0), max_rows: 1, break_anywhere: true, overflow_character: Some('…') };
+        painter.layout_job(job)
+    };
+    let content = rect.shrink2(Vec2::new(6.0, 8.0));
+    let title = line(&format!("{} {}", node.data.kind().icon(), node.display_title()), 13.0, theme.text);
+    let title_height = title.size().y;
+    painter.with_clip_rect(rect).galley(content.min, title, theme.text);
+    if let Some(summary) = node.data.primary_text().filter(|_| !node.collapsed && content.height() > title_height * 2.0) {
+        let summary = line(summary.lines().next().unwrap_or_default(), 11.0, theme.muted_text);
+        painter.with_clip_rect(rect).galley(content.min + Vec2::new(0.0, title_height + 2.0), summary, theme.muted_text);
+    }
+}
+
+/// Screen-space radius of the status badge drawn on a node's top-right corner.
+const BADGE_RADIUS: f32 = 8.0;
+
+/// Names a widget inside a node frame for screen readers, e.g. "Node 4 – Research query".
+fn node_widget(response: egui::Response, typ: egui::WidgetType, id: u64, title: &str, what: &str) -> egui::Response {
+    response.widget_info(|| egui::WidgetInfo::labeled(typ, response.enabled(), format!("Node {} – {} {}", id, title, what)));
+    response
+}
+
+/// Characters of result text shown in a node's hover preview.
+const PREVIEW_CHARS: usize = 600;
+/// How long the pointer rests on a canvas node before its preview appears.
+const PREVIEW_DELAY_SECS: f32 = 0.5;
+
+/// Tooltip body summarising what a node produced: its result, export status or concept text, or the generated image.
+fn node_preview_ui(ui: &mut egui::Ui, node: &Node) {
+    ui.set_max_width(320.0);
+    ui.strong(format!("{} {}", node.data.kind().icon(), node.display_title()));
+    let text = match &node.data {
+        NodeData::Concept { text } | NodeData::Note { body: text, .. } | NodeData::Character { description: text, .. } | NodeData::Frame { caption: text, .. } | NodeData::Transcribe { transcript: text, .. } => Some(text.as_str()),
+        NodeData::YouComResearch { result, .. } | NodeData::AgnosticAI { result, .. } | NodeData::Translate { result, .. } | NodeData::WebFetch { result, .. } => result.as_deref(),
+        NodeData::FoxitExport { status, .. } => Some(status.as_str()),
+        NodeData::Merge { output, .. } | NodeData::Compare { output, .. } | NodeData::Script { output, .. } | NodeData::Transform { output, .. } | NodeData::MarkdownView { text: output } => Some(output.as_str()),
+        NodeData::Select { chosen: Some(chosen), output, .. } if output.is_empty() => { ui.label(format!("Chose #{}", chosen)); return; }
+        NodeData::Select { output, .. } => Some(output.as_str()),
+        NodeData::Branch { outcome, .. } => { ui.label(format!("Outcome: {}", branch::outcome_text(*outcome))); return; }
+        NodeData::Visual { texture: Some(tex), .. } => { ui.add(egui::Image::new(tex).max_size(Vec2::new(240.0, 160.0))); return; }
+        NodeData::Audio { audio, text, .. } => { if let Some(clip) = audio { ui.label(format!("🔊 {} clip", audio::duration_label(clip))); } Some(text.as_str()) }
+        NodeData::Visual { .. } => None,
+    }.filter(|t| !t.trim().is_empty());
+    match text {
+        Some(text) => {
+            let shown: String = text.chars().take(PREVIEW_CHARS).collect();
+            ui.add(egui::Label::new(egui::RichText::new(shown.trim_end()).monospace()).wrap());
+            if text.chars().nth(PREVIEW_CHARS).is_some() { ui.weak("…"); }
+        }
+        None => { ui.weak("No result yet"); }
+    }
+}
+
+/// Loading spinner, green check or red cross on the node's corner, the same size at every zoom.
+fn paint_status_badge(painter: &egui::Painter, theme: &Theme, node: &Node, rect: Rect, time: f64) {
+    let status = node.status();
+    let inset = (BADGE_RADIUS + 4.0).min(rect.width() / 2.0).min(rect.height() / 2.0);
+    let center = rect.right_top() + Vec2::new(-inset, inset);
+    let r = BADGE_RADIUS;
+    if node.stale { painter.circle_filled(center - Vec2::new(2.0 * r + 2.0, 0.0), r * 0.45, STATUS_STALE); }
+    if status == NodeStatus::Idle { return; }
+    painter.circle_filled(center, r, theme.overlay);
+    match status {
+        NodeStatus::Loading => {
+            let start = time as f32 * 6.0;
+            let points = (0..=12).map(|k| { let a = start + k as f32 * 0.375; center + r * 0.6 * Vec2::angled(a) }).collect();
+            painter.add(egui::Shape::line(points, Stroke::new(2.0, node.data.accent_color())));
+        }
+        NodeStatus::Queued => { painter.circle_stroke(center, r * 0.45, Stroke::new(2.0, STATUS_QUEUED)); }
+        NodeStatus::Skipped => { painter.line_segment([center - Vec2::new(r * 0.45, 0.0), center + Vec2::new(r * 0.45, 0.0)], Stroke::new(2.0, STATUS_SKIPPED)); }
+        NodeStatus::Done => {
+            painter.add(egui::Shape::line(vec![center + r * Vec2::new(-0.45, 0.0), center + r * Vec2::new(-0.1, 0.35), center + r * Vec2::new(0.45, -0.35)], Stroke::new(2.0, STATUS_DONE)));
+        }
+        NodeStatus::Failed => {
+            let d = r * 0.4;
+            painter.line_segment([center - Vec2::splat(d), center + Vec2::splat(d)], Stroke::new(2.0, STATUS_FAILED));
+            painter.line_segment([center + Vec2::new(-d, d), center + Vec2::new(d, -d)], Stroke::new(2.0, STATUS_FAILED));
+        }
+        NodeStatus::Idle => {}
+    }
+}
+
+/// Screen-space radius of the port circles, and how close the pointer must be to grab one.
+const PORT_RADIUS: f32 = 6.0;
+const PORT_HIT_RADIUS: f32 = 10.0;
+
+fn resize_handle_rect(node_rect: Rect) -> Rect {
+    Rect::from_min_max(node_rect.right_bottom() - Vec2::splat(RESIZE_HANDLE), node_rect.right_bottom())
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Edge {
     pub id: u64,
     pub from: u64,
     pub to: u64,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Which outcome of a Branch node this edge follows; `None` on edges that don't leave one.
+    #[serde(default)]
+    pub role: Option<BranchRole>,
+}
+
+/// Nodes moving together under the pointer, with their positions when the drag began.
+#[derive(Clone, Debug)]
+pub struct NodeDrag {
+    /// The node the drag started on.
+    pub anchor: u64,
+    pub origins: Vec<(u64, Pos2)>,
+    /// World-space pointer travel since the drag began or Shift was last released.
+    pub travel: Vec2,
+    /// Offset from `origins` already settled when Shift was released.
+    pub settled: Vec2,
+    /// Whether the previous frame was axis-constrained.
+    pub constrained: bool,
+}
+
+impl NodeDrag {
+    pub fn new(anchor: u64, origins: Vec<(u64, Pos2)>) -> Self { Self { anchor, origins, travel: Vec2::ZERO, settled: Vec2::ZERO, constrained: false } }
+    pub fn contains(&self, id: u64) -> bool { self.origins.iter().any(|(n, _)| *n == id) }
+
+    /// Adds this frame's pointer movement and returns the offset from `origins`.
+    /// With `constrain`, only the dominant axis of the accumulated travel counts, so small wobbles can't flip it.
+    pub fn offset(&mut self, delta: Vec2, constrain: bool) -> Vec2 {
+        self.travel += delta;
+        let along = if self.travel.x.abs() >= self.travel.y.abs() { Vec2::new(self.travel.x, 0.0) } else { Vec2::new(0.0, self.travel.y) };
+        if constrain { self.constrained = true; return self.settled + along; }
+        // On release keep the constrained position rather than jumping to the pointer.
+        self.settled += if std::mem::take(&mut self.constrained) { along } else { self.travel };
+        self.travel = Vec2::ZERO;
+        self.settled
+    }
 }
 
 pub struct CanvasState {
@@ -87,8 +770,32 @@ pub struct CanvasState {
     pub next_id: u64,
     pub camera_offset: Vec2,
     pub camera_zoom: f32,
-    pub dragging_node: Option<u64>,
-    pub linking_from: Option<u64>,
+    pub dragging: Option<NodeDrag>,
+    /// Node being resized from its corner handle, with its size when the drag began.
+    pub resizing_node: Option<(u64, Vec2)>,
+    pub linking_from: Vec<u64>,
+    /// True while `linking_from` is being dragged out of an output port rather than set from the sidebar.
+    pub linking_drag: bool,
+    pub selected_edge: Option<u64>,
+    /// Back-to-front paint order; nodes missing from it are drawn first, by id.
+    pub draw_order: Vec<u64>,
+    /// Frames drawn behind the nodes, back to front.
+    pub groups: Vec<Group>,
+    pub dragging_group: Option<GroupDrag>,
+    /// Group being resized from its corner handle, with its rect when the drag began.
+    pub resizing_group: Option<(u64, Rect)>,
+    /// Sticky notes, back to front.
+    pub annotations: Vec<Annotation>,
+    /// Note being moved by its strip or resized from its corner, as it was when the drag began.
+    pub dragging_note: Option<Annotation>,
+    pub resizing_note: Option<Annotation>,
+    pub history: History,
+    /// Keyboard cursor for Tab navigation; unlike `selected`, there is at most one and the mouse only moves it by grabbing a node.
+    pub focused_node: Option<u64>,
+    /// Bumped on every command, undo and redo, so graph-derived caches know when to recompute.
+    pub graph_version: u64,
+    /// Each Transform node's last result, under the `transform::fingerprint` of the input and steps it came from.
+    pub transform_cache: HashMap<u64, (u64, Result<String, String>)>,
 }
 
 impl Default for CanvasState {
@@ -99,172 +806,1214 @@ impl Default for CanvasState {
             next_id: 1,
             camera_offset: Vec2::ZERO,
             camera_zoom: 1.0,
-            dragging_node: None,
-            linking_from: None,
+            dragging: None,
+            resizing_node: None,
+            linking_from: Vec::new(),
+            linking_drag: false,
+            selected_edge: None,
+            draw_order: Vec::new(),
+            groups: Vec::new(),
+            dragging_group: None,
+            resizing_group: None,
+            annotations: Vec::new(),
+            dragging_note: None,
+            resizing_note: None,
+            history: History::default(),
+            focused_node: None,
+            graph_version: 0,
+            transform_cache: HashMap::new(),
+        }
+    }
+}
+
+impl CanvasState {
+    pub fn selected_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.nodes.values().filter(|n| n.selected).map(|n| n.id).collect();
+        ids.sort();
+        ids
+    }
+
+    /// Node ids back to front.
+    pub fn ordered_ids(&self) -> Vec<u64> {
+        let mut rest: Vec<u64> = self.nodes.keys().copied().filter(|id| !self.draw_order.contains(id)).collect();
+        rest.sort();
+        rest.extend(self.draw_order.iter().copied().filter(|id| self.nodes.contains_key(id)));
+        rest
+    }
+
+    pub fn bring_to_front(&mut self, id: u64) {
+        let nodes = &self.nodes;
+        self.draw_order.retain(|&other| other != id && nodes.contains_key(&other));
+        self.draw_order.push(id);
+    }
+
+    /// `id` plus every node reachable from it by following edges forwards or backwards.
+    pub fn lineage(&self, id: u64) -> HashSet<u64> {
+        let mut seen = HashSet::from([id]);
+        for downstream in [true, false] {
+            let mut stack = vec![id];
+            let mut visited = HashSet::from([id]);
+            while let Some(current) = stack.pop() {
+                for e in &self.edges {
+                    let (from, to) = if downstream { (e.from, e.to) } else { (e.to, e.from) };
+                    if from == current && visited.insert(to) { stack.push(to); }
+                }
+            }
+            seen.extend(visited);
+        }
+        seen
+    }
+
+    /// Moves `focused_node` to the next (or previous) node by id, wrapping around, and selects it.
+    pub fn cycle_focus(&mut self, forward: bool) -> Option<u64> {
+        let mut ids: Vec<u64> = self.nodes.keys().copied().collect();
+        ids.sort();
+        let current = self.focused_node.and_then(|f| ids.iter().position(|&id| id == f));
+        let next = match (current, forward) {
+            (Some(i), true) => ids.get((i + 1) % ids.len().max(1)),
+            (Some(i), false) => ids.get((i + ids.len() - 1) % ids.len().max(1)),
+            (None, true) => ids.first(),
+            (None, false) => ids.last(),
+        }.copied()?;
+        self.focused_node = Some(next);
+        self.select_only(next);
+        Some(next)
+    }
+
+    /// Topmost node matching `hit`.
+    pub fn topmost(&self, hit: impl Fn(&Node) -> bool) -> Option<u64> {
+        self.ordered_ids().into_iter().rev().find(|id| hit(&self.nodes[id]))
+    }
+
+    /// Topmost node containing `world_pos`.
+    pub fn node_at(&self, world_pos: Pos2) -> Option<u64> {
+        self.topmost(|n| n.bounds().contains(world_pos))
+    }
+
+    /// Node an Alt-drop of `drag` would link its anchor to: the topmost other node under the anchor's center, unless already linked.
+    pub fn drop_link_target(&self, drag: &NodeDrag) -> Option<u64> {
+        let center = self.nodes.get(&drag.anchor)?.bounds().center();
+        self.topmost(|n| !drag.contains(n.id) && n.bounds().contains(center)).filter(|&to| !self.edges.iter().any(|e| e.from == drag.anchor && e.to == to))
+    }
+
+    /// Moves `id` off `target` to the left of it, stepping down past any node already there.
+    pub fn place_before(&mut self, id: u64, target: u64) {
+        let (Some(node), Some(target)) = (self.nodes.get(&id), self.nodes.get(&target)) else { return };
+        let mut spot = Rect::from_min_size(target.position - Vec2::new(node.size.x + 80.0, 0.0), node.size);
+        for _ in 0..20 {
+            let Some(blocker) = self.nodes.values().filter(|n| n.id != id).map(|n| n.bounds()).find(|b| b.intersects(spot)) else { break };
+            spot = spot.translate(Vec2::new(0.0, blocker.max.y + 40.0 - spot.min.y));
+        }
+        if let Some(node) = self.nodes.get_mut(&id) { node.position = spot.min; node.velocity = Vec2::ZERO; }
+    }
+
+    /// Selects `id` alone and raises it above the other nodes.
+    pub fn select_only(&mut self, id: u64) {
+        for node in self.nodes.values_mut() { node.selected = node.id == id; }
+        self.bring_to_front(id);
+    }
+
+    /// Screen-space control points of the bezier drawn for `edge`, if both endpoints exist.
+    pub fn edge_curve(&self, edge: &Edge, world_to_screen: impl Fn(Pos2) -> Pos2) -> Option<[Pos2; 4]> {
+        let (n1, n2) = (self.nodes.get(&edge.from)?, self.nodes.get(&edge.to)?);
+        Some(link_curve(world_to_screen(n1.output_port()), world_to_screen(n2.input_port())))
+    }
+
+    /// The edge whose curve passes closest to `screen_pos`, within `EDGE_HIT_RADIUS` screen pixels.
+    pub fn edge_at(&self, screen_pos: Pos2, world_to_screen: impl Fn(Pos2) -> Pos2 + Copy) -> Option<u64> {
+        self.edges.iter()
+            .filter_map(|e| Some((e.id, distance_to_bezier(&self.edge_curve(e, world_to_screen)?, screen_pos))))
+            .filter(|&(_, d)| d <= EDGE_HIT_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+
+    /// Centers the camera on `world` and zooms so it fills `fill` of a `viewport`-sized canvas.
+    pub fn frame_rect(&mut self, world: Rect, viewport: Vec2, fill: f32, max_zoom: f32) {
+        self.camera_offset = world.center().to_vec2();
+        let zoom = (viewport.x * fill / world.width().max(1.0)).min(viewport.y * fill / world.height().max(1.0));
+        self.camera_zoom = zoom.clamp(MIN_ZOOM, max_zoom.min(MAX_ZOOM));
+    }
+
+    /// Sets the zoom, clamped, keeping the world point under the screen position `anchor` where it is.
+    pub fn zoom_at(&mut self, zoom: f32, anchor: Pos2, canvas_rect: Rect) {
+        let world = self.camera_offset + (anchor - canvas_rect.center()) / self.camera_zoom;
+        self.camera_zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.camera_offset = world - (anchor - canvas_rect.center()) / self.camera_zoom;
+    }
+
+    /// 100% zoom centered on the average node center.
+    pub fn reset_zoom(&mut self) {
+        let centers: Vec<Pos2> = self.nodes.values().map(|n| n.bounds().center()).collect();
+        self.camera_offset = if centers.is_empty() { Vec2::ZERO } else { centers.iter().fold(Vec2::ZERO, |sum, c| sum + c.to_vec2()) / centers.len() as f32 };
+        self.camera_zoom = 1.0;
+    }
+
+    pub fn fit_all(&mut self, viewport: Vec2) {
+        match self.nodes.values().map(|n| n.bounds()).reduce(|a, b| a.union(b)) {
+            Some(bounds) => self.frame_rect(bounds, viewport, 0.9, FIT_MAX_ZOOM),
+            None => { self.camera_offset = Vec2::ZERO; self.camera_zoom = 1.0; }
         }
     }
+
+    /// Frames the selected nodes so they fill ~70% of the viewport. Returns false when nothing is selected.
+    pub fn zoom_to_selection(&mut self, viewport: Vec2) -> bool {
+        let Some(bounds) = self.nodes.values().filter(|n| n.selected).map(|n| n.bounds()).reduce(|a, b| a.union(b)) else { return false };
+        self.frame_rect(bounds, viewport, 0.7, MAX_ZOOM);
+        true
+    }
+
+    pub fn to_project(&self, name: &str) -> Project {
+        let mut nodes: Vec<Node> = self.nodes.values().cloned().collect();
+        nodes.sort_by_key(|n| n.id);
+        Project { name: name.to_string(), nodes, edges: self.edges.clone(), groups: self.groups.clone(), annotations: self.annotations.clone(), draw_order: self.ordered_ids(), boards: Vec::new() }
+    }
+}
+
+#[cfg(test)]
+impl CanvasState {
+    /// A board for tests: a default node of each kind under its id, linked as `link_all` does.
+    pub fn graph(kinds: &[(u64, NodeKind)], pairs: &[(u64, u64)]) -> Self {
+        let mut state = Self::default();
+        for &(id, kind) in kinds { state.nodes.insert(id, Node::new(id, Default::default(), kind.default_data())); }
+        state.link_all(pairs);
+        state
+    }
+
+    /// Links `from → to` for each pair, edge ids counting up from 100 past the edges already there.
+    pub fn link_all(&mut self, pairs: &[(u64, u64)]) {
+        for &(from, to) in pairs { let id = 100 + self.edges.len() as u64; self.edges.push(Edge { id, from, to, label: None, role: None }); }
+    }
+}
+
+pub const MIN_ZOOM: f32 = 0.05;
+/// Lowest zoom the camera settles at when jumping to a node from the sidebar.
+const READABLE_ZOOM: f32 = 0.8;
+/// Closest the presentation camera zooms in on a small node.
+const PRESENT_MAX_ZOOM: f32 = 1.5;
+/// Zoom factor of one keyboard +/− press.
+const ZOOM_STEP: f32 = 1.25;
+
+/// An eased camera move between two offset/zoom pairs.
+struct CameraTween {
+    from: (Vec2, f32),
+    to: (Vec2, f32),
+    started: Instant,
+    secs: f32,
+}
+
+/// Full-window view of a Visual node's image, panned and zoomed independently of the canvas.
+struct ImagePreview {
+    id: u64,
+    /// Image center relative to the view center, in screen points.
+    offset: Vec2,
+    /// 1.0 shows the image at its native pixel size.
+    zoom: f32,
+}
+
+/// Floating node-type picker: where the node will go, the filter text and the highlighted entry.
+struct NodePalette {
+    world: Pos2,
+    filter: String,
+    highlighted: usize,
+}
+
+/// True when every character of `query` appears in `text` in order, ignoring case.
+fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut chars = text.chars().flat_map(char::to_lowercase);
+    query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()).all(|q| chars.any(|c| c == q))
+}
+
+pub const MAX_ZOOM: f32 = 5.0;
+/// Upper zoom bound when framing nodes, so a lone small node isn't blown up to `MAX_ZOOM`.
+const FIT_MAX_ZOOM: f32 = 1.5;
+const EDGE_HIT_RADIUS: f32 = 8.0;
+const EDGE_DELETE_RADIUS: f32 = 8.0;
+
+/// Horizontal-tangent bezier between two screen points, the shape every link is drawn with.
+fn link_curve(p1: Pos2, p2: Pos2) -> [Pos2; 4] {
+    let cp_dist = (p2.x - p1.x).abs() * 0.5;
+    [p1, p1 + Vec2::new(cp_dist, 0.0), p2 - Vec2::new(cp_dist, 0.0), p2]
+}
+
+/// Triangle pointing along the curve's end tangent, its tip pulled back so the input port circle doesn't cover it.
+fn arrowhead(points: &[Pos2; 4], size: f32) -> [Pos2; 3] {
+    let mut dir = points[3] - points[2];
+    if dir.length_sq() < 1e-3 { dir = points[3] - bezier_point(points, 0.9); }
+    let dir = if dir.length_sq() < 1e-6 { Vec2::X } else { dir.normalized() };
+    let tip = points[3] - dir * PORT_RADIUS;
+    let base = tip - dir * size;
+    let side = dir.rot90() * (size * 0.5);
+    [tip, base + side, base - side]
+}
+
+fn bezier_point(points: &[Pos2; 4], t: f32) -> Pos2 {
+    let u = 1.0 - t;
+    (points[0].to_vec2() * (u * u * u) + points[1].to_vec2() * (3.0 * u * u * t) + points[2].to_vec2() * (3.0 * u * t * t) + points[3].to_vec2() * (t * t * t)).to_pos2()
+}
+
+fn distance_to_segment(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_sq() > 0.0 { ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0) } else { 0.0 };
+    (a + ab * t).distance(p)
+}
+
+/// Distance from `p` to the curve, approximated by sampling it as a polyline.
+fn distance_to_bezier(points: &[Pos2; 4], p: Pos2) -> f32 {
+    const SAMPLES: usize = 24;
+    let mut prev = points[0];
+    let mut best = f32::INFINITY;
+    for i in 1..=SAMPLES {
+        let next = bezier_point(points, i as f32 / SAMPLES as f32);
+        best = best.min(distance_to_segment(p, prev, next));
+        prev = next;
+    }
+    best
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Project {
+    pub name: String,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Node ids back to front, so overlapping nodes keep their stacking.
+    #[serde(default)]
+    pub draw_order: Vec<u64>,
+    /// Set when several boards were merged into this project.
+    #[serde(default)]
+    pub boards: Vec<BoardSummary>,
 }
 
 #[derive(PartialEq)]
 enum AppState { Intro, Editing }
 
+const MINIMAP_SIZE: Vec2 = Vec2::new(200.0, 140.0);
+const SETTINGS_KEY: &str = "storyboard_settings";
+/// Longer pasted text is cut off with an ellipsis.
+const PASTE_MAX_CHARS: usize = 2000;
+/// How long a toast stays on screen.
+const TOAST_SECS: f32 = 4.0;
+/// Screen distance within which a dragged node snaps into line with another.
+const ALIGN_SNAP_DISTANCE: f32 = 6.0;
+/// Below this speed (world units per frame) a node is considered at rest and may be snapped.
+const SNAP_REST_SPEED: f32 = 0.5;
+/// World-space spacing of the background grid; every fifth line is drawn heavier.
+const BG_GRID_MINOR: f32 = 50.0;
+const BG_GRID_MAJOR: f32 = 250.0;
+
+/// Paints the world-space background grid, only the lines crossing `canvas_rect`. Lines fade out as they get closer than ~20px on screen.
+fn draw_background_grid(painter: &egui::Painter, theme: &Theme, canvas_rect: Rect, world_to_screen: impl Fn(Pos2) -> Pos2, screen_to_world: impl Fn(Pos2) -> Pos2, zoom: f32) {
+    let (min, max) = (screen_to_world(canvas_rect.min), screen_to_world(canvas_rect.max));
+    for (step, weight) in [(BG_GRID_MINOR, 0.5), (BG_GRID_MAJOR, 1.0)] {
+        let alpha = ((step * zoom - 6.0) / 14.0).clamp(0.0, 1.0);
+        if alpha <= 0.0 { continue; }
+        let stroke = Stroke::new(1.0, theme.canvas_bg.lerp_to_gamma(theme.grid, weight * alpha));
+        let mut x = (min.x / step).ceil() * step;
+        while x <= max.x {
+            // Minor lines that coincide with a major line are drawn in the second pass.
+            if step == BG_GRID_MAJOR || (x / BG_GRID_MAJOR).fract() != 0.0 { let sx = world_to_screen(Pos2::new(x, 0.0)).x; painter.vline(sx, canvas_rect.y_range(), stroke); }
+            x += step;
+        }
+        let mut y = (min.y / step).ceil() * step;
+        while y <= max.y {
+            if step == BG_GRID_MAJOR || (y / BG_GRID_MAJOR).fract() != 0.0 { let sy = world_to_screen(Pos2::new(0.0, y)).y; painter.hline(canvas_rect.x_range(), sy, stroke); }
+            y += step;
+        }
+    }
+}
+
+/// User preferences persisted through eframe storage.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub snap_to_grid: bool,
+    /// Snap dragged nodes into line with nearby nodes and show guides.
+    pub align_guides: bool,
+    pub grid_size: f32,
+    pub physics_enabled: bool,
+    pub show_grid: bool,
+    /// Bottom strip of Frame thumbnails in sequence order.
+    pub show_timeline: bool,
+    /// Sidebar shrunk to an icon rail.
+    pub sidebar_collapsed: bool,
+    /// Include sticky notes in the text sent to the PDF export.
+    pub export_notes: bool,
+    /// Link nodes added from the sidebar to the single selected node.
+    pub auto_connect: bool,
+    /// Retry failed requests automatically, backing off 1s, 2s and 4s.
+    pub auto_retry: bool,
+    /// Requests allowed in flight at once; the rest wait in `StoryBoardApp::request_queue`.
+    pub max_concurrent: usize,
+    /// Include earlier results of Research and AI nodes in the text sent to the PDF export.
+    pub export_history: bool,
+    /// Execution log panel shrunk to its header row.
+    pub log_collapsed: bool,
+    /// Byte budget of the response cache in MB; 0 turns caching off.
+    pub cache_mb: usize,
+    /// Minutes a cached response is reused for.
+    pub cache_ttl_mins: u32,
+    pub theme: ThemeKind,
+    pub scroll_mode: ScrollMode,
+}
+
+/// What the mouse wheel or two-finger trackpad scroll does on the canvas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ScrollMode {
+    #[default]
+    Zoom,
+    /// Scroll pans; Ctrl/Cmd+scroll zooms.
+    Pan,
+}
+
+impl ScrollMode {
+    pub fn label(self) -> &'static str {
+        match self { Self::Zoom => "Zoom on scroll", Self::Pan => "Pan on scroll / zoom on Ctrl+scroll" }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { snap_to_grid: false, align_guides: true, grid_size: 25.0, physics_enabled: true, show_grid: true, show_timeline: false, sidebar_collapsed: false, export_notes: false, auto_connect: true, auto_retry: false, max_concurrent: queue::default_max_concurrent(), export_history: false, log_collapsed: true, cache_mb: cache::default_cache_mb(), cache_ttl_mins: cache::default_cache_ttl_mins(), theme: ThemeKind::Dark, scroll_mode: ScrollMode::Zoom }
+    }
+}
+
+impl Settings {
+    pub fn snap(&self, pos: Pos2) -> Pos2 {
+        let g = self.grid_size.max(1.0);
+        Pos2::new((pos.x / g).round() * g, (pos.y / g).round() * g)
+    }
+}
+
 pub struct StoryBoardApp {
+    /// The active board's canvas.
     state: CanvasState,
+    boards: Vec<Board>,
+    active_board: usize,
+    /// Board whose tab is being renamed, with the draft name.
+    renaming_board: Option<(usize, String)>,
+    project_name: String,
+    settings: Settings,
+    shortcuts: Shortcuts,
+    show_shortcuts: bool,
+    /// The "Graph check" window is open.
+    show_validation: bool,
     app_state: AppState,
     intro_animation: f32,
+    /// World-space anchor of an in-progress Shift+drag selection rectangle.
     selection_start: Option<Pos2>,
-    selected_nodes: HashSet<u64>,
+    /// Node the canvas context menu was opened on.
+    context_node: Option<u64>,
+    /// Edge the canvas context menu was opened on, when no node was under the pointer.
+    context_edge: Option<u64>,
+    /// Sticky note the canvas context menu was opened on, when neither a node nor an edge was under the pointer.
+    context_note: Option<u64>,
+    /// A note as it was when its text field took focus; the edit is recorded as one undo step when focus leaves.
+    note_text_before: Option<Annotation>,
+    /// Edge whose label is being edited, with the draft text.
+    editing_edge_label: Option<(u64, String)>,
+    /// Node whose header title is being edited, with the draft text.
+    editing_title: Option<(u64, String)>,
+    /// Group whose title is being edited, with the draft text.
+    editing_group_title: Option<(u64, String)>,
+    /// Last lineage computed for Alt-hover: node id, `CanvasState.graph_version` it was computed at, and the lit nodes.
+    lineage_cache: Option<(u64, u64, HashSet<u64>)>,
+    /// Node whose main text field should grab keyboard focus the next time it is drawn.
+    pending_text_focus: Option<u64>,
+    /// Quick-add palette opened by Shift+A, Alt+double-click or the canvas menu.
+    node_palette: Option<NodePalette>,
+    /// World position of the last right-click on empty canvas, where the menu's "Add node" puts it.
+    context_world: Option<Pos2>,
+    /// Edge whose row in the sidebar connection list is hovered; its curve is drawn highlighted.
+    sidebar_edge: Option<u64>,
+    /// Case-insensitive text filter for the sidebar node list.
+    node_filter: String,
+    /// Node types hidden from the sidebar node list.
+    hidden_kinds: Vec<NodeKind>,
+    /// Screen rect of the canvas from the last frame, for camera commands issued outside the central panel.
+    canvas_rect: Rect,
+    /// In-flight camera animation: start offset/zoom, target offset/zoom, start time.
+    camera_tween: Option<CameraTween>,
+    layout_animation: Option<LayoutAnimation>,
+    image_preview: Option<ImagePreview>,
+    /// Node whose "Preview request" window is open.
+    request_preview: Option<u64>,
+    /// Canvas node under the pointer and when the pointer arrived on it, for the delayed result preview.
+    hover_preview: Option<(u64, Instant)>,
+    /// Set while presenting: the sidebar is hidden, the canvas is read-only and the arrow keys step through nodes.
+    presentation: Option<Presentation>,
+    /// Set while "Run Pipeline" is working through the graph.
+    pipeline: Option<PipelineRun>,
+    /// Outcome of the last run, shown under the pipeline controls until dismissed.
+    pipeline_summary: Option<PipelineSummary>,
+    /// When each Research node with a refresh interval on the active board re-runs next.
+    refresh_due: HashMap<u64, Instant>,
+    /// Plays Audio nodes' clips, one at a time.
+    player: audio::Player,
+    /// Time of the last `step_refresh`, to notice frames the window skipped.
+    refresh_clock: Instant,
+    /// Requests made for nodes this session, oldest first; not saved.
+    run_log: Vec<runlog::LogEntry>,
+    /// Models the local Ollama offers to Agnostic AI nodes switched to it.
+    local_models: local_ai::LocalModels,
+    /// Templates saved from selections, persisted through eframe storage.
+    templates: Vec<PipelineTemplate>,
+    /// Name typed for the next "Save selection as template".
+    template_name: String,
+    /// Recent successful node responses, reused for identical requests.
+    cache: cache::ResponseCache,
+    /// Nodes whose requests skip the cache while "Force refresh" runs them.
+    bypass_cache: HashSet<u64>,
+    /// Failed nodes waiting on, or running, an automatic retry.
+    retries: HashMap<u64, Retry>,
+    /// Set when a result or an input was edited; staleness is re-checked on the next frame.
+    stale_check: bool,
+    /// Nodes showing an earlier result instead of the current one.
+    version_views: HashMap<u64, VersionView>,
+    /// Positions of the nodes being moved with the arrow keys, from before the first nudge; committed as one undo step on key release.
+    nudge_origins: Option<Vec<(u64, Pos2)>>,
+    /// Short messages shown at the bottom of the canvas, with when they were raised.
+    toasts: Vec<(String, Instant)>,
     http_rx: mpsc::Receiver<AppMessage>,
     http_tx: mpsc::Sender<AppMessage>,
-    frame_times: Vec<f32>,
+    /// Requests sent by `post_json` that haven't answered yet.
+    in_flight: Arc<AtomicUsize>,
+    /// Requests waiting for one of the `settings.max_concurrent` slots.
+    request_queue: VecDeque<QueuedRequest>,
+    perf: PerfStats,
+}
+
+/// One paragraph of an "Include everything" export for node `n`, or `None` when it has nothing to add.
+fn export_line(n: &Node) -> Option<String> {
+    match &n.data {
+        NodeData::Concept { text } => Some(format!("Concept: {}\n\n", text)),
+        NodeData::YouComResearch { query, result, .. } => Some(format!("Research ({}): {}\n\n", query, result.as_deref().unwrap_or("None"))),
+        NodeData::AgnosticAI { model, prompt, result, .. } => Some(format!("AI ({}, {}): {}\n\n", model, prompt, result.as_deref().unwrap_or("None"))),
+        NodeData::Merge { output, .. } if !output.trim().is_empty() => Some(format!("Merge: {}\n\n", output)),
+        NodeData::Compare { output, .. } if !output.trim().is_empty() => Some(format!("Compare (picked): {}\n\n", output)),
+        NodeData::Select { output, .. } if !output.trim().is_empty() => Some(format!("Select (chosen): {}\n\n", output)),
+        NodeData::Note { body, pass_on: true, .. } if !body.trim().is_empty() => Some(format!("Note ({}): {}\n\n", n.display_title(), notes::render_body(body))),
+        NodeData::Audio { voice, text, .. } if !text.trim().is_empty() => Some(format!("Narration ({}): {}\n\n", voice, text)),
+        NodeData::Script { output, .. } if !output.trim().is_empty() => Some(format!("Script:\n{}\n\n", output)),
+        NodeData::Character { name, description, .. } if !description.trim().is_empty() => Some(format!("Character ({}): {}\n\n", name, description)),
+        NodeData::Translate { target_lang, result: Some(result), .. } => Some(format!("Translation ({}): {}\n\n", target_lang, result)),
+        NodeData::WebFetch { url, result: Some(result), .. } => Some(format!("Web page ({}): {}\n\n", url, result)),
+        NodeData::Transform { output, .. } if !output.trim().is_empty() => Some(format!("Transform: {}\n\n", output)),
+        NodeData::Frame { sequence_index, shot_type, caption, .. } => Some(format!("{}: {}\n\n", frame::frame_label(*sequence_index, shot_type), caption)),
+        NodeData::Transcribe { file_name, transcript, .. } if !transcript.trim().is_empty() => Some(format!("Transcript ({}): {}\n\n", file_name, transcript)),
+        _ => None,
+    }
 }
 
 impl StoryBoardApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let (http_tx, http_rx) = mpsc::channel();
+        let settings: Settings = cc.storage.and_then(|s| eframe::get_value(s, SETTINGS_KEY)).unwrap_or_default();
+        cc.egui_ctx.set_visuals(settings.theme.visuals());
         let mut app = Self {
             state: CanvasState::default(),
+            boards: vec![Board::new("Board 1")],
+            active_board: 0,
+            renaming_board: None,
+            project_name: "Mars Colony Documentary".to_string(),
+            settings,
+            shortcuts: Shortcuts::default(),
+            show_shortcuts: false,
+            show_validation: false,
             app_state: AppState::Intro,
             intro_animation: 1.0,
             selection_start: None,
-            selected_nodes: HashSet::new(),
+            context_node: None,
+            context_edge: None,
+            context_note: None,
+            note_text_before: None,
+            editing_edge_label: None,
+            editing_title: None,
+            editing_group_title: None,
+            lineage_cache: None,
+            pending_text_focus: None,
+            node_palette: None,
+            context_world: None,
+            sidebar_edge: None,
+            node_filter: String::new(),
+            hidden_kinds: Vec::new(),
+            canvas_rect: Rect::from_min_size(Pos2::ZERO, Vec2::new(1200.0, 800.0)),
+            camera_tween: None,
+            layout_animation: None,
+            image_preview: None,
+            request_preview: None,
+            hover_preview: None,
+            presentation: None,
+            pipeline: None,
+            pipeline_summary: None,
+            refresh_due: HashMap::new(),
+            player: audio::Player::default(),
+            refresh_clock: Instant::now(),
+            run_log: Vec::new(),
+            local_models: local_ai::LocalModels::Unknown,
+            templates: cc.storage.and_then(|s| eframe::get_value(s, TEMPLATES_KEY)).unwrap_or_default(),
+            template_name: String::new(),
+            cache: cache::ResponseCache::default(),
+            bypass_cache: HashSet::new(),
+            nudge_origins: None,
+            toasts: Vec::new(),
+            retries: HashMap::new(),
+            stale_check: false,
+            version_views: HashMap::new(),
             http_rx,
             http_tx,
-            frame_times: Vec::new(),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            request_queue: VecDeque::new(),
+            perf: PerfStats::default(),
         };
         app.setup_demo_scene();
         app
     }
 
+    /// Forgets pointer, editing and camera state tied to the nodes of the board being left.
+    fn reset_transient_ui(&mut self) {
+        self.selection_start = None;
+        (self.context_node, self.context_edge, self.context_note) = (None, None, None);
+        self.note_text_before = None;
+        (self.editing_edge_label, self.editing_title, self.editing_group_title) = (None, None, None);
+        self.lineage_cache = None;
+        self.pending_text_focus = None;
+        self.node_palette = None;
+        self.sidebar_edge = None;
+        self.camera_tween = None;
+        self.layout_animation = None;
+        self.image_preview = None;
+        self.hover_preview = None;
+        self.presentation = None;
+        self.stop_pipeline();
+        self.nudge_origins = None;
+    }
+
+    /// Opens the session on the first built-in template.
     fn setup_demo_scene(&mut self) {
-        let c1_id = self.add_node(Pos2::new(-450.0, 0.0), NodeData::Concept { text: "Mars Colony Documentary".to_string() });
-        let r1_id = self.add_node(Pos2::new(-150.0, -150.0), NodeData::YouComResearch { query: "Mars colony life".to_string(), result: None, is_loading: false });
-        let a1_id = self.add_node(Pos2::new(150.0, -150.0), NodeData::AgnosticAI { model: "google/gemini-flash-1.5".to_string(), prompt: "Write script based on Mars research".to_string(), result: None, is_loading: false });
-        let p1_id = self.add_node(Pos2::new(450.0, 0.0), NodeData::Visual { prompt: "Mars base interior".to_string(), texture: None, is_loading: false });
-        let f1_id = self.add_node(Pos2::new(0.0, 250.0), NodeData::FoxitExport { status: "Ready".to_string(), is_loading: false });
-        self.state.edges.push(Edge { id: 1, from: c1_id, to: r1_id });
-        self.state.edges.push(Edge { id: 2, from: r1_id, to: a1_id });
-        self.state.edges.push(Edge { id: 3, from: a1_id, to: p1_id });
-        self.state.edges.push(Edge { id: 4, from: p1_id, to: f1_id });
-    }
-
-    fn add_node(&mut self, pos: Pos2, data: NodeData) -> u64 {
+        if let Some(demo) = presets::builtin_templates().first() { self.state.instantiate(demo, Pos2::ZERO); }
+        for node in self.state.nodes.values_mut() { node.selected = false; }
+        self.state.history.clear();
+    }
+
+    fn add_node(&mut self, pos: Pos2, mut data: NodeData) -> u64 {
+        if let NodeData::Frame { sequence_index, .. } = &mut data { *sequence_index = self.state.next_frame_index(); }
         let id = self.state.next_id;
-        self.state.nodes.insert(id, Node::new(id, pos, data));
         self.state.next_id += 1;
+        self.state.execute(Command::AddNode(Node::new(id, pos, data)));
         id
     }
 
-    fn trigger_research(&self, node_id: u64, query: String, ctx: egui::Context) {
-        let tx = self.http_tx.clone();
-        let body = serde_json::json!({"query": query});
-        let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
-        let mut request = ehttp::Request::post("/api/research", body_bytes);
-        request.headers.insert("Content-Type", "application/json");
-        ehttp::fetch(request, move |result| {
-            if let Ok(response) = result { let _ = tx.send(AppMessage::TextResponse(node_id, response.text().unwrap_or_default().to_string())); }
-            ctx.request_repaint();
-        });
+    /// Adds a node of `kind` with its default content at the camera center. With auto-connect on and exactly one node selected,
+    /// it goes to the right of that node instead, linked from it and selected so the next one continues the chain.
+    fn create_node(&mut self, kind: NodeKind, connect: bool) -> u64 {
+        let mut data = kind.default_data();
+        if let NodeData::Frame { sequence_index, .. } = &mut data { *sequence_index = self.state.next_frame_index(); }
+        let parent = match self.state.selected_ids()[..] { [id] if connect && self.settings.auto_connect => self.state.nodes.get(&id).filter(|n| ports::port_mismatch(&n.data, &data).is_none()).map(|n| (id, n.bounds())), _ => None };
+        let Some((parent, bounds)) = parent else { return self.add_node(self.state.camera_offset.to_pos2(), data) };
+        let id = self.state.next_id;
+        let mut node = Node::new(id, bounds.right_top() + Vec2::new(AUTO_CONNECT_GAP, 0.0), data);
+        while self.state.nodes.values().any(|n| n.bounds().intersects(node.bounds())) { node.position.y += AUTO_CONNECT_GAP; }
+        let edge = Edge { id: id + 1, from: parent, to: id, label: None, role: None };
+        self.state.next_id += 2;
+        self.state.execute(Command::Batch(vec![Command::AddNode(node), Command::AddEdge(edge)]));
+        self.state.select_only(id);
+        id
     }
 
-    fn trigger_visualize(&self, node_id: u64, prompt: String, ctx: egui::Context) {
-        let tx = self.http_tx.clone();
-        let body = serde_json::json!({"prompt": prompt});
-        let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
-        let mut request = ehttp::Request::post("/api/visualize", body_bytes);
-        request.headers.insert("Content-Type", "application/json");
-        ehttp::fetch(request, move |result| {
-            if let Ok(response) = result { let _ = tx.send(AppMessage::ImageResponse(node_id, response.bytes)); }
-            ctx.request_repaint();
-        });
+    /// Starts moving the camera towards `offset`/`zoom`; manual pan or zoom cancels it.
+    fn animate_camera(&mut self, offset: Vec2, zoom: f32, secs: f32) {
+        self.camera_tween = Some(CameraTween { from: (self.state.camera_offset, self.state.camera_zoom), to: (offset, zoom.clamp(MIN_ZOOM, MAX_ZOOM)), started: Instant::now(), secs });
     }
 
-    fn trigger_agnostic_ai(&self, node_id: u64, model: String, prompt: String, ctx: egui::Context) {
-        let tx = self.http_tx.clone();
-        let body = serde_json::json!({"model": model, "prompt": prompt});
-        let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
-        let mut request = ehttp::Request::post("/api/agnostic-ai", body_bytes);
-        request.headers.insert("Content-Type", "application/json");
-        ehttp::fetch(request, move |result| {
-            if let Ok(response) = result { let _ = tx.send(AppMessage::TextResponse(node_id, response.text().unwrap_or_default().to_string())); }
-            ctx.request_repaint();
-        });
+    fn zoom_to_selection(&mut self) {
+        let from = (self.state.camera_offset, self.state.camera_zoom);
+        if !self.state.zoom_to_selection(self.canvas_rect.size()) { return; }
+        let to = (self.state.camera_offset, self.state.camera_zoom);
+        (self.state.camera_offset, self.state.camera_zoom) = from;
+        self.animate_camera(to.0, to.1, 0.2);
     }
 
-    fn trigger_foxit(&self, node_id: u64, all_text: String, ctx: egui::Context) {
-        let tx = self.http_tx.clone();
-        let body = serde_json::json!({"all_node_text": all_text});
-        let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
-        let mut request = ehttp::Request::post("/api/foxit", body_bytes);
-        request.headers.insert("Content-Type", "application/json");
-        ehttp::fetch(request, move |result| {
-            if let Ok(resp) = result { let _ = tx.send(AppMessage::TextResponse(node_id, resp.text().unwrap_or_default().to_string())); }
-            ctx.request_repaint();
-        });
+    /// Centers `id` at a readable zoom.
+    fn focus_camera_on(&mut self, id: u64) {
+        let Some(center) = self.state.nodes.get(&id).map(|n| n.bounds().center()) else { return };
+        self.animate_camera(center.to_vec2(), self.state.camera_zoom.max(READABLE_ZOOM), 0.35);
     }
 
-    fn apply_physics(&mut self) {
-        let repulsion = 6000.0;
-        let attraction = 0.02;
-        let damping = 0.8;
-        let node_ids: Vec<u64> = self.state.nodes.keys().copied().collect();
-        let mut forces: HashMap<u64, Vec2> = node_ids.iter().map(|&id| (id, Vec2::ZERO)).collect();
-        for &i in &node_ids {
-            for &j in &node_ids {
-                if i == j { continue; }
-                let pos_i = self.state.nodes[&i].position;
-                let pos_j = self.state.nodes[&j].position;
-                let delta = pos_i - pos_j;
-                let dist = delta.length().max(100.0);
-                let force = delta.normalized() * (repulsion / (dist * dist));
-                *forces.get_mut(&i).unwrap() += force;
-            }
-        }
-        for edge in &self.state.edges {
-            if let (Some(n1), Some(n2)) = (self.state.nodes.get(&edge.from), self.state.nodes.get(&edge.to)) {
-                let delta = n2.position - n1.position;
-                let dist = delta.length();
-                let force = delta.normalized() * (dist - 400.0) * attraction;
-                *forces.get_mut(&edge.from).unwrap() += force;
-                *forces.get_mut(&edge.to).unwrap() -= force;
-            }
-        }
-        for id in node_ids {
-            if let Some(node) = self.state.nodes.get_mut(&id) {
-                if self.state.dragging_node == Some(id) { continue; }
-                node.velocity = (node.velocity + forces[&id]) * damping;
-                node.position += node.velocity;
+    fn step_camera_tween(&mut self, ctx: &egui::Context) {
+        let Some(tween) = &self.camera_tween else { return };
+        let t = (tween.started.elapsed().as_secs_f32() / tween.secs).min(1.0);
+        let eased = if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 };
+        self.state.camera_offset = tween.from.0 + (tween.to.0 - tween.from.0) * eased;
+        self.state.camera_zoom = tween.from.1 + (tween.to.1 - tween.from.1) * eased;
+        if t < 1.0 { ctx.request_repaint(); } else { self.camera_tween = None; }
+    }
+
+    /// Turning physics back on starts every node at rest, so velocities left over from before don't fling them.
+    fn set_physics_enabled(&mut self, enabled: bool) {
+        if enabled && !self.settings.physics_enabled { for node in self.state.nodes.values_mut() { node.velocity = Vec2::ZERO; } }
+        self.settings.physics_enabled = enabled;
+    }
+
+    fn cycle_focus(&mut self, ctx: &egui::Context, forward: bool) {
+        // Tab would otherwise also walk egui's focus through the sidebar buttons.
+        if let Some(id) = ctx.memory(|m| m.focused()) { ctx.memory_mut(|m| m.surrender_focus(id)); }
+        let Some(id) = self.state.cycle_focus(forward) else { return };
+        let visible = Rect::from_center_size(self.state.camera_offset.to_pos2(), self.canvas_rect.size() / self.state.camera_zoom);
+        if !visible.contains_rect(self.state.nodes[&id].bounds()) { self.focus_camera_on(id); }
+    }
+
+    fn run_action(&mut self, ctx: &egui::Context, action: Action) {
+        match action {
+            Action::AddNode(kind) => { self.create_node(kind, true); }
+            Action::DeleteSelection => {
+                let selected = self.state.selected_ids();
+                if !selected.is_empty() { self.state.remove_nodes(&selected); }
+                else if let Some(edge_id) = self.state.selected_edge { self.state.remove_edge(edge_id); }
             }
+            Action::FitView => self.state.fit_all(self.canvas_rect.size()),
+            Action::ZoomIn => { self.state.zoom_at(self.state.camera_zoom * ZOOM_STEP, self.canvas_rect.center(), self.canvas_rect); self.camera_tween = None; }
+            Action::ZoomOut => { self.state.zoom_at(self.state.camera_zoom / ZOOM_STEP, self.canvas_rect.center(), self.canvas_rect); self.camera_tween = None; }
+            Action::ResetZoom => { self.state.reset_zoom(); self.camera_tween = None; }
+            Action::ZoomToSelection => self.zoom_to_selection(),
+            Action::TogglePhysics => self.set_physics_enabled(!self.settings.physics_enabled),
+            Action::Undo => self.state.undo(),
+            Action::Redo => self.state.redo(),
+            Action::ShowShortcuts => self.show_shortcuts = !self.show_shortcuts,
+            Action::FocusNext => self.cycle_focus(ctx, true),
+            Action::FocusPrevious => self.cycle_focus(ctx, false),
+            Action::EditFocused => self.pending_text_focus = self.state.focused_node,
+            Action::QuickAdd => { let world = self.pointer_world(ctx); self.open_palette(world); }
+            Action::Present => self.start_presentation(),
+            Action::RunPipeline => if self.pipeline.is_some() { self.finish_pipeline(Some("cancelled".to_string())); } else { self.start_pipeline(); },
         }
     }
 
-    fn draw_intro_screen(&mut self, ctx: &egui::Context) {
-        let bg_color = Color32::from_rgba_premultiplied(10, 10, 10, (self.intro_animation * 230.0) as u8);
-        
-        // 1. Draw background overlay using a high-order painter
-        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("intro_bg")));
-        painter.rect_filled(ctx.screen_rect(), 0.0, bg_color);
+    fn start_presentation(&mut self) {
+        let steps = self.state.presentation_order();
+        if steps.is_empty() { self.toast("Add some nodes before presenting"); return; }
+        self.editing_title = None;
+        self.node_palette = None;
+        self.presentation = Some(Presentation { steps, index: 0, restore: (self.state.camera_offset, self.state.camera_zoom) });
+        self.present_step(0);
+    }
 
-        // 2. Draw the interactive panel in a separate Area
-        egui::Area::new("intro_panel".into())
-            .anchor(egui::Align2::CENTER_CENTER, Vec2::new(0.0, (1.0 - self.intro_animation) * -100.0))
-            .order(egui::Order::Foreground)
-            .interactable(true)
-            .show(ctx, |ui| {
-                Frame::none()
-                    .inner_margin(40.0)
-                    .rounding(16.0)
-                    .fill(Color32::from_gray(30).linear_multiply(self.intro_animation))
-                    .stroke(Stroke::new(1.0, Color32::from_gray(60).linear_multiply(self.intro_animation)))
-                    .show(ui, |ui| {
-                        ui.vertical_centered(|ui| {
-                            ui.heading(egui::RichText::new("🎬 StoryBoard AI").size(32.0).color(Color32::WHITE.linear_multiply(self.intro_animation)));
-                            ui.add_space(10.0);
-                            ui.label(egui::RichText::new("The Visual IDE for AI Storytelling").size(18.0).color(Color32::GRAY.linear_multiply(self.intro_animation)));
-                            ui.add_space(30.0);
-                            
-                            let button = egui::Button::new(egui::RichText::new("✨ Start New Flow").size(20.0).color(Color32::WHITE))
-                                .fill(Color32::from_rgb(40, 120, 200).linear_multiply(self.intro_animation))
+    /// Moves to step `index`, clamped, and eases the camera onto its node.
+    fn present_step(&mut self, index: usize) {
+        let Some(p) = self.presentation.as_mut() else { return };
+        p.index = index.min(p.steps.len() - 1);
+        let Some(bounds) = p.current().and_then(|id| self.state.nodes.get(&id)).map(|n| n.bounds()) else { return };
+        // The card covers the lower half of the canvas, so the node is framed in the upper half.
+        let viewport = self.canvas_rect.size() * Vec2::new(1.0, 0.5);
+        let zoom = (viewport.x * 0.8 / bounds.width()).min(viewport.y * 0.8 / bounds.height()).clamp(READABLE_ZOOM, PRESENT_MAX_ZOOM);
+        self.animate_camera(bounds.center().to_vec2() + Vec2::new(0.0, self.canvas_rect.height() * 0.25 / zoom), zoom, 0.45);
+    }
+
+    fn stop_presentation(&mut self) {
+        if let Some(p) = self.presentation.take() { self.animate_camera(p.restore.0, p.restore.1, 0.35); }
+    }
+
+    fn presentation_input(&mut self, ctx: &egui::Context) {
+        let Some(index) = self.presentation.as_ref().map(|p| p.index) else { return };
+        let pressed = |keys: &[egui::Key]| ctx.input_mut(|i| keys.iter().any(|&k| i.consume_key(egui::Modifiers::NONE, k)));
+        if pressed(&[egui::Key::Escape, egui::Key::P]) { self.stop_presentation(); }
+        else if pressed(&[egui::Key::ArrowRight, egui::Key::ArrowDown, egui::Key::Space, egui::Key::PageDown]) { self.present_step(index + 1); }
+        else if pressed(&[egui::Key::ArrowLeft, egui::Key::ArrowUp, egui::Key::PageUp]) { self.present_step(index.saturating_sub(1)); }
+    }
+
+    /// Card at the bottom of the canvas showing the current step's image or result text, with step navigation.
+    fn draw_presentation(&mut self, ctx: &egui::Context) {
+        let Some(p) = &self.presentation else { return };
+        let (index, count) = (p.index, p.steps.len());
+        let Some(node) = p.current().and_then(|id| self.state.nodes.get(&id)) else { self.presentation = None; return };
+        let theme = self.settings.theme.palette();
+        let width = (self.canvas_rect.width() - 40.0).min(720.0);
+        let body_height = self.canvas_rect.height() * 0.4;
+        let mut step = None;
+        let mut exit = false;
+        egui::Area::new(egui::Id::new("presentation")).anchor(egui::Align2::CENTER_BOTTOM, Vec2::new(self.canvas_rect.center().x - ctx.screen_rect().center().x, -24.0)).order(egui::Order::Foreground).show(ctx, |ui| {
+            Frame::popup(ui.style()).fill(theme.node_fill).stroke(Stroke::new(1.0, theme.node_border)).inner_margin(Margin::same(16.0)).show(ui, |ui| {
+                ui.set_width(width);
+                ui.horizontal(|ui| {
+                    ui.heading(egui::RichText::new(format!("{} {}", node.data.kind().icon(), node.display_title())).color(theme.text));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("✕ Exit").on_hover_text("Esc").clicked() { exit = true; }
+                        if ui.add_enabled(index + 1 < count, egui::Button::new("▶")).on_hover_text("Next (→)").clicked() { step = Some(index + 1); }
+                        ui.label(format!("{} / {}", index + 1, count));
+                        if ui.add_enabled(index > 0, egui::Button::new("◀")).on_hover_text("Previous (←)").clicked() { step = Some(index - 1); }
+                    });
+                });
+                ui.separator();
+                let (context, text) = match &node.data {
+                    NodeData::Concept { text } | NodeData::Note { body: text, .. } | NodeData::Audio { text, .. } => (None, Some(text.as_str())),
+                    NodeData::YouComResearch { query, result, .. } | NodeData::WebFetch { url: query, result, .. } => (Some(query.as_str()), result.as_deref()),
+                    NodeData::AgnosticAI { prompt, result, .. } => (Some(prompt.as_str()), result.as_deref()),
+                    NodeData::FoxitExport { status, .. } => (None, Some(status.as_str())),
+                    NodeData::Merge { output, .. } | NodeData::Compare { output, .. } | NodeData::Select { output, .. } | NodeData::Script { output, .. } | NodeData::Transform { output, .. } | NodeData::MarkdownView { text: output } => (None, Some(output.as_str())),
+                    NodeData::Branch { condition, input, .. } => (Some(condition.as_str()), Some(input.as_str())),
+                    NodeData::Character { name, description, .. } => (Some(name.as_str()), Some(description.as_str())),
+                    NodeData::Frame { sequence_index, shot_type, caption, .. } => {
+                        if let Some(tex) = self.state.frame_image(node.id).and_then(|bytes| cached_texture(ui.ctx(), node.id, bytes)) { ui.vertical_centered(|ui| { ui.add(egui::Image::new(&tex).max_size(Vec2::new(width, body_height))); }); }
+                        ui.strong(frame::frame_label(*sequence_index, shot_type));
+                        (None, Some(caption.as_str()))
+                    }
+                    NodeData::Translate { target_lang, result, .. } => (Some(target_lang.as_str()), result.as_deref()),
+                    NodeData::Transcribe { file_name, transcript, .. } => (Some(file_name.as_str()), Some(transcript.as_str())),
+                    NodeData::Visual { prompt, texture, .. } => {
+                        if let Some(tex) = texture { ui.vertical_centered(|ui| { ui.add(egui::Image::new(tex).max_size(Vec2::new(width, body_height))); }); }
+                        (Some(prompt.as_str()), None)
+                    }
+                };
+                if let Some(context) = context.filter(|c| !c.trim().is_empty()) { ui.label(egui::RichText::new(truncate(context.trim(), 200)).italics().color(theme.muted_text)); }
+                match text.filter(|t| !t.trim().is_empty()) {
+                    Some(text) => {
+                        let rendered = matches!(node.data, NodeData::YouComResearch { .. } | NodeData::AgnosticAI { .. } | NodeData::MarkdownView { .. }) && !node.raw_markdown;
+                        egui::ScrollArea::vertical().max_height(body_height).show(ui, |ui| {
+                            if rendered { markdown::render_capped(ui, egui::Id::new(("markdown_more", node.id)), text.trim(), 16.0); }
+                            else { ui.label(egui::RichText::new(text.trim()).size(16.0).color(theme.text)); }
+                        });
+                    }
+                    None if !matches!(node.data, NodeData::Visual { texture: Some(_), .. }) => { ui.weak("No result yet"); }
+                    None => {}
+                }
+            });
+        });
+        if exit { self.stop_presentation(); } else if let Some(index) = step { self.present_step(index); }
+    }
+
+    fn toast(&mut self, message: impl Into<String>) {
+        self.toasts.push((message.into(), Instant::now()));
+    }
+
+    /// Saves `bytes` as `file_name` and says in a toast whether it worked.
+    fn download(&mut self, file_name: &str, mime: &str, bytes: &[u8]) {
+        match download_bytes(file_name, mime, bytes) {
+            Ok(()) => self.toast(format!("💾 Saved {}", file_name)),
+            Err(e) => self.toast(format!("⚠ Couldn't save {}: {}", file_name, e)),
+        }
+    }
+
+    fn draw_toasts(&mut self, ctx: &egui::Context) {
+        self.toasts.retain(|(_, at)| at.elapsed().as_secs_f32() < TOAST_SECS);
+        if self.toasts.is_empty() { return; }
+        let theme = self.settings.theme.palette();
+        egui::Area::new(egui::Id::new("toasts")).anchor(egui::Align2::CENTER_BOTTOM, Vec2::new(0.0, -20.0)).order(egui::Order::Foreground).interactable(false).show(ctx, |ui| {
+            for (message, _) in &self.toasts {
+                Frame::popup(ui.style()).fill(theme.overlay).show(ui, |ui| { ui.colored_label(theme.text, message); });
+            }
+        });
+    }
+
+    /// Arrow keys move the selection by one world unit, ten with Shift; key repeat keeps it moving while held.
+    fn nudge_selection(&mut self, ctx: &egui::Context) {
+        const ARROWS: [(egui::Key, Vec2); 4] = [(egui::Key::ArrowLeft, Vec2::new(-1.0, 0.0)), (egui::Key::ArrowRight, Vec2::new(1.0, 0.0)), (egui::Key::ArrowUp, Vec2::new(0.0, -1.0)), (egui::Key::ArrowDown, Vec2::new(0.0, 1.0))];
+        let held = !ctx.wants_keyboard_input() && ctx.input(|i| ARROWS.iter().any(|(key, _)| i.key_down(*key)));
+        if !held {
+            if let Some(origins) = self.nudge_origins.take() {
+                let moves: Vec<(u64, Pos2, Pos2)> = origins.into_iter().filter_map(|(id, from)| self.state.nodes.get(&id).map(|n| (id, from, n.position))).filter(|(_, from, to)| from != to).collect();
+                if !moves.is_empty() { self.state.history.push(Command::MoveNodes(moves)); }
+            }
+            return;
+        }
+        let selected = self.state.selected_ids();
+        if selected.is_empty() { return; }
+        let step = if ctx.input(|i| i.modifiers.shift) { 10.0 } else { 1.0 };
+        let delta = ctx.input(|i| ARROWS.iter().map(|(key, dir)| *dir * i.num_presses(*key) as f32).fold(Vec2::ZERO, |a, b| a + b)) * step;
+        if delta == Vec2::ZERO { return; }
+        let origins = self.nudge_origins.get_or_insert_with(Vec::new);
+        for id in selected {
+            let Some(node) = self.state.nodes.get_mut(&id) else { continue };
+            if !origins.iter().any(|(o, _)| *o == id) { origins.push((id, node.position)); }
+            node.position += delta;
+            node.velocity = Vec2::ZERO;
+        }
+    }
+
+    /// Pasted text becomes a Concept node at the camera center, stepped aside from earlier pastes that are still there.
+    fn paste_text(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() { return; }
+        let mut pos = self.state.camera_offset.to_pos2();
+        while self.state.nodes.values().any(|n| n.position == pos) { pos += Vec2::splat(40.0); }
+        let id = self.add_node(pos, NodeData::Concept { text: truncate(text, PASTE_MAX_CHARS) });
+        self.state.select_only(id);
+    }
+
+    /// Turns image files dropped on the window into Visual nodes, fanned out from the drop point. An image dropped on a Character node
+    /// becomes its portrait instead, and one dropped on a Frame node its image. Recordings become Transcribe nodes, or the recording of
+    /// the Transcribe node they're dropped on.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context, screen_to_world: impl Fn(Pos2) -> Pos2) {
+        let files = ctx.input(|i| i.raw.dropped_files.clone());
+        if files.is_empty() { return; }
+        let origin = ctx.input(|i| i.pointer.hover_pos()).filter(|p| self.canvas_rect.contains(*p)).map(screen_to_world).unwrap_or(self.state.camera_offset.to_pos2());
+        let portrait_of = self.state.node_at(origin).filter(|id| matches!(self.state.nodes[id].data, NodeData::Character { .. } | NodeData::Frame { .. }));
+        let mut offset = Vec2::ZERO;
+        for file in files {
+            let name = file.path.as_ref().and_then(|p| p.file_name()).map(|n| n.to_string_lossy().into_owned()).unwrap_or(file.name.clone());
+            // The browser hands over the bytes; native only gives a path.
+            let bytes = file.bytes.as_ref().map(|b| b.to_vec()).or_else(|| file.path.as_ref().and_then(|p| std::fs::read(p).ok()));
+            if transcribe::is_audio_file(&name) {
+                let bytes = bytes.unwrap_or_default();
+                match self.state.node_at(origin).filter(|id| matches!(self.state.nodes[id].data, NodeData::Transcribe { .. })) {
+                    Some(target) => self.attach_audio(target, name, bytes),
+                    None => match transcribe::check_audio(&name, &bytes) {
+                        Ok(()) => { self.add_node(origin + offset, NodeData::Transcribe { file_name: name, audio: Some(bytes.into()), transcript: String::new(), is_loading: false }); offset += Vec2::splat(40.0); }
+                        Err(err) => self.toast(format!("⚠ Can't use {}: {}", name, err)),
+                    },
+                }
+                continue;
+            }
+            if let Some(target) = portrait_of {
+                let Some(bytes) = bytes.filter(|b| image::load_from_memory(b).is_ok()) else { self.toast(format!("⚠ Can't use {}: only PNG and JPEG images are supported", name)); continue };
+                let before = self.state.nodes[&target].data.clone();
+                let mut after = before.clone();
+                match &mut after { NodeData::Character { reference_image, .. } => *reference_image = Some(bytes.into()), NodeData::Frame { image, .. } => *image = Some(bytes), _ => {} }
+                self.state.execute(Command::EditData { id: target, before, after });
+                return;
+            }
+            let id = self.state.next_id;
+            let Some((texture, bytes)) = bytes.and_then(|b| Some((load_node_texture(ctx, id, &b)?, b))) else { self.toast(format!("⚠ Can't add {}: only PNG and JPEG images and audio recordings are supported", name)); continue };
+            self.add_node(origin + offset, NodeData::Visual { prompt: name, texture: Some(texture), image: Some(bytes), is_loading: false, variant_count: 1, variants: Vec::new(), use_parent_image: false, auto_run: false, seed: None, image_seed: None, request_seeds: Vec::new() });
+            offset += Vec2::splat(40.0);
+        }
+    }
+
+    /// Modal over the whole window; Esc, ✕ or clicking outside the panel closes it.
+    fn draw_image_preview(&mut self, ctx: &egui::Context) {
+        let Some(preview) = self.image_preview.as_mut() else { return };
+        let Some((texture, image, prompt, is_loading)) = self.state.nodes.get(&preview.id).and_then(|n| match &n.data { NodeData::Visual { texture: Some(t), image, prompt, is_loading, .. } => Some((t.clone(), image.clone(), prompt.clone(), *is_loading)), _ => None }) else { self.image_preview = None; return };
+        let id = preview.id;
+        let theme = self.settings.theme.palette();
+        let screen = ctx.screen_rect();
+        let panel = screen.shrink(40.0);
+        let (mut close, mut regenerate, mut save) = (ctx.input(|i| i.key_pressed(egui::Key::Escape)), false, false);
+        egui::Area::new(egui::Id::new("image_preview")).fixed_pos(screen.min).order(egui::Order::Foreground).show(ctx, |ui| {
+            let backdrop = ui.allocate_rect(screen, Sense::click());
+            ui.painter().rect_filled(screen, 0.0, Color32::from_black_alpha(200));
+            ui.painter().rect(panel, 8.0, theme.node_fill, Stroke::new(1.0, theme.node_border));
+            if backdrop.clicked() && backdrop.interact_pointer_pos().is_some_and(|p| !panel.contains(p)) { close = true; }
+            let toolbar = Rect::from_min_size(panel.min, Vec2::new(panel.width(), 36.0)).shrink(6.0);
+            ui.allocate_new_ui(egui::UiBuilder::new().max_rect(toolbar).layout(egui::Layout::left_to_right(egui::Align::Center)), |ui| {
+                ui.label(egui::RichText::new(format!("🖼 {}", truncate(&prompt, 60))).color(theme.text));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("✕").on_hover_text("Close (Esc)").clicked() { close = true; }
+                    if ui.add_enabled(image.is_some(), egui::Button::new("💾 Save")).clicked() { save = true; }
+                    if ui.add_enabled(!is_loading, egui::Button::new("🔄 Regenerate")).clicked() { regenerate = true; }
+                    if is_loading { ui.spinner(); }
+                    if ui.button("1:1").on_hover_text("Native size").clicked() { preview.zoom = 1.0; preview.offset = Vec2::ZERO; }
+                    ui.label(format!("{:.0}%", preview.zoom * 100.0));
+                });
+            });
+            let view = Rect::from_min_max(Pos2::new(panel.left(), toolbar.bottom()), panel.max).shrink(6.0);
+            let response = ui.interact(view, ui.id().with("image_preview_view"), Sense::drag());
+            if response.dragged() { preview.offset += response.drag_delta(); }
+            let scroll = ui.input(|i| i.raw_scroll_delta.y);
+            if let (true, Some(pointer)) = (response.hovered() && scroll != 0.0, ui.input(|i| i.pointer.hover_pos())) {
+                let factor = if scroll > 0.0 { 1.1 } else { 1.0 / 1.1 };
+                let new_zoom = (preview.zoom * factor).clamp(0.05, 16.0);
+                let center = view.center() + preview.offset;
+                preview.offset = pointer + (center - pointer) * (new_zoom / preview.zoom) - view.center();
+                preview.zoom = new_zoom;
+            }
+            let size = texture.size_vec2() / ctx.pixels_per_point() * preview.zoom;
+            let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+            ui.painter_at(view).image(texture.id(), Rect::from_center_size(view.center() + preview.offset, size), uv, Color32::WHITE);
+        });
+        if save {
+            let ext = if image::guess_format(image.as_deref().unwrap_or_default()).is_ok_and(|f| f == image::ImageFormat::Png) { "png" } else { "jpeg" };
+            if let Some(bytes) = &image { self.download(&format!("storyboard_image_{}.{}", id, ext), &format!("image/{}", ext), bytes); }
+        }
+        if regenerate {
+            if let Some(node) = self.state.nodes.get_mut(&id) {
+                if let Some(flag) = node.data.loading_flag() { *flag = true; }
+                node.error = None;
+            }
+            self.trigger_visualize(id, prompt, ctx.clone());
+        }
+        if close { self.image_preview = None; }
+    }
+
+    /// Queues a JSON POST of `body` to `url` for node `node`, sent once a slot is free; whatever `on_result` makes of the response goes
+    /// to the app. The node shows as queued until the request leaves. Node requests identical to one answered recently are served from
+    /// the response cache instead.
+    fn post_json(&mut self, node: Option<u64>, url: &str, body: serde_json::Value, ctx: egui::Context, on_result: impl FnOnce(ehttp::Result<ehttp::Response>) -> Option<AppMessage> + Send + 'static) {
+        let mut on_result: queue::OnResult = Box::new(on_result);
+        let cache_key = node.map(|_| cache::request_key(url, &body));
+        if let (Some(id), Some(key)) = (node, cache_key) {
+            match self.answer_from_cache(id, key, url, on_result) { Ok(()) => { ctx.request_repaint(); return; } Err(miss) => on_result = miss }
+        }
+        let mut request = ehttp::Request::post(url, serde_json::to_vec(&body).unwrap_or_default());
+        request.headers.insert("Content-Type", "application/json");
+        if let Some(n) = node.and_then(|id| self.node_mut(id)) { n.queued = true; }
+        self.request_queue.push_back(QueuedRequest { node, request, on_result, cache_key });
+        self.pump_requests(&ctx);
+    }
+
+    fn trigger_research(&mut self, node_id: u64, query: String, ctx: egui::Context) {
+        self.post_json(Some(node_id), "/api/research", requests::research_body(&query), ctx, move |result| Some(reply(node_id, result, |r| AppMessage::TextResponse(node_id, r.text().unwrap_or_default().to_string()))));
+    }
+
+    /// Asks for one image, or for the node's variant count, each with its own seed (counting up from the locked one, if any). With
+    /// "Use parent image" on, the first Visual parent's image goes along as the starting point.
+    fn trigger_visualize(&mut self, node_id: u64, prompt: String, ctx: egui::Context) {
+        let prompt = self.state.render_prompt(node_id, &prompt);
+        let use_parent = matches!(self.state.nodes.get(&node_id).map(|n| &n.data), Some(NodeData::Visual { use_parent_image: true, .. }));
+        let image = self.state.img2img_source(node_id).filter(|_| use_parent).map(<[u8]>::to_vec);
+        let count = self.node_mut(node_id).map_or(1, |n| n.start_variants());
+        let locked = match self.state.nodes.get(&node_id).map(|n| &n.data) { Some(NodeData::Visual { seed, .. }) => *seed, _ => None };
+        let seeds = seeds::request_seeds(locked, count);
+        if let Some(NodeData::Visual { request_seeds, .. }) = self.node_mut(node_id).map(|n| &mut n.data) { *request_seeds = seeds.clone(); }
+        if count == 1 {
+            self.post_json(Some(node_id), "/api/visualize", requests::visualize_body(&prompt, Some(seeds[0]), image.as_deref()), ctx, move |result| Some(reply(node_id, result, |r| AppMessage::ImageResponse(node_id, None, r.bytes))));
+            return;
+        }
+        for (index, seed) in seeds.into_iter().enumerate() {
+            self.post_json(Some(node_id), "/api/visualize", requests::visualize_body(&prompt, Some(seed), image.as_deref()), ctx.clone(), move |result| Some(match reply(node_id, result, |r| AppMessage::ImageResponse(node_id, Some(index), r.bytes)) {
+                AppMessage::Error(id, err) => AppMessage::VariantError(id, index, err),
+                msg => msg,
+            }));
+        }
+    }
+
+    /// Sends the prompt, with its placeholders filled in, to OpenRouter or the local Ollama as the node's provider says.
+    fn trigger_agnostic_ai(&mut self, node_id: u64, model: String, prompt: String, ctx: egui::Context) {
+        let prompt = self.state.render_prompt(node_id, &prompt);
+        let provider = match self.state.nodes.get(&node_id).map(|n| &n.data) { Some(NodeData::AgnosticAI { provider, .. }) => *provider, _ => local_ai::Provider::OpenRouter };
+        self.post_json(Some(node_id), provider.endpoint(), requests::agnostic_ai_body(&model, &prompt), ctx, move |result| Some(reply(node_id, result, |r| AppMessage::TextResponse(node_id, r.text().unwrap_or_default().to_string()))));
+    }
+
+    fn trigger_tts(&mut self, node_id: u64, voice: String, text: String, ctx: egui::Context) {
+        self.post_json(Some(node_id), "/api/tts", requests::tts_body(&voice, &text), ctx, move |result| Some(reply(node_id, result, |r| AppMessage::AudioResponse(node_id, r.bytes))));
+    }
+
+    fn trigger_translate(&mut self, node_id: u64, language: String, text: String, ctx: egui::Context) {
+        self.post_json(Some(node_id), "/api/agnostic-ai", requests::agnostic_ai_body(translate::TRANSLATE_MODEL, &translate::translate_prompt(&language, &text)), ctx, move |result| Some(reply(node_id, result, |r| AppMessage::TextResponse(node_id, r.text().unwrap_or_default().to_string()))));
+    }
+
+    fn trigger_fetch(&mut self, node_id: u64, url: String, ctx: egui::Context) {
+        self.post_json(Some(node_id), "/api/fetch-url", requests::fetch_url_body(&url), ctx, move |result| Some(reply(node_id, result, |r| AppMessage::TextResponse(node_id, r.text().unwrap_or_default().to_string()))));
+    }
+
+    fn trigger_transcribe(&mut self, node_id: u64, file_name: String, audio: Arc<[u8]>, ctx: egui::Context) {
+        self.post_json(Some(node_id), "/api/transcribe", requests::transcribe_body(&file_name, &audio), ctx, move |result| Some(reply(node_id, result, |r| AppMessage::TextResponse(node_id, r.text().unwrap_or_default().to_string()))));
+    }
+
+    fn trigger_foxit(&mut self, node_id: u64, all_text: String, ctx: egui::Context) {
+        self.post_json(Some(node_id), "/api/foxit", requests::foxit_body(&all_text), ctx, move |result| Some(reply(node_id, result, |r| AppMessage::TextResponse(node_id, r.text().unwrap_or_default().to_string()))));
+    }
+
+    /// Text sent to the PDF export from node `export_id`: the nodes feeding it in pipeline order, each under a header, or every node on
+    /// the board when the export node has "Include everything" ticked. Earlier results and notes follow the export settings.
+    fn export_text(&self, export_id: u64) -> String {
+        let mut all_text = String::new();
+        let mut rejected = self.state.rejected_by_picks();
+        rejected.extend(self.state.skipped_by_branches());
+        let history = |n: &Node, all_text: &mut String| {
+            if self.settings.export_history { for (i, text) in n.versions.iter().filter_map(|v| v.text.as_deref()).enumerate() { all_text.push_str(&format!("Earlier version {} of node {}: {}\n\n", i + 1, n.id, text)); } }
+        };
+        if !matches!(self.state.nodes.get(&export_id).map(|n| &n.data), Some(NodeData::FoxitExport { include_everything: true, .. })) {
+            let mut ids = self.state.upstream_order(export_id).unwrap_or_else(|stuck| stuck);
+            self.state.sequence_frames(&mut ids);
+            for n in ids.iter().filter(|id| !rejected.contains(id)).filter_map(|id| self.state.nodes.get(id)) {
+                let body = match &n.data {
+                    NodeData::Concept { text } => text.clone(),
+                    NodeData::YouComResearch { query, result, .. } => format!("Query: {}\n{}", query, result.as_deref().unwrap_or("(no result yet)")),
+                    NodeData::AgnosticAI { model, prompt, result, .. } => format!("Model: {}\nPrompt: {}\n{}", model, prompt, result.as_deref().unwrap_or("(no result yet)")),
+                    NodeData::Visual { prompt, .. } => format!("Image prompt: {}", prompt),
+                    NodeData::Merge { output, .. } => output.clone(),
+                    NodeData::Transform { output, error, .. } => error.clone().unwrap_or_else(|| output.clone()),
+                    NodeData::Compare { output, .. } => if output.is_empty() { "(nothing picked yet)".to_string() } else { output.clone() },
+                    NodeData::Select { chosen, output, chosen_image, .. } => match chosen { Some(c) if *chosen_image => format!("(image from node #{})", c), _ if output.is_empty() => "(nothing chosen yet)".to_string(), _ => output.clone() },
+                    NodeData::Branch { condition, outcome, .. } => format!("Condition: {}\nOutcome: {}", condition, branch::outcome_text(*outcome)),
+                    NodeData::Note { body, pass_on: true, .. } => notes::render_body(body),
+                    NodeData::Audio { voice, text, .. } => format!("Narration ({} voice): {}", voice, text),
+                    NodeData::Character { description, .. } => description.clone(),
+                    NodeData::Frame { sequence_index, shot_type, caption, .. } => format!("{}\n{}", frame::frame_label(*sequence_index, shot_type), caption),
+                    NodeData::WebFetch { url, result, .. } => format!("URL: {}\n{}", url, result.as_deref().unwrap_or("(not fetched yet)")),
+                    NodeData::Translate { target_lang, result, .. } => format!("({})\n{}", target_lang, result.as_deref().unwrap_or("(not translated yet)")),
+                    NodeData::Script { output, .. } => if output.is_empty() { "(empty scene)".to_string() } else { output.clone() },
+                    NodeData::Transcribe { file_name, transcript, .. } => format!("Recording: {}\n{}", file_name, if transcript.is_empty() { "(not transcribed yet)" } else { transcript }),
+                    NodeData::FoxitExport { .. } | NodeData::Note { .. } | NodeData::MarkdownView { .. } => continue,
+                };
+                let header = match n.title.as_deref().or(n.data.own_title()) { Some(title) => format!("{}: {}", n.data.kind().title(), title), None => n.data.kind().title().to_string() };
+                all_text.push_str(&format!("== {} ==\n{}\n\n", header, body.trim()));
+                history(n, &mut all_text);
+            }
+        } else {
+            let mut ids: Vec<u64> = self.state.nodes.keys().copied().filter(|id| !rejected.contains(id)).collect();
+            ids.sort();
+            self.state.sequence_frames(&mut ids);
+            for n in ids.iter().map(|id| &self.state.nodes[id]) {
+                if let Some(line) = export_line(n) { all_text.push_str(&line); }
+                history(n, &mut all_text);
+            }
+        }
+        if self.settings.export_notes { for note in self.state.annotations.iter().filter(|a| !a.text.trim().is_empty()) { all_text.push_str(&format!("Note: {}\n\n", note.text.trim())); } }
+        all_text
+    }
+
+    /// Adds the edges as one undo step and explains any that were refused for mismatched ports or closing a loop.
+    fn link(&mut self, pairs: &[(u64, u64)]) {
+        let rejected = self.state.add_edges(pairs);
+        self.refuse_links(rejected);
+    }
+
+    /// Says why each of the `rejected` links couldn't be made.
+    fn refuse_links(&mut self, rejected: Vec<(u64, u64)>) {
+        for (from, to) in rejected {
+            let reason = self.state.link_mismatch(from, to).unwrap_or_else(|| "it would create a cycle".to_string());
+            self.toast(format!("Can't link #{} → #{}: {}", from, to, reason));
+        }
+    }
+
+    fn start_pipeline(&mut self) {
+        let blocking = self.state.validate().iter().filter(|f| f.severity == validate::Severity::Error).count();
+        if blocking > 0 { self.show_validation = true; self.toast(format!("Can't run the pipeline: {} problems to fix first", blocking)); return; }
+        match self.state.pipeline_order() {
+            Ok(order) => {
+                let order: Vec<u64> = order.into_iter().filter(|id| !matches!(self.state.nodes[id].data, NodeData::Concept { .. } | NodeData::Merge { .. } | NodeData::Compare { .. } | NodeData::Select { .. } | NodeData::Note { .. } | NodeData::Character { .. } | NodeData::Transform { .. } | NodeData::Frame { .. } | NodeData::MarkdownView { .. })).collect();
+                for node in self.state.nodes.values_mut() { node.skipped = false; }
+                for id in &order { if let Some(node) = self.state.nodes.get_mut(id) { node.queued = true; } }
+                self.pipeline_summary = None;
+                self.pipeline = Some(PipelineRun::new(order));
+            }
+            Err(stuck) => self.toast(format!("Can't run the pipeline: nodes {} form a cycle", stuck.iter().map(|id| format!("#{}", id)).collect::<Vec<_>>().join(", "))),
+        }
+    }
+
+    /// Ends the run and keeps its summary for the sidebar; `failure` says why it stopped early.
+    fn finish_pipeline(&mut self, failure: Option<String>) {
+        self.pipeline_summary = self.pipeline.as_ref().map(|run| run.summary(failure));
+        self.stop_pipeline();
+    }
+
+    /// Ends the run, started or not, and clears what it left queued on every board; nodes waiting on a request slot stay queued.
+    fn stop_pipeline(&mut self) {
+        self.pipeline = None;
+        let waiting: HashSet<u64> = self.request_queue.iter().filter_map(|q| q.node).collect();
+        for node in self.state.nodes.values_mut().chain(self.boards.iter_mut().flat_map(|b| b.state.nodes.values_mut())) { if !waiting.contains(&node.id) { node.queued = false; } }
+    }
+
+    /// Called every frame while a run is active: waits out the current request, then starts the next executable node with its parents' output as input.
+    fn step_pipeline(&mut self, ctx: &egui::Context) {
+        let Some(run) = self.pipeline.as_mut() else { return };
+        if let Some(id) = run.current {
+            match self.state.nodes.get(&id) {
+                None => { self.finish_pipeline(Some(format!("node #{} was deleted", id))); self.toast(format!("Pipeline stopped: node #{} was deleted", id)); return; }
+                Some(n) if n.data.is_loading() || self.retries.contains_key(&id) => return,
+                Some(n) if n.error.is_some() => { let err = n.error.clone().unwrap_or_default(); self.finish_pipeline(Some(format!("node #{} failed", id))); self.toast(format!("Pipeline stopped: node #{} failed: {}", id, err)); return; }
+                Some(_) => {
+                    if let Some(started) = run.current_started.take() { run.done.push((id, started.elapsed())); }
+                    run.current = None;
+                }
+            }
+        }
+        while let Some(&id) = run.order.get(run.next) {
+            run.next += 1;
+            let input = self.state.parent_output(id);
+            let skipped = self.state.skipped_by_branches().contains(&id);
+            let Some(node) = self.state.nodes.get_mut(&id) else { continue };
+            if skipped { node.queued = false; node.skipped = true; continue; }
+            match &mut node.data {
+                NodeData::YouComResearch { query, .. } => { if let Some(text) = input { *query = text; } }
+                NodeData::AgnosticAI { prompt, .. } | NodeData::Visual { prompt, .. } => { if let Some(text) = input.filter(|_| !templates::has_placeholders(prompt)) { *prompt = text; } }
+                NodeData::Audio { text, .. } | NodeData::Translate { text, .. } => { if let Some(input) = input { *text = input; } }
+                NodeData::WebFetch { url, .. } => { if let Some(link) = input.as_deref().and_then(webfetch::first_url) { *url = link; } }
+                // A scene typed in by hand has nothing to be parsed from.
+                NodeData::Script { .. } if input.is_none() => { node.queued = false; continue; }
+                NodeData::Transcribe { audio: None, .. } => { node.queued = false; continue; }
+                NodeData::FoxitExport { .. } | NodeData::Branch { .. } | NodeData::Script { .. } | NodeData::Transcribe { .. } => {}
+                NodeData::Concept { .. } | NodeData::Merge { .. } | NodeData::Compare { .. } | NodeData::Select { .. } | NodeData::Note { .. } | NodeData::Character { .. } | NodeData::Transform { .. } | NodeData::Frame { .. } | NodeData::MarkdownView { .. } => continue,
+            }
+            node.queued = false;
+            self.retries.remove(&id);
+            self.run_node(id, ctx);
+            if let Some(run) = self.pipeline.as_mut() { run.current = Some(id); run.current_started = Some(Instant::now()); }
+            return;
+        }
+        self.finish_pipeline(None);
+        self.toast("Pipeline finished");
+    }
+
+    fn trigger_html_report(&mut self, ctx: egui::Context) {
+        let body = serde_json::json!({"project": self.to_project()});
+        self.post_json(None, "/api/report/html", body, ctx, |result| Some(AppMessage::HtmlReport(match result {
+            Ok(r) if r.ok => Ok(r.bytes),
+            Ok(r) => Err(response_error(&r)),
+            Err(err) => Err(err),
+        })));
+    }
+
+    fn apply_physics(&mut self) {
+        let repulsion = 6000.0;
+        let attraction = 0.02;
+        let damping = 0.8;
+        let node_ids: Vec<u64> = self.state.nodes.keys().copied().collect();
+        let mut forces: HashMap<u64, Vec2> = node_ids.iter().map(|&id| (id, Vec2::ZERO)).collect();
+        for &i in &node_ids {
+            for &j in &node_ids {
+                if i == j { continue; }
+                let pos_i = self.state.nodes[&i].position;
+                let pos_j = self.state.nodes[&j].position;
+                let delta = pos_i - pos_j;
+                let dist = delta.length().max(100.0);
+                let force = delta.normalized() * (repulsion / (dist * dist));
+                *forces.get_mut(&i).unwrap() += force;
+            }
+        }
+        for edge in &self.state.edges {
+            if let (Some(n1), Some(n2)) = (self.state.nodes.get(&edge.from), self.state.nodes.get(&edge.to)) {
+                let delta = n2.position - n1.position;
+                let dist = delta.length();
+                // Tall or wide nodes need a longer spring so their neighbours don't end up underneath them.
+                let rest = 400.0_f32.max((n1.size + n2.size).max_elem() * 0.5 + 60.0);
+                let force = delta.normalized() * (dist - rest) * attraction;
+                *forces.get_mut(&edge.from).unwrap() += force;
+                *forces.get_mut(&edge.to).unwrap() -= force;
+            }
+        }
+        for id in node_ids {
+            if let Some(node) = self.state.nodes.get_mut(&id) {
+                let nudged = self.nudge_origins.as_ref().is_some_and(|origins| origins.iter().any(|(m, _)| *m == id));
+                if nudged || self.state.dragging.as_ref().is_some_and(|drag| drag.contains(id)) || node.pinned || self.state.dragging_group.as_ref().is_some_and(|drag| drag.members.iter().any(|(m, _)| *m == id)) { continue; }
+                node.velocity = (node.velocity + forces[&id]) * damping;
+                node.position += node.velocity;
+                if self.settings.snap_to_grid && node.velocity.length() < SNAP_REST_SPEED { node.position = self.settings.snap(node.position); node.velocity = Vec2::ZERO; }
+            }
+        }
+    }
+
+    fn draw_intro_screen(&mut self, ctx: &egui::Context) {
+        let theme = self.settings.theme.palette();
+        let bg_color = theme.canvas_bg.gamma_multiply(self.intro_animation * 0.9);
+        
+        // 1. Draw background overlay using a high-order painter
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("intro_bg")));
+        painter.rect_filled(ctx.screen_rect(), 0.0, bg_color);
+
+        // 2. Draw the interactive panel in a separate Area
+        egui::Area::new("intro_panel".into())
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::new(0.0, (1.0 - self.intro_animation) * -100.0))
+            .order(egui::Order::Foreground)
+            .interactable(true)
+            .show(ctx, |ui| {
+                Frame::none()
+                    .inner_margin(40.0)
+                    .rounding(16.0)
+                    .fill(theme.node_fill.linear_multiply(self.intro_animation))
+                    .stroke(Stroke::new(1.0, theme.node_border.linear_multiply(self.intro_animation)))
+                    .show(ui, |ui| {
+                        ui.vertical_centered(|ui| {
+                            ui.heading(egui::RichText::new("🎬 StoryBoard AI").size(32.0).color(theme.text.linear_multiply(self.intro_animation)));
+                            ui.add_space(10.0);
+                            ui.label(egui::RichText::new("The Visual IDE for AI Storytelling").size(18.0).color(theme.muted_text.linear_multiply(self.intro_animation)));
+                            ui.add_space(30.0);
+                            
+                            let button = egui::Button::new(egui::RichText::new("✨ Start New Flow").size(20.0).color(Color32::WHITE))
+                                .fill(Color32::from_rgb(40, 120, 200).linear_multiply(self.intro_animation))
                                 .min_size(Vec2::new(220.0, 50.0));
                                 
                             if ui.add(button).clicked() {
@@ -272,109 +2021,708 @@ impl StoryBoardApp {
                             }
                             
                             ui.add_space(15.0);
-                            ui.small(egui::RichText::new("Built with Rust + egui + Wasm").color(Color32::from_gray(100).linear_multiply(self.intro_animation)));
+                            ui.small(egui::RichText::new("Built with Rust + egui + Wasm").color(theme.muted_text.linear_multiply(self.intro_animation)));
                         });
                     });
             });
     }
 
-    fn handle_selection(&mut self, ctx: &egui::Context, canvas_rect: Rect, _world_to_screen: impl Fn(Pos2) -> Pos2, screen_to_world: impl Fn(Pos2) -> Pos2) {
-        let is_shift_down = ctx.input(|i| i.modifiers.shift);
-        if is_shift_down {
-            if ctx.input(|i| i.pointer.primary_pressed()) {
-                if let Some(pos) = ctx.input(|i| i.pointer.press_origin()) {
-                    if canvas_rect.contains(pos) { self.selection_start = Some(pos); self.selected_nodes.clear(); }
-                }
-            }
-            if let Some(start_pos) = self.selection_start {
-                if let Some(current_pos) = ctx.input(|i| i.pointer.hover_pos()) {
-                    let selection_rect = Rect::from_two_pos(start_pos, current_pos);
-                    let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Tooltip, "selection".into()));
-                    painter.rect_filled(selection_rect, 0.0, Color32::from_rgba_premultiplied(100, 100, 255, 40));
-                    painter.rect_stroke(selection_rect, 0.0, Stroke::new(1.0, Color32::from_rgb(150, 150, 255)));
-                    let selection_world = Rect::from_two_pos(screen_to_world(start_pos), screen_to_world(current_pos));
-                    for node in self.state.nodes.values_mut() {
-                        if selection_world.intersects(node.bounds()) { self.selected_nodes.insert(node.id); node.selected = true; }
-                        else { self.selected_nodes.remove(&node.id); node.selected = false; }
-                    }
+    /// Full-width editors for the single selected node; they write through the same undo path as the in-node editors.
+    fn draw_properties(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, id: u64) {
+        let Some(node) = self.state.nodes.get(&id) else { return };
+        let mut data = node.data.clone();
+        let (mut changed, mut trigger) = (false, false);
+        egui::CollapsingHeader::new("Properties").id_salt("properties").default_open(true).show(ui, |ui| {
+            ui.label(format!("{} {} · #{}", data.kind().icon(), node.display_title(), id));
+            ui.small(format!("Position ({:.0}, {:.0})", node.position.x, node.position.y));
+            let list = |ids: Vec<u64>| if ids.is_empty() { "none".to_string() } else { ids.iter().map(|i| format!("#{}", i)).collect::<Vec<_>>().join(", ") };
+            ui.small(format!("Parents: {}", list(self.state.edges.iter().filter(|e| e.to == id).map(|e| e.from).collect())));
+            ui.small(format!("Children: {}", list(self.state.edges.iter().filter(|e| e.from == id).map(|e| e.to).collect())));
+            fn wide(text: &mut String, multiline: bool) -> egui::TextEdit<'_> { if multiline { egui::TextEdit::multiline(text).desired_rows(4) } else { egui::TextEdit::singleline(text) }.desired_width(f32::INFINITY) }
+            let loading = data.is_loading();
+            match &mut data {
+                NodeData::Concept { text } => { changed |= ui.add(wide(text, true)).changed(); }
+                NodeData::YouComResearch { query, .. } => {
+                    ui.label("Query:"); changed |= ui.add(wide(query, false)).changed();
+                    trigger = ui.add_enabled(!loading, egui::Button::new("🌐 Search")).clicked();
+                }
+                NodeData::AgnosticAI { model, prompt, provider, .. } => {
+                    ui.label("Provider:"); changed |= local_ai::provider_picker(ui, id, provider, model, &self.local_models);
+                    ui.label("Model:"); changed |= ui.add(wide(model, false)).changed();
+                    ui.label("Prompt:"); changed |= ui.add(wide(prompt, true)).changed();
+                    trigger = ui.add_enabled(!loading, egui::Button::new("🤖 Generate")).clicked();
+                }
+                NodeData::Visual { prompt, .. } => {
+                    ui.label("Prompt:"); changed |= ui.add(wide(prompt, true)).changed();
+                    trigger = ui.add_enabled(!loading, egui::Button::new("🎨 Generate")).clicked();
+                }
+                NodeData::FoxitExport { status, .. } => { ui.label(format!("Status: {}", status)); }
+                NodeData::Merge { separator, .. } => { ui.label("Separator:"); changed |= ui.add(wide(separator, false)).changed(); }
+                NodeData::Transform { steps, .. } => { changed |= transform::transform_ui(ui, id, steps); }
+                NodeData::MarkdownView { text } => { if text.is_empty() { ui.weak("Nothing linked in yet"); } else { ui.small(format!("{} characters", text.chars().count())); } }
+                NodeData::Transcribe { file_name, audio, transcript, .. } => {
+                    match audio { Some(clip) => ui.small(transcribe::clip_summary(file_name, clip)), None => ui.weak("No recording yet") };
+                    ui.label("Transcript:"); changed |= ui.add(wide(transcript, true)).changed();
+                    trigger = ui.add_enabled(!loading && audio.is_some(), egui::Button::new("📝 Transcribe")).clicked();
+                }
+                NodeData::Compare { output, .. } => { if output.is_empty() { ui.weak("Nothing picked yet"); } else { ui.small(truncate(output, 200)); } }
+                NodeData::Select { chosen, .. } => { ui.small(match chosen { Some(c) => format!("Chosen: #{}", c), None => "Nothing chosen yet".to_string() }); }
+                NodeData::Branch { condition, use_ai, outcome, .. } => {
+                    ui.label(if *use_ai { "Question:" } else { "Keyword:" }); changed |= ui.add(wide(condition, false)).changed();
+                    ui.small(format!("Outcome: {}", branch::outcome_text(*outcome)));
+                    trigger = ui.add_enabled(!loading, egui::Button::new("▶ Evaluate")).clicked();
+                }
+                NodeData::Note { title, body, pass_on } => {
+                    ui.label("Title:"); changed |= ui.add(wide(title, false)).changed();
+                    changed |= ui.add(wide(body, true)).changed();
+                    changed |= ui.checkbox(pass_on, "📤 Pass on to Link Parent and exports").changed();
+                }
+                NodeData::Audio { voice, text, .. } => {
+                    ui.label("Voice:"); changed |= ui.add(wide(voice, false)).changed();
+                    ui.label("Narration:"); changed |= ui.add(wide(text, true)).changed();
+                    trigger = ui.add_enabled(!loading, egui::Button::new("🔊 Generate")).clicked();
+                }
+                NodeData::Script { heading, location, time_of_day, action, dialogue, .. } => {
+                    ui.label("Scene heading:"); changed |= ui.add(wide(heading, false)).changed();
+                    ui.label("Location:"); changed |= ui.add(wide(location, false)).changed();
+                    ui.label("Time of day:"); changed |= ui.add(wide(time_of_day, false)).changed();
+                    ui.label("Action:"); changed |= ui.add(wide(action, true)).changed();
+                    ui.small(format!("{} lines of dialogue", dialogue.len()));
+                    trigger = ui.add_enabled(!loading, egui::Button::new("🪄 Parse from parent")).clicked();
+                }
+                NodeData::WebFetch { url, .. } => {
+                    ui.label("URL:"); changed |= ui.add(wide(url, false)).changed();
+                    trigger = ui.add_enabled(!loading && webfetch::looks_fetchable(url), egui::Button::new("📥 Fetch")).clicked();
+                }
+                NodeData::Translate { target_lang, text, .. } => {
+                    ui.label("Language:"); changed |= ui.add(wide(target_lang, false)).changed();
+                    ui.label("Text:"); changed |= ui.add(wide(text, true)).changed();
+                    trigger = ui.add_enabled(!loading, egui::Button::new("🔤 Translate")).clicked();
+                }
+                NodeData::Frame { sequence_index, shot_type, caption, image } => {
+                    ui.horizontal(|ui| { ui.label("Sequence #"); changed |= ui.add(egui::DragValue::new(sequence_index).range(1..=9999)).changed(); });
+                    ui.label("Shot type:"); changed |= ui.add(wide(shot_type, false)).changed();
+                    ui.label("Caption:"); changed |= ui.add(wide(caption, true)).changed();
+                    if image.is_some() { ui.horizontal(|ui| { ui.small("Has an uploaded image"); if ui.small_button("🗑 Remove").clicked() { *image = None; changed = true; } }); }
+                }
+                NodeData::Character { name, description, reference_image } => {
+                    ui.label("Name:"); changed |= ui.add(wide(name, false)).changed();
+                    ui.label("Description:"); changed |= ui.add(wide(description, true)).changed();
+                    if reference_image.is_some() { ui.horizontal(|ui| { ui.small("Has a portrait"); if ui.small_button("🗑 Remove").clicked() { *reference_image = None; changed = true; } }); }
+                }
+            }
+            if loading { ui.spinner(); }
+        });
+        if changed {
+            if let Some(n) = self.state.nodes.get_mut(&id) {
+                let before = std::mem::replace(&mut n.data, data.clone());
+                self.state.history.push(Command::EditData { id, before, after: data.clone() });
+            }
+        }
+        if trigger { self.retry_now(id, ctx); }
+    }
+
+    /// Narrow stand-in for the sidebar on small screens: add-node icons, link, clear and expand.
+    fn draw_sidebar_rail(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::left("sidebar_rail").resizable(false).exact_width(48.0).show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(10.0);
+                if ui.button("»").on_hover_text("Expand sidebar").clicked() { self.settings.sidebar_collapsed = false; }
+                ui.separator();
+                for kind in [NodeKind::Concept, NodeKind::Research, NodeKind::AgnosticAI, NodeKind::Visual, NodeKind::Merge, NodeKind::Compare, NodeKind::Branch, NodeKind::Select, NodeKind::Note, NodeKind::Audio, NodeKind::Script, NodeKind::Character, NodeKind::Translate, NodeKind::WebFetch, NodeKind::Transform, NodeKind::Frame, NodeKind::MarkdownView, NodeKind::Transcribe] {
+                    if ui.button(kind.icon()).on_hover_text(format!("Add {} node", kind.title())).clicked() { self.create_node(kind, !ui.input(|i| i.modifiers.shift)); }
+                }
+                ui.separator();
+                let selected = self.state.selected_ids();
+                if !self.state.linking_from.is_empty() {
+                    if ui.button("🚫").on_hover_text("Cancel link").clicked() { self.state.linking_from.clear(); }
+                } else if ui.add_enabled(!selected.is_empty(), egui::Button::new("🔗")).on_hover_text("Link selection to the next clicked node").clicked() {
+                    self.state.linking_from = selected;
+                }
+                if ui.button("▶").on_hover_text("Present (P)").clicked() { self.start_presentation(); }
+                if ui.button("🗑").on_hover_text("Clear canvas").clicked() { let ids: Vec<u64> = self.state.nodes.keys().copied().collect(); self.state.remove_nodes(&ids); }
+            });
+        });
+    }
+
+    /// Current zoom percentage in the canvas corner; clicking it offers presets.
+    fn draw_zoom_indicator(&mut self, ui: &mut egui::Ui, canvas_rect: Rect) {
+        let rect = Rect::from_min_size(canvas_rect.right_top() + Vec2::new(-82.0, 10.0), Vec2::new(72.0, 24.0));
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(rect), |ui| {
+            ui.menu_button(format!("🔍 {:.0}%", self.state.camera_zoom * 100.0), |ui| {
+                for percent in [50.0, 100.0, 200.0] {
+                    if ui.button(format!("{:.0}%", percent)).clicked() { self.state.zoom_at(percent / 100.0, canvas_rect.center(), canvas_rect); self.camera_tween = None; ui.close_menu(); }
+                }
+                if ui.button("⛶ Fit").clicked() { self.state.fit_all(canvas_rect.size()); self.camera_tween = None; ui.close_menu(); }
+            }).response.on_hover_text("+ / − to zoom, 0 to reset");
+        });
+    }
+
+    fn draw_minimap(&mut self, ui: &mut egui::Ui, canvas_rect: Rect) {
+        let map_rect = Rect::from_min_size(canvas_rect.right_bottom() - MINIMAP_SIZE - Vec2::splat(12.0), MINIMAP_SIZE);
+        let response = ui.interact(map_rect, ui.id().with("minimap"), Sense::click_and_drag());
+        let viewport = Rect::from_center_size(self.state.camera_offset.to_pos2(), canvas_rect.size() / self.state.camera_zoom);
+        // Always include the viewport so the map stays meaningful on an empty canvas or far away from the graph.
+        let world = self.state.nodes.values().fold(viewport, |r, n| r.union(n.bounds())).expand(50.0);
+        let inner = map_rect.shrink(6.0);
+        let scale = (inner.width() / world.width()).min(inner.height() / world.height());
+        let to_map = |p: Pos2| inner.center() + (p - world.center()) * scale;
+        let painter = ui.painter_at(map_rect);
+        let theme = self.settings.theme.palette();
+        painter.rect(map_rect, 6.0, theme.overlay, Stroke::new(1.0, theme.node_border));
+        for node in self.state.nodes.values() {
+            let r = Rect::from_min_max(to_map(node.bounds().min), to_map(node.bounds().max));
+            painter.rect_filled(Rect::from_center_size(r.center(), r.size().max(Vec2::splat(2.0))), 1.0, node.data.accent_color());
+        }
+        painter.rect_stroke(Rect::from_min_max(to_map(viewport.min), to_map(viewport.max)), 0.0, Stroke::new(1.0, theme.text));
+        if response.clicked() || response.dragged() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let pos = inner.clamp(pos);
+                self.state.camera_offset = (world.center() + (pos - inner.center()) / scale).to_vec2();
+            }
+        }
+    }
+
+    fn edge_context_menu(&mut self, ui: &mut egui::Ui, id: u64) {
+        ui.label(format!("Edge {}", id));
+        ui.separator();
+        if ui.button("🏷 Edit label").clicked() { self.start_edge_label_edit(id); ui.close_menu(); }
+        let Some(edge) = self.state.edges.iter().find(|e| e.id == id) else { return };
+        if matches!(self.state.nodes.get(&edge.from).map(|n| &n.data), Some(NodeData::Branch { .. })) {
+            let current = edge.role;
+            ui.horizontal(|ui| {
+                ui.label("Follow when");
+                for role in BranchRole::ALL { if ui.selectable_label(current == Some(role), role.label()).clicked() { self.state.set_edge_role(id, Some(role)); ui.close_menu(); } }
+                if ui.selectable_label(current.is_none(), "always").clicked() { self.state.set_edge_role(id, None); ui.close_menu(); }
+            });
+        }
+        if ui.button("🗑 Delete").clicked() { self.state.remove_edge(id); ui.close_menu(); }
+    }
+
+    fn start_edge_label_edit(&mut self, id: u64) {
+        if let Some(edge) = self.state.edges.iter().find(|e| e.id == id) { self.editing_edge_label = Some((id, edge.label.clone().unwrap_or_default())); }
+    }
+
+    /// Small popup at the edge midpoint for editing its label; Enter or clicking elsewhere commits, Escape cancels.
+    fn draw_edge_label_editor(&mut self, ctx: &egui::Context, world_to_screen: impl Fn(Pos2) -> Pos2 + Copy) {
+        let Some((id, mut draft)) = self.editing_edge_label.take() else { return };
+        let Some(mid) = self.state.edges.iter().find(|e| e.id == id).and_then(|e| self.state.edge_curve(e, world_to_screen)).map(|c| bezier_point(&c, 0.5)) else { return };
+        let area = egui::Area::new(egui::Id::new("edge_label_editor")).fixed_pos(mid - Vec2::new(80.0, 12.0)).order(egui::Order::Foreground).show(ctx, |ui| {
+            Frame::popup(ui.style()).show(ui, |ui| {
+                ui.add(egui::TextEdit::singleline(&mut draft).hint_text("Edge label").desired_width(160.0)).request_focus();
+            });
+        });
+        let clicked_outside = ctx.input(|i| i.pointer.any_pressed()) && !area.response.contains_pointer();
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) { return; }
+        if clicked_outside || ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+            self.state.set_edge_label(id, Some(draft.trim().to_string()).filter(|l| !l.is_empty()));
+            return;
+        }
+        self.editing_edge_label = Some((id, draft));
+    }
+
+    /// While Alt is held over a node, the set of nodes upstream and downstream of it; everything else is drawn dimmed.
+    fn hovered_lineage(&mut self, ctx: &egui::Context, canvas_rect: Rect, screen_to_world: impl Fn(Pos2) -> Pos2) -> Option<HashSet<u64>> {
+        // Alt during a node drag means "drop to link", not lineage.
+        if !ctx.input(|i| i.modifiers.alt) || self.state.dragging.is_some() { return None; }
+        let id = ctx.input(|i| i.pointer.hover_pos()).filter(|p| canvas_rect.contains(*p)).and_then(|p| self.state.node_at(screen_to_world(p)))?;
+        let version = self.state.graph_version;
+        if !matches!(&self.lineage_cache, Some((cached, v, _)) if *cached == id && *v == version) {
+            self.lineage_cache = Some((id, version, self.state.lineage(id)));
+        }
+        self.lineage_cache.as_ref().map(|(_, _, set)| set.clone())
+    }
+
+    /// Adds a node with empty text under the pointer, selected and ready for typing.
+    fn quick_create(&mut self, world: Pos2, kind: NodeKind) {
+        let mut data = kind.default_data();
+        if let NodeData::Concept { text } = &mut data { text.clear(); }
+        // Put the header just under the pointer.
+        let id = self.add_node(world - Vec2::new(125.0, 20.0), data);
+        self.state.select_only(id);
+        self.pending_text_focus = Some(id);
+    }
+
+    fn open_palette(&mut self, world: Pos2) {
+        self.node_palette = Some(NodePalette { world, filter: String::new(), highlighted: 0 });
+    }
+
+    /// World position under the pointer, or the camera center when the pointer is off the canvas.
+    fn pointer_world(&self, ctx: &egui::Context) -> Pos2 {
+        let center = self.state.camera_offset.to_pos2();
+        ctx.input(|i| i.pointer.hover_pos()).filter(|p| self.canvas_rect.contains(*p)).map_or(center, |p| center + (p - self.canvas_rect.center()) / self.state.camera_zoom)
+    }
+
+    /// Arrow keys move the highlight, Enter or a click creates the node, Escape or clicking elsewhere closes.
+    fn draw_node_palette(&mut self, ctx: &egui::Context, world_to_screen: impl Fn(Pos2) -> Pos2) {
+        let Some(palette) = self.node_palette.as_mut() else { return };
+        let kinds: Vec<NodeKind> = NodeKind::ALL.into_iter().filter(|k| fuzzy_match(&palette.filter, k.title())).collect();
+        ctx.input_mut(|i| {
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) { palette.highlighted += 1; }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) { palette.highlighted = palette.highlighted.saturating_sub(1); }
+        });
+        palette.highlighted = palette.highlighted.min(kinds.len().saturating_sub(1));
+        let world = palette.world;
+        let mut picked = None;
+        let area = egui::Area::new(egui::Id::new("node_palette")).fixed_pos(world_to_screen(world)).order(egui::Order::Foreground).show(ctx, |ui| {
+            Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label("Add node");
+                let r = ui.add(egui::TextEdit::singleline(&mut palette.filter).hint_text("🔍 Filter").desired_width(160.0));
+                if r.changed() { palette.highlighted = 0; }
+                if !r.has_focus() && !r.lost_focus() { r.request_focus(); }
+                for (index, kind) in kinds.iter().enumerate() {
+                    if ui.selectable_label(index == palette.highlighted, format!("{} {}", kind.icon(), kind.title())).clicked() { picked = Some(*kind); }
+                }
+                if kinds.is_empty() { ui.weak("No matching node type"); }
+            });
+        });
+        if ctx.input(|i| i.key_pressed(egui::Key::Enter)) { picked = picked.or(kinds.get(palette.highlighted).copied()); }
+        if let Some(kind) = picked { self.node_palette = None; self.quick_create(world, kind); }
+        else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) || (ctx.input(|i| i.pointer.any_pressed()) && !area.response.contains_pointer()) { self.node_palette = None; }
+    }
+
+    /// Shows `node_preview_ui` once the pointer has rested on a node for `PREVIEW_DELAY_SECS`, unless something is being dragged or the node is being typed into.
+    fn draw_hover_preview(&mut self, ui: &egui::Ui, ctx: &egui::Context, screen_to_world: impl Fn(Pos2) -> Pos2) {
+        let busy = ctx.input(|i| i.pointer.any_down()) || self.state.dragging.is_some() || self.image_preview.is_some() || self.presentation.is_some();
+        let hovered = ctx.input(|i| i.pointer.hover_pos()).filter(|pos| !busy && ctx.layer_id_at(*pos) == Some(ui.layer_id())).and_then(|pos| self.state.node_at(screen_to_world(pos)));
+        let Some(id) = hovered else { self.hover_preview = None; return };
+        let since = match self.hover_preview { Some((prev, since)) if prev == id => since, _ => { let now = Instant::now(); self.hover_preview = Some((id, now)); now } };
+        let node = &self.state.nodes[&id];
+        let editing = ctx.memory(|m| m.focused()).and_then(|f| ctx.read_response(f)).is_some_and(|r| node.bounds().contains(screen_to_world(r.rect.center())));
+        let waited = since.elapsed().as_secs_f32();
+        if editing { return; }
+        if waited < PREVIEW_DELAY_SECS { ctx.request_repaint_after(std::time::Duration::from_secs_f32(PREVIEW_DELAY_SECS - waited)); return; }
+        egui::show_tooltip_at_pointer(ctx, ui.layer_id(), egui::Id::new(("node_preview", id)), |ui| node_preview_ui(ui, node));
+    }
+
+    /// Sticky notes with an always-editable text body; the strip along the top drags them.
+    fn draw_annotations(&mut self, ui: &mut egui::Ui, painter: &egui::Painter, world_to_screen: impl Fn(Pos2) -> Pos2) {
+        let zoom = self.state.camera_zoom;
+        let theme = self.settings.theme.palette();
+        let mut finished_edit = None;
+        for note in &mut self.state.annotations {
+            let rect = Rect::from_min_max(world_to_screen(note.rect.min), world_to_screen(note.rect.max));
+            if !painter.clip_rect().intersects(rect) { continue; }
+            let header = Rect::from_min_max(rect.min, world_to_screen(note.header().max));
+            painter.rect(rect, 4.0 * zoom, note.color.gamma_multiply(0.25), Stroke::new(1.0, note.color.gamma_multiply(0.8)));
+            painter.rect_filled(header, egui::Rounding { nw: 4.0 * zoom, ne: 4.0 * zoom, sw: 0.0, se: 0.0 }, note.color.gamma_multiply(0.6));
+            let body = Rect::from_min_max(Pos2::new(rect.left(), header.bottom()), rect.max).shrink(6.0 * zoom.min(1.0));
+            if zoom >= LOD_DETAIL_ZOOM {
+                let before = note.clone();
+                let r = ui.put(body, egui::TextEdit::multiline(&mut note.text).frame(false).hint_text("Note…").text_color(theme.text).desired_width(body.width()));
+                if r.gained_focus() { self.note_text_before = Some(before); }
+                if r.lost_focus() { finished_edit = self.note_text_before.take(); }
+            } else {
+                painter.with_clip_rect(body).text(body.min, egui::Align2::LEFT_TOP, &note.text, egui::FontId::proportional(14.0 * zoom), theme.text);
+            }
+            let grip = resize_handle_rect(rect).shrink(3.0);
+            painter.line_segment([grip.left_bottom(), grip.right_top()], Stroke::new(1.0, theme.handle));
+        }
+        if let Some(before) = finished_edit {
+            if let Some(after) = self.state.annotations.iter().find(|a| a.id == before.id).filter(|a| a.text != before.text).cloned() { self.state.history.push(Command::EditAnnotation { before, after }); }
+        }
+    }
+
+    /// Group frames with their title bars; double-clicking a title (handled by the canvas) edits it in place.
+    fn draw_groups(&mut self, ui: &mut egui::Ui, painter: &egui::Painter, world_to_screen: impl Fn(Pos2) -> Pos2) {
+        let zoom = self.state.camera_zoom;
+        let theme = self.settings.theme.palette();
+        let mut to_remove = None;
+        let mut title_edit = None;
+        for group in &self.state.groups {
+            let rect = Rect::from_min_max(world_to_screen(group.rect.min), world_to_screen(group.rect.max));
+            if !painter.clip_rect().intersects(rect) { continue; }
+            let header = Rect::from_min_max(rect.min, world_to_screen(group.header().max));
+            painter.rect(rect, 10.0 * zoom, theme.group_fill, Stroke::new(1.0, theme.node_border));
+            painter.rect_filled(header, egui::Rounding { nw: 10.0 * zoom, ne: 10.0 * zoom, sw: 0.0, se: 0.0 }, theme.group_header);
+            let font = egui::FontId::proportional((16.0 * zoom).clamp(8.0, 32.0));
+            match self.editing_group_title.as_mut().filter(|(id, _)| *id == group.id) {
+                Some((_, draft)) => {
+                    let r = ui.put(header.shrink2(Vec2::new(8.0, 3.0)), egui::TextEdit::singleline(draft).font(font));
+                    if r.lost_focus() { title_edit = Some(!ui.input(|i| i.key_pressed(egui::Key::Escape))); }
+                    else if !r.has_focus() { r.request_focus(); }
                 }
+                None => { painter.text(header.left_center() + Vec2::new(10.0, 0.0), egui::Align2::LEFT_CENTER, &group.title, font, theme.text); }
             }
-            if ctx.input(|i| i.pointer.primary_released()) { self.selection_start = None; }
-        } else { self.selection_start = None; }
+            if zoom >= LOD_DETAIL_ZOOM && ui.put(Rect::from_center_size(header.right_center() - Vec2::new(16.0, 0.0), Vec2::splat(20.0)), egui::Button::new("✕").small().frame(false)).on_hover_text("Delete group (keeps its nodes)").clicked() { to_remove = Some(group.id); }
+            let grip = resize_handle_rect(rect).shrink(3.0);
+            painter.line_segment([grip.left_bottom(), grip.right_top()], Stroke::new(1.0, theme.handle));
+        }
+        if let Some(commit) = title_edit {
+            if let (true, Some((id, draft))) = (commit, self.editing_group_title.take()) { self.state.set_group_title(id, draft.trim().to_string()); }
+            self.editing_group_title = None;
+        }
+        if let Some(id) = to_remove { self.state.remove_group(id); }
+    }
+
+    fn canvas_context_menu(&mut self, ui: &mut egui::Ui) {
+        if let Some(edge_id) = self.context_edge.filter(|id| self.state.edges.iter().any(|e| e.id == *id)) { self.edge_context_menu(ui, edge_id); return; }
+        if let Some(note_id) = self.context_note.filter(|id| self.state.annotations.iter().any(|a| a.id == *id)) {
+            ui.label("Note");
+            ui.separator();
+            for (color, name) in NOTE_COLORS {
+                if ui.button(egui::RichText::new(format!("■ {}", name)).color(color)).clicked() { self.state.set_annotation_color(note_id, color); ui.close_menu(); }
+            }
+            ui.separator();
+            if ui.button("🗑 Delete note").clicked() { self.state.remove_annotation(note_id); ui.close_menu(); }
+            return;
+        }
+        let Some(id) = self.context_node.filter(|id| self.state.nodes.contains_key(id)) else {
+            if ui.button("➕ Add node…").clicked() { if let Some(world) = self.context_world { self.open_palette(world); } ui.close_menu(); }
+            if ui.button("🗒 Add note").clicked() { if let Some(world) = self.context_world { self.state.add_annotation(world); } ui.close_menu(); }
+            return;
+        };
+        ui.label(format!("Node {}", id));
+        ui.separator();
+        if ui.button("⧉ Duplicate").clicked() { self.state.duplicate_node(id); ui.close_menu(); }
+        if ui.button("✂ Disconnect all edges").clicked() { self.state.disconnect_node(id); ui.close_menu(); }
+        if ui.button("🔗 Start link from here").clicked() { self.state.linking_from = vec![id]; ui.close_menu(); }
+        let calls_server = self.plan_request(id).is_some();
+        if calls_server && ui.button("🔍 Preview request").clicked() { self.request_preview = Some(id); ui.close_menu(); }
+        if calls_server && ui.button("⟳ Force refresh").on_hover_text("Run again, skipping the response cache").clicked() { self.force_refresh(id, ui.ctx()); ui.close_menu(); }
+        if ui.button("⬆ Bring to front").clicked() { self.state.bring_to_front(id); ui.close_menu(); }
+        if let Some(n) = self.state.nodes.get_mut(&id) { if ui.checkbox(&mut n.auto_size, "↕ Auto-size height").clicked() { ui.close_menu(); } }
+        let selected = self.state.selected_ids();
+        ui.menu_button("📐 Arrange", |ui| {
+            for op in Arrange::ALL {
+                if ui.add_enabled(selected.len() >= 2, egui::Button::new(op.label())).clicked() { self.state.arrange(&selected, op); ui.close_menu(); }
+            }
+        });
+        if ui.button("▭ Group selection").clicked() { self.state.add_group(&selected); ui.close_menu(); }
+        if selected.len() >= 2 {
+            ui.menu_button(format!("☰ Selection ({})", selected.len()), |ui| { if selection::selection_actions(ui, &mut self.state, &selected) { ui.close_menu(); } });
+        }
+        ui.separator();
+        if ui.button("🗑 Delete").clicked() { self.state.remove_nodes(&[id]); ui.close_menu(); }
+    }
+
+    fn handle_selection(&mut self, ctx: &egui::Context, response: &egui::Response, world_to_screen: impl Fn(Pos2) -> Pos2, screen_to_world: impl Fn(Pos2) -> Pos2) {
+        if response.drag_started() && ctx.input(|i| i.modifiers.shift) {
+            if let Some(origin) = ctx.input(|i| i.pointer.press_origin()) {
+                let world = screen_to_world(origin);
+                if !self.state.nodes.values().any(|n| n.bounds().contains(world)) { self.selection_start = Some(world); }
+            }
+        }
+        let Some(start) = self.selection_start else { return };
+        if let Some(current_pos) = ctx.input(|i| i.pointer.hover_pos()) {
+            let selection_world = Rect::from_two_pos(start, screen_to_world(current_pos));
+            let selection_rect = Rect::from_two_pos(world_to_screen(selection_world.min), world_to_screen(selection_world.max));
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Tooltip, "selection".into()));
+            let selection = self.settings.theme.palette().selection;
+            painter.rect_filled(selection_rect, 0.0, selection.gamma_multiply(0.15));
+            painter.rect_stroke(selection_rect, 0.0, Stroke::new(1.0, selection));
+            for node in self.state.nodes.values_mut() { node.selected = selection_world.intersects(node.bounds()); }
+        }
+        if response.drag_stopped() || !ctx.input(|i| i.pointer.primary_down()) { self.selection_start = None; }
     }
 }
 
 impl eframe::App for StoryBoardApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings);
+        eframe::set_value(storage, TEMPLATES_KEY, &self.templates);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let start_time = Instant::now();
         let animation_target = if self.app_state == AppState::Intro { 1.0 } else { 0.0 };
         self.intro_animation = ctx.animate_value_with_time(egui::Id::new("intro"), animation_target, 0.5);
 
-        if self.app_state == AppState::Editing {
+        let show_sidebar = self.app_state == AppState::Editing && self.presentation.is_none();
+        if show_sidebar && self.settings.sidebar_collapsed { self.draw_sidebar_rail(ctx); }
+        else if show_sidebar {
             egui::SidePanel::left("sidebar").resizable(true).default_width(220.0).show(ctx, |ui| {
                 ui.vertical(|ui| {
-                    ui.add_space(10.0); ui.heading("🎬 StoryBoard AI"); ui.separator();
-                    ui.label("Add New Node:");
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.heading("🎬 StoryBoard AI");
+                        if ui.small_button("«").on_hover_text("Collapse sidebar").clicked() { self.settings.sidebar_collapsed = true; }
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(self.state.history.can_undo(), egui::Button::new("↩ Undo")).on_hover_text("Ctrl+Z").clicked() { self.state.undo(); }
+                        if ui.add_enabled(self.state.history.can_redo(), egui::Button::new("↪ Redo")).on_hover_text("Ctrl+Shift+Z").clicked() { self.state.redo(); }
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Add New Node:");
+                        if ui.small_button("➕").on_hover_text("Quick-add palette (Shift+A)").clicked() { self.open_palette(self.state.camera_offset.to_pos2()); }
+                    });
                     ui.horizontal_wrapped(|ui| {
-                        if ui.button("🧠 Concept").clicked() {
-                            let pos = Pos2::new(self.state.camera_offset.x, self.state.camera_offset.y);
-                            self.add_node(pos, NodeData::Concept { text: "New Idea".to_string() });
-                        }
-                        if ui.button("🌐 Research").clicked() {
-                            let pos = Pos2::new(self.state.camera_offset.x, self.state.camera_offset.y);
-                            self.add_node(pos, NodeData::YouComResearch { query: "Topic".to_string(), result: None, is_loading: false });
-                        }
-                        if ui.button("🤖 AI").clicked() {
-                            let pos = Pos2::new(self.state.camera_offset.x, self.state.camera_offset.y);
-                            self.add_node(pos, NodeData::AgnosticAI { model: "google/gemini-flash-1.5".to_string(), prompt: "Prompt".to_string(), result: None, is_loading: false });
-                        }
-                        if ui.button("🎨 Visual").clicked() {
-                            let pos = Pos2::new(self.state.camera_offset.x, self.state.camera_offset.y);
-                            self.add_node(pos, NodeData::Visual { prompt: "Scene".to_string(), texture: None, is_loading: false });
+                        for kind in [NodeKind::Concept, NodeKind::Research, NodeKind::AgnosticAI, NodeKind::Visual, NodeKind::Merge, NodeKind::Compare, NodeKind::Branch, NodeKind::Select, NodeKind::Note, NodeKind::Audio, NodeKind::Script, NodeKind::Character, NodeKind::Translate, NodeKind::WebFetch, NodeKind::Transform, NodeKind::Frame, NodeKind::MarkdownView, NodeKind::Transcribe] {
+                            if ui.button(format!("{} {}", kind.icon(), kind.short_label())).on_hover_text("Shift-click to add without connecting").clicked() { self.create_node(kind, !ui.input(|i| i.modifiers.shift)); }
                         }
                     });
+                    ui.checkbox(&mut self.settings.auto_connect, "Auto-connect new nodes").on_hover_text("Link a new node from the one selected node and place it to its right; Shift-click an add button to skip");
+                    self.templates_section(ui);
                     ui.add_space(10.0); ui.separator(); ui.label("Active Nodes:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.node_filter).hint_text("🔍 Search nodes").desired_width(150.0));
+                        if !self.node_filter.is_empty() && ui.small_button("✕").clicked() { self.node_filter.clear(); }
+                    });
+                    ui.horizontal_wrapped(|ui| {
+                        for kind in NodeKind::ALL {
+                            let shown = !self.hidden_kinds.contains(&kind);
+                            if ui.selectable_label(shown, kind.icon()).on_hover_text(kind.title()).clicked() {
+                                if shown { self.hidden_kinds.push(kind); } else { self.hidden_kinds.retain(|&k| k != kind); }
+                            }
+                        }
+                    });
+                    let query = self.node_filter.trim().to_lowercase();
                     let mut to_delete = None;
+                    let mut to_duplicate = None;
+                    let mut to_focus = None;
+                    let mut to_toggle = None;
+                    let mut to_preview = None;
                     egui::ScrollArea::vertical().show(ui, |ui| {
-                        let mut ids: Vec<u64> = self.state.nodes.keys().copied().collect(); ids.sort();
+                        let mut ids: Vec<u64> = self.state.nodes.values()
+                            .filter(|n| !self.hidden_kinds.contains(&n.data.kind()))
+                            .filter(|n| query.is_empty() || n.title.iter().map(String::as_str).chain(n.data.primary_text()).any(|t| t.to_lowercase().contains(&query)))
+                            .map(|n| n.id).collect();
+                        ids.sort();
                         for id in ids {
                             ui.horizontal(|ui| {
                                 let node = &self.state.nodes[&id];
-                                let icon = match &node.data { NodeData::Concept { .. } => "🧠", NodeData::YouComResearch { .. } => "🌐", NodeData::AgnosticAI { .. } => "🤖", NodeData::Visual { .. } => "🎨", NodeData::FoxitExport { .. } => "📄" };
-                                if ui.selectable_label(node.selected, format!("{} Node {}", icon, id)).clicked() { self.state.camera_offset = node.position.to_vec2(); }
+                                // Visual rows lead with the generated image, drawn from the node's own texture.
+                                if let NodeData::Visual { texture, .. } = &node.data {
+                                    match texture {
+                                        Some(tex) => {
+                                            if ui.add(egui::Image::new(tex).fit_to_exact_size(SIDEBAR_THUMBNAIL).sense(Sense::click())).on_hover_text("Click to preview").clicked() { to_preview = Some(id); }
+                                        }
+                                        None => { let (rect, _) = ui.allocate_exact_size(SIDEBAR_THUMBNAIL, Sense::hover()); ui.painter().rect_stroke(rect, 3.0, Stroke::new(1.0, ui.visuals().weak_text_color())); }
+                                    }
+                                }
+                                if !matches!(node.data, NodeData::Concept { .. } | NodeData::Merge { .. } | NodeData::Note { .. }) {
+                                    let status = node.status();
+                                    let (dot, _) = ui.allocate_exact_size(Vec2::splat(10.0), Sense::hover());
+                                    ui.painter().circle_filled(dot.center(), 4.0, status.color(&self.settings.theme.palette(), ui.input(|i| i.time)));
+                                    ui.interact(dot, ui.id().with(("status", id)), Sense::hover()).on_hover_text(status.label());
+                                    if node.stale {
+                                        let (dot, _) = ui.allocate_exact_size(Vec2::splat(10.0), Sense::hover());
+                                        ui.painter().circle_filled(dot.center(), 4.0, STATUS_STALE);
+                                        ui.interact(dot, ui.id().with(("stale", id)), Sense::hover()).on_hover_text("Stale: an upstream result changed since this node last ran");
+                                    }
+                                }
+                                ui.vertical(|ui| {
+                                    let title = format!("{} {} · {}", node.data.kind().icon(), id, truncate(node.display_title(), 22));
+                                    let text = node.data.primary_text().filter(|t| !t.trim().is_empty());
+                                    let r = ui.selectable_label(node.selected, title);
+                                    let summary = format!("Node {} – {}: {}", id, node.display_title(), text.map_or("empty".to_string(), |t| truncate(&t.replace('\n', " "), SIDEBAR_PREVIEW_CHARS)));
+                                    r.widget_info(|| egui::WidgetInfo::selected(egui::WidgetType::SelectableLabel, true, node.selected, &summary));
+                                    if r.on_hover_ui(|ui| { node_preview_ui(ui, node); ui.separator(); ui.weak("Shift-click to add to or remove from the selection"); }).clicked() {
+                                        if ui.input(|i| i.modifiers.shift) { to_toggle = Some(id); } else { to_focus = Some(id); }
+                                    }
+                                    match (&node.data, text) {
+                                        (NodeData::FoxitExport { status, .. }, _) => ui.small(truncate(status, SIDEBAR_PREVIEW_CHARS)),
+                                        (NodeData::Merge { output, .. } | NodeData::Compare { output, .. } | NodeData::Select { output, .. } | NodeData::Transform { output, .. }, _) if !output.trim().is_empty() => ui.small(truncate(&output.replace('\n', " "), SIDEBAR_PREVIEW_CHARS)),
+                                        (_, Some(text)) => ui.small(truncate(&text.replace('\n', " "), SIDEBAR_PREVIEW_CHARS)),
+                                        (_, None) => ui.weak("empty"),
+                                    };
+                                });
+                                if ui.button("⧉").on_hover_text("Duplicate").clicked() { to_duplicate = Some(id); }
                                 if ui.button("🗑").clicked() { to_delete = Some(id); }
                             });
                         }
                     });
-                    if let Some(id) = to_delete { self.state.nodes.remove(&id); self.state.edges.retain(|e| e.from != id && e.to != id); }
+                    if let Some(id) = to_delete { self.state.remove_nodes(&[id]); }
+                    if let Some(id) = to_duplicate { self.state.duplicate_node(id); }
+                    if let Some(id) = to_focus { self.state.select_only(id); self.focus_camera_on(id); }
+                    if let Some(n) = to_toggle.and_then(|id| self.state.nodes.get_mut(&id)) { n.selected = !n.selected; }
+                    if let Some(id) = to_preview { self.image_preview = Some(ImagePreview { id, offset: Vec2::ZERO, zoom: 1.0 }); }
+                    let selected = self.state.selected_ids();
+                    if !selected.is_empty() {
+                        ui.label(format!("{} nodes selected", selected.len()));
+                        ui.horizontal_wrapped(|ui| { selection::selection_actions(ui, &mut self.state, &selected); });
+                    }
+                    self.sidebar_edge = None;
+                    let mut edge_to_delete = None;
+                    egui::CollapsingHeader::new(format!("Connections ({})", self.state.edges.len())).id_salt("connections").show(ui, |ui| {
+                        egui::ScrollArea::vertical().id_salt("connections_scroll").max_height(160.0).show(ui, |ui| {
+                            for edge in &self.state.edges {
+                                let end = |id: u64| self.state.nodes.get(&id).map_or("⚠".to_string(), |n| format!("{} #{}", n.data.kind().icon(), id));
+                                let dangling = !self.state.nodes.contains_key(&edge.from) || !self.state.nodes.contains_key(&edge.to);
+                                let row = ui.horizontal(|ui| {
+                                    let text = egui::RichText::new(format!("{} → {}", end(edge.from), end(edge.to)));
+                                    let r = ui.selectable_label(self.state.selected_edge == Some(edge.id), if dangling { text.color(ui.visuals().warn_fg_color) } else { text });
+                                    let r = if dangling { r.on_hover_text(format!("Edge {} points at a deleted node", edge.id)) } else { r };
+                                    if r.clicked() { self.state.selected_edge = Some(edge.id); }
+                                    if ui.button("🗑").on_hover_text("Delete connection").clicked() { edge_to_delete = Some(edge.id); }
+                                }).response;
+                                if row.contains_pointer() { self.sidebar_edge = Some(edge.id); }
+                            }
+                            if self.state.edges.is_empty() { ui.weak("No connections yet"); }
+                        });
+                    });
+                    if let Some(id) = edge_to_delete { self.state.remove_edge(id); }
+                    if let [id] = self.state.selected_ids()[..] { self.draw_properties(ui, ctx, id); }
                     ui.separator(); ui.label("Pipeline:");
-                    if self.state.linking_from.is_some() {
-                        if ui.button("🚫 Cancel").clicked() { self.state.linking_from = None; }
-                        ui.label("Click target node...");
-                    } else {
-                        if ui.button("🔗 Create Link").clicked() {
-                            if let Some(&id) = self.state.nodes.iter().find(|(_, n)| n.selected).map(|(id, _)| id) { self.state.linking_from = Some(id); }
+                    match self.pipeline.as_ref() {
+                        Some(run) => {
+                            let (mut cancel, mut jump) = (false, None);
+                            let total = run.order.len().max(1);
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                match run.current.and_then(|id| self.state.nodes.get(&id)) {
+                                    Some(node) => {
+                                        let elapsed = run.current_started.map_or(0.0, |s| s.elapsed().as_secs_f32());
+                                        ui.label(format!("Running {}/{} —", run.next, total));
+                                        if ui.link(format!("{} #{} ({:.1}s)…", node.display_title(), node.id, elapsed)).on_hover_text("Show on the canvas").clicked() { jump = Some(node.id); }
+                                    }
+                                    None => { ui.label(format!("Running {}/{}", run.next, total)); }
+                                }
+                                if ui.button("⏹ Cancel").on_hover_text("F5").clicked() { cancel = true; }
+                            });
+                            ui.add(egui::ProgressBar::new(run.done.len() as f32 / total as f32).desired_height(6.0));
+                            for &(id, took) in &run.done {
+                                let title = self.state.nodes.get(&id).map_or("(deleted)".to_string(), |n| n.display_title().to_string());
+                                ui.small(format!("✔ {} #{} — {:.1}s", title, id, took.as_secs_f32()));
+                            }
+                            if let Some(id) = jump { self.focus_camera_on(id); }
+                            if cancel { self.finish_pipeline(Some("cancelled".to_string())); }
                         }
+                        None => {
+                            ui.horizontal(|ui| {
+                                if ui.add_enabled(!self.state.nodes.is_empty(), egui::Button::new("▶ Run Pipeline")).on_hover_text("Run every node in dependency order (F5)").clicked() { self.start_pipeline(); }
+                                let stale = self.state.stale_count();
+                                if stale > 0 && ui.button(format!("🔁 Re-run stale ({})", stale)).on_hover_text("Run only the nodes whose inputs changed, in dependency order").clicked() { self.start_stale_run(); }
+                                if ui.button("🩺 Validate").on_hover_text("Check the graph for loops, broken links, placeholders and unconnected nodes").clicked() { self.show_validation = !self.show_validation; }
+                            });
+                            if let Some(summary) = &self.pipeline_summary {
+                                let mut dismiss = false;
+                                ui.horizontal(|ui| {
+                                    let color = if summary.failure.is_some() { ui.visuals().warn_fg_color } else { ui.visuals().text_color() };
+                                    ui.label(egui::RichText::new(summary.text()).color(color).small());
+                                    if ui.small_button("✖").on_hover_text("Dismiss").clicked() { dismiss = true; }
+                                });
+                                if dismiss { self.pipeline_summary = None; }
+                            }
+                        }
+                    }
+                    if !self.request_queue.is_empty() || !self.retries.is_empty() {
+                        let waiting = self.request_queue.len();
+                        if ui.button(format!("⏹ Stop all ({} queued)", waiting)).on_hover_text("Drop queued requests and pending retries; requests already sent still finish").clicked() { self.stop_all(); }
+                    }
+                    if !self.state.linking_from.is_empty() {
+                        if ui.button("🚫 Cancel").clicked() { self.state.linking_from.clear(); }
+                        ui.label(format!("Click target node for {} source(s)...", self.state.linking_from.len()));
+                    } else if ui.add_enabled(!selected.is_empty(), egui::Button::new("🔗 Create Link")).clicked() {
+                        self.state.linking_from = selected.clone();
+                    }
+                    ui.separator(); ui.label("Arrange:");
+                    for op in Arrange::ALL {
+                        if ui.add_enabled(selected.len() >= 2, egui::Button::new(op.label())).clicked() { self.state.arrange(&selected, op); }
                     }
+                    if ui.button(if selected.is_empty() { "▭ New group" } else { "▭ Group selection" }).clicked() { self.state.add_group(&selected); }
+                    ui.separator(); ui.label("Layout:");
+                    if ui.button("⛶ Fit view").on_hover_text("F").clicked() { self.state.fit_all(self.canvas_rect.size()); }
+                    if ui.add_enabled(!selected.is_empty(), egui::Button::new("🔍 Zoom to selection")).on_hover_text("Z").clicked() { self.zoom_to_selection(); }
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.settings.snap_to_grid, "Snap to grid");
+                        ui.add(egui::DragValue::new(&mut self.settings.grid_size).range(5.0..=200.0).suffix(" u"));
+                    });
+                    if ui.button("🗂 Auto layout").on_hover_text("Arrange the pipeline left to right").clicked() { self.layout_animation = Some(self.state.auto_layout()); }
+                    ui.checkbox(&mut self.settings.align_guides, "Alignment guides").on_hover_text("Snap dragged nodes into line with their neighbours");
+                    egui::ComboBox::from_label("Scroll").selected_text(self.settings.scroll_mode.label()).width(150.0).show_ui(ui, |ui| {
+                        for mode in [ScrollMode::Zoom, ScrollMode::Pan] { ui.selectable_value(&mut self.settings.scroll_mode, mode, mode.label()); }
+                    });
+                    let mut physics = self.settings.physics_enabled;
+                    if ui.checkbox(&mut physics, "Physics").on_hover_text("Shift+P").changed() { self.set_physics_enabled(physics); }
+                    ui.horizontal(|ui| {
+                        ui.menu_button("👁 View", |ui| {
+                            ui.checkbox(&mut self.settings.show_grid, "Background grid");
+                            ui.checkbox(&mut self.settings.show_timeline, "🎞 Timeline strip").on_hover_text("Frame nodes in storyboard order along the bottom");
+                        });
+                        if ui.button(self.settings.theme.toggled().label()).on_hover_text("Switch theme").clicked() {
+                            self.settings.theme = self.settings.theme.toggled();
+                            ctx.set_visuals(self.settings.theme.visuals());
+                        }
+                    });
+                    ui.separator(); ui.label("Export:");
+                    ui.horizontal(|ui| { ui.label("Project:"); ui.text_edit_singleline(&mut self.project_name); });
+                    if ui.button("📰 Export HTML report").clicked() { self.trigger_html_report(ctx.clone()); }
+                    ui.checkbox(&mut self.settings.export_notes, "Include notes in PDF export");
+                    ui.checkbox(&mut self.settings.export_history, "Include earlier results in PDF export");
+                    ui.checkbox(&mut self.settings.auto_retry, "Retry failed requests automatically").on_hover_text(format!("Up to {} more attempts, 1s, 2s and 4s apart", MAX_RETRIES));
+                    ui.horizontal(|ui| { ui.label("Parallel requests:"); ui.add(egui::DragValue::new(&mut self.settings.max_concurrent).range(1..=8)).on_hover_text("More than this wait in a queue"); });
+                    ui.horizontal(|ui| {
+                        ui.label("Cache:");
+                        ui.add(egui::DragValue::new(&mut self.settings.cache_mb).range(0..=1024).suffix(" MB")).on_hover_text("Identical requests reuse a recent answer; 0 turns the cache off");
+                        ui.add_enabled(self.settings.cache_mb > 0, egui::DragValue::new(&mut self.settings.cache_ttl_mins).range(1..=1440).suffix(" min")).on_hover_text("How long an answer is reused");
+                    });
+                    if self.cache.len() > 0 && ui.small_button(format!("🧹 Clear cache ({} answers, {:.1} MB)", self.cache.len(), self.cache.bytes() as f32 / (1024.0 * 1024.0))).clicked() { self.cache.clear(); }
                     ui.add_space(10.0); ui.separator();
-                    if ui.button("🗑 Clear Canvas").clicked() { self.state.nodes.clear(); self.state.edges.clear(); }
-                    ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
-                        ui.label(format!("FPS: {:.0}", 1000.0 / (self.frame_times.iter().sum::<f32>() / self.frame_times.len().max(1) as f32)));
+                    if ui.button("▶ Present").on_hover_text("Step through the pipeline (P)").clicked() { self.start_presentation(); }
+                    if ui.button("⌨ Keyboard shortcuts").clicked() { self.show_shortcuts = !self.show_shortcuts; }
+                    if ui.button("🗑 Clear Canvas").clicked() { let ids: Vec<u64> = self.state.nodes.keys().copied().collect(); self.state.remove_nodes(&ids); }
+                    ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                        self.perf.show(ui, self.state.nodes.len(), self.state.edges.len(), self.in_flight.load(Ordering::Relaxed));
                     });
                 });
             });
         }
 
+        if show_sidebar { self.draw_board_tabs(ctx); self.draw_run_log(ctx); if self.settings.show_timeline { self.draw_timeline(ctx); } }
+
+        if self.app_state == AppState::Editing {
+            if self.presentation.is_some() { self.presentation_input(ctx); }
+            else if self.image_preview.is_none() {
+                for action in self.shortcuts.poll(ctx) { self.run_action(ctx, action); }
+                self.nudge_selection(ctx);
+            }
+            // With a text field focused the paste belongs to it.
+            if !ctx.wants_keyboard_input() && self.presentation.is_none() {
+                let pasted: Vec<String> = ctx.input(|i| i.events.iter().filter_map(|e| match e { egui::Event::Paste(text) => Some(text.clone()), _ => None }).collect());
+                for text in pasted { self.paste_text(&text); }
+            }
+            let mut show_shortcuts = self.show_shortcuts;
+            self.shortcuts.show_help(ctx, &mut show_shortcuts);
+            self.show_shortcuts = show_shortcuts;
+        }
+
         while let Ok(msg) = self.http_rx.try_recv() {
+            if let AppMessage::TextResponse(id, _) | AppMessage::ImageResponse(id, None, _) | AppMessage::AudioResponse(id, _) = &msg { self.retries.remove(id); self.version_views.remove(id); }
+            if let AppMessage::TextResponse(id, _) | AppMessage::ImageResponse(id, _, _) | AppMessage::AudioResponse(id, _) = &msg { if let Some(node) = self.node_mut(*id) { node.stale = false; } }
+            self.stale_check = true;
             match msg {
-                AppMessage::TextResponse(id, text) => { if let Some(node) = self.state.nodes.get_mut(&id) { match &mut node.data { NodeData::YouComResearch { result: r, is_loading, .. } => { *r = Some(text); *is_loading = false; } NodeData::AgnosticAI { result: r, is_loading, .. } => { *r = Some(text); *is_loading = false; } NodeData::FoxitExport { status, is_loading } => { *status = text; *is_loading = false; } _ => {} } } }
-                AppMessage::ImageResponse(id, bytes) => { if let Some(node) = self.state.nodes.get_mut(&id) { if let NodeData::Visual { texture, is_loading, .. } = &mut node.data { *is_loading = false; if let Ok(image) = image::load_from_memory(&bytes) { let size = [image.width() as usize, image.height() as usize]; let image_buffer = image.to_rgba8(); let color_image = egui::ColorImage::from_rgba_unmultiplied(size, image_buffer.as_raw()); *texture = Some(ctx.load_texture(format!("node-image-{}", id), color_image, egui::TextureOptions::LINEAR)); } } } }
-                AppMessage::Error(id, _err) => { if let Some(node) = self.state.nodes.get_mut(&id) { match &mut node.data { NodeData::YouComResearch { is_loading, .. } => *is_loading = false, NodeData::AgnosticAI { is_loading, .. } => *is_loading = false, NodeData::Visual { is_loading, .. } => *is_loading = false, NodeData::FoxitExport { is_loading, .. } => *is_loading = false, _ => {} } } }
+                AppMessage::TextResponse(id, text) => { if let Some(node) = self.node_mut(id) { node.archive_result(); match &mut node.data { NodeData::YouComResearch { result: r, is_loading, .. } => { *r = Some(text); *is_loading = false; } NodeData::AgnosticAI { result: r, is_loading, .. } | NodeData::Translate { result: r, is_loading, .. } | NodeData::WebFetch { result: r, is_loading, .. } => { *r = Some(text); *is_loading = false; } NodeData::FoxitExport { status, is_loading, .. } => { *status = text; *is_loading = false; } NodeData::Branch { outcome, is_loading, .. } => { *outcome = Some(branch::parse_yes_no(&text)); *is_loading = false; } script @ NodeData::Script { .. } => { if let Err(err) = script.apply_scene(&text) { node.error = Some(err); } } NodeData::Transcribe { transcript, is_loading, .. } => { *transcript = text; *is_loading = false; } _ => {} } } self.reschedule_refresh(id); self.auto_run_children(id, ctx); }
+                AppMessage::ImageResponse(id, Some(index), bytes) => self.receive_variant(ctx, id, index, Ok(bytes)),
+                AppMessage::VariantError(id, index, err) => self.receive_variant(ctx, id, index, Err(err)),
+                AppMessage::ImageResponse(id, None, bytes) => {
+                    if let Some(node) = self.node_mut(id) {
+                        let texture = load_node_texture(ctx, id, &bytes);
+                        if texture.is_some() { node.archive_result(); }
+                        if let NodeData::Visual { texture: current, image: raw, is_loading, image_seed, request_seeds, .. } = &mut node.data { *is_loading = false; if let Some(tex) = texture { *current = Some(tex); *raw = Some(bytes); *image_seed = request_seeds.first().copied(); } else { node.error = Some("The server sent an image that couldn't be decoded".to_string()); } }
+                    }
+                }
+                AppMessage::AudioResponse(id, bytes) => {
+                    if self.player.position(id).is_some() { self.player.stop(); }
+                    if let Some(NodeData::Audio { audio, is_loading, .. }) = self.node_mut(id).map(|n| &mut n.data) { *audio = Some(bytes.into()); *is_loading = false; }
+                }
+                AppMessage::HtmlReport(Ok(bytes)) => self.download("storyboard_report.html", "text/html", &bytes),
+                AppMessage::HtmlReport(Err(err)) => self.toast(format!("⚠ Couldn't build the report: {}", err)),
+                AppMessage::AudioFile(id, name, bytes) => self.attach_audio(id, name, bytes),
+                AppMessage::LocalModels(models) => self.local_models = match models { Ok(names) => local_ai::LocalModels::Listed(names), Err(err) => local_ai::LocalModels::Failed(err) },
+                AppMessage::Logged(entry) => runlog::push_entry(&mut self.run_log, entry),
+                AppMessage::CacheStore(key, response) => self.store_response(key, response),
+                AppMessage::Error(id, err) => { if let Some(node) = self.node_mut(id) { if let Some(flag) = node.data.loading_flag() { *flag = false; } node.error = Some(err); self.schedule_retry(id); } }
             }
         }
-        self.apply_physics();
-        egui::CentralPanel::default().frame(egui::Frame::none().fill(Color32::from_rgb(15, 15, 15))).show(ctx, |ui| {
+        if let Some(anim) = self.layout_animation.as_mut() {
+            if !anim.step(&mut self.state) { self.layout_animation = None; }
+        } else if self.settings.physics_enabled { let started = Instant::now(); self.apply_physics(); self.perf.record(Phase::Physics, started); }
+        self.state.recompute_outputs();
+        if std::mem::take(&mut self.stale_check) { self.state.mark_stale(); }
+        self.step_retries(ctx);
+        self.step_refresh(ctx);
+        self.player.poll();
+        if self.player.is_playing() { ctx.request_repaint_after(std::time::Duration::from_millis(250)); }
+        self.step_pipeline(ctx);
+        self.pump_requests(ctx);
+        self.step_camera_tween(ctx);
+        let theme = self.settings.theme.palette();
+        egui::CentralPanel::default().frame(egui::Frame::none().fill(theme.canvas_bg)).show(ctx, |ui| {
             let canvas_rect = ui.max_rect();
+            self.canvas_rect = canvas_rect;
             let (response, painter) = ui.allocate_painter(canvas_rect.size(), Sense::click_and_drag());
             let camera_offset = self.state.camera_offset;
             let camera_zoom = self.state.camera_zoom;
@@ -382,147 +2730,782 @@ impl eframe::App for StoryBoardApp {
             let screen_to_world = |pos: Pos2| { let center = canvas_rect.center(); let rel = (pos - center) / camera_zoom; Pos2::new(rel.x + camera_offset.x, rel.y + camera_offset.y) };
             
             // Only handle canvas inputs if intro is not fully showing
-            if self.app_state == AppState::Editing {
-                if response.dragged() && self.state.dragging_node.is_none() { self.state.camera_offset -= response.drag_delta() / camera_zoom; }
-                let scroll_delta = ctx.input(|i| i.raw_scroll_delta.y);
-                if scroll_delta != 0.0 {
-                    if let Some(pointer_pos) = ctx.input(|i| i.pointer.hover_pos()) {
-                        let zoom_factor = if scroll_delta > 0.0 { 1.1 } else { 0.9 };
-                        let world_pos_before = screen_to_world(pointer_pos);
-                        let new_zoom = (self.state.camera_zoom * zoom_factor).clamp(0.05, 5.0);
-                        self.state.camera_zoom = new_zoom;
-                        let center = canvas_rect.center();
-                        self.state.camera_offset = world_pos_before.to_vec2() - (pointer_pos - center) / self.state.camera_zoom;
+            // The image preview modal takes over pointer and scroll input while it's open; presenting leaves the canvas read-only.
+            if self.app_state == AppState::Editing && self.image_preview.is_none() && self.presentation.is_none() {
+                // Two-finger touch: pinch zooms around where the gesture began and moving both fingers pans.
+                let pinch = ctx.input(|i| i.multi_touch()).filter(|t| t.num_touches >= 2);
+                // Space+drag, middle-drag and touch gestures always pan, and never grab, resize or select nodes.
+                let panning = (ctx.input(|i| i.key_down(egui::Key::Space)) && !ctx.wants_keyboard_input()) || ctx.input(|i| i.pointer.middle_down()) || pinch.is_some();
+                if panning && response.hovered() { ctx.set_cursor_icon(if response.dragged() { egui::CursorIcon::Grabbing } else { egui::CursorIcon::Grab }); }
+                if !panning || self.selection_start.is_some() { self.handle_selection(ctx, &response, world_to_screen, screen_to_world); }
+                // Also true for a touch long-press.
+                if response.secondary_clicked() {
+                    let pos = response.interact_pointer_pos();
+                    self.context_node = pos.and_then(|pos| self.state.node_at(screen_to_world(pos)));
+                    self.context_world = pos.map(screen_to_world);
+                    self.context_edge = if self.context_node.is_some() { None } else { pos.and_then(|pos| self.state.edge_at(pos, world_to_screen)) };
+                    self.context_note = if self.context_node.is_some() || self.context_edge.is_some() { None } else { pos.and_then(|pos| self.state.annotation_at(screen_to_world(pos))) };
+                    if let Some(id) = self.context_node.filter(|id| !self.state.nodes[id].selected) { self.state.select_only(id); }
+                    if self.context_edge.is_some() { self.state.selected_edge = self.context_edge; }
+                }
+                response.context_menu(|ui| self.canvas_context_menu(ui));
+                if response.double_clicked() {
+                    if let Some(group_id) = response.interact_pointer_pos().map(screen_to_world).filter(|pos| self.state.node_at(*pos).is_none()).and_then(|pos| self.state.group_header_at(pos)) {
+                        self.editing_group_title = self.state.groups.iter().find(|g| g.id == group_id).map(|g| (g.id, g.title.clone()));
+                    } else if let Some(edge_id) = response.interact_pointer_pos().filter(|pos| self.state.node_at(screen_to_world(*pos)).is_none()).and_then(|pos| self.state.edge_at(pos, world_to_screen)) { self.start_edge_label_edit(edge_id); }
+                    else if let Some(world) = response.interact_pointer_pos().map(screen_to_world).filter(|pos| self.state.node_at(*pos).is_none()) {
+                        if ctx.input(|i| i.modifiers.alt) { self.open_palette(world); } else { self.quick_create(world, NodeKind::Concept); }
+                    }
+                }
+                self.handle_dropped_files(ctx, screen_to_world);
+                if let Some(touch) = pinch {
+                    // A node drag begun by the first finger is undone once it turns out to be a pinch.
+                    if let Some(drag) = self.state.dragging.take() { for (id, from) in drag.origins { if let Some(n) = self.state.nodes.get_mut(&id) { n.position = from; } } }
+                    // egui doesn't expose the live centroid; the gesture's start point is close enough as a zoom anchor.
+                    self.state.zoom_at(self.state.camera_zoom * touch.zoom_delta, touch.start_pos, canvas_rect);
+                    self.state.camera_offset -= touch.translation_delta / self.state.camera_zoom;
+                    self.camera_tween = None;
+                }
+                let pressed_on_node = ctx.input(|i| i.pointer.press_origin()).is_some_and(|p| self.state.node_at(screen_to_world(p)).is_some());
+                if response.dragged() && pinch.is_none() && (panning || (!pressed_on_node && self.state.dragging.is_none() && self.state.resizing_node.is_none() && self.state.dragging_group.is_none() && self.state.resizing_group.is_none() && self.state.dragging_note.is_none() && self.state.resizing_note.is_none() && !self.state.linking_drag && self.selection_start.is_none())) { self.state.camera_offset -= response.drag_delta() / camera_zoom; self.camera_tween = None; }
+                let (scroll, zoom_delta, zoom_modifier, shift) = ctx.input(|i| (i.raw_scroll_delta, i.zoom_delta(), i.modifiers.command, i.modifiers.shift));
+                // Some backends already turn Shift+wheel into horizontal scroll; for the rest, do it here.
+                let scroll = if shift && scroll.x == 0.0 { Vec2::new(scroll.y, 0.0) } else { scroll };
+                // In zoom mode only the vertical wheel zooms: sideways scroll still pans, and Shift makes the whole wheel pan.
+                let pan_all = self.settings.scroll_mode == ScrollMode::Pan || shift;
+                let pan = if zoom_modifier { Vec2::ZERO } else if pan_all { scroll } else { Vec2::new(scroll.x, 0.0) };
+                // `zoom_delta` covers Ctrl+scroll and trackpad pinch; touch pinches are handled above.
+                let zoom_factor = if pinch.is_none() && zoom_delta != 1.0 { zoom_delta } else if !pan_all && scroll.y != 0.0 { if scroll.y > 0.0 { 1.1 } else { 0.9 } } else { 1.0 };
+                if pan != Vec2::ZERO && response.hovered() {
+                    self.state.camera_offset -= pan / self.state.camera_zoom;
+                    self.camera_tween = None;
+                }
+                if zoom_factor != 1.0 {
+                    if let Some(pointer_pos) = ctx.input(|i| i.pointer.hover_pos()).filter(|p| canvas_rect.contains(*p)) {
+                        self.state.zoom_at(self.state.camera_zoom * zoom_factor, pointer_pos, canvas_rect);
+                        self.camera_tween = None;
                     }
                 }
                 if let Some(pointer_pos) = ctx.input(|i| i.pointer.interact_pos()) {
                     let world_pos = screen_to_world(pointer_pos);
-                    if response.drag_started() {
-                        let mut clicked_id = None;
-                        for node in self.state.nodes.values_mut() {
-                            if node.bounds().contains(world_pos) { clicked_id = Some(node.id); self.state.dragging_node = Some(node.id); node.selected = true; }
-                            else { node.selected = false; }
+                    if response.drag_started() && !panning && self.selection_start.is_none() {
+                        let press_pos = ctx.input(|i| i.pointer.press_origin()).unwrap_or(pointer_pos);
+                        if let Some(id) = self.state.topmost(|n| world_to_screen(n.output_port()).distance(press_pos) <= PORT_HIT_RADIUS) {
+                            self.state.linking_from = vec![id];
+                            self.state.linking_drag = true;
+                        } else {
+                            self.state.resizing_node = self.state.topmost(|n| !n.collapsed && resize_handle_rect(Rect::from_min_size(world_to_screen(n.position), n.size * camera_zoom)).contains(press_pos)).map(|id| (id, self.state.nodes[&id].size));
                         }
-                        if let (Some(from_id), Some(to_id)) = (self.state.linking_from, clicked_id) {
-                            if from_id != to_id { self.state.edges.push(Edge { id: self.state.next_id, from: from_id, to: to_id }); self.state.next_id += 1; }
-                            self.state.linking_from = None;
+                        let press_world = screen_to_world(press_pos);
+                        if self.state.resizing_node.is_none() && !self.state.linking_drag && self.state.node_at(press_world).is_none() {
+                            // Notes sit above groups, so they get the first chance at the press.
+                            self.state.resizing_note = self.state.annotations.iter().rev().find(|a| resize_handle_rect(Rect::from_min_max(world_to_screen(a.rect.min), world_to_screen(a.rect.max))).contains(press_pos)).cloned();
+                            if self.state.resizing_note.is_none() { self.state.dragging_note = self.state.annotations.iter().rev().find(|a| a.header().contains(press_world)).cloned(); }
+                        }
+                        if self.state.resizing_node.is_none() && !self.state.linking_drag && self.state.node_at(press_world).is_none() && self.state.dragging_note.is_none() && self.state.resizing_note.is_none() {
+                            self.state.resizing_group = self.state.groups.iter().rev().find(|g| resize_handle_rect(Rect::from_min_max(world_to_screen(g.rect.min), world_to_screen(g.rect.max))).contains(press_pos)).map(|g| (g.id, g.rect));
+                            if self.state.resizing_group.is_none() {
+                                self.state.dragging_group = self.state.group_header_at(press_world).and_then(|id| {
+                                    let before = self.state.groups.iter().find(|g| g.id == id)?.rect;
+                                    Some(GroupDrag { id, before, members: self.state.group_members(id).into_iter().map(|m| (m, self.state.nodes[&m].position)).collect() })
+                                });
+                            }
+                        }
+                    }
+                    if response.drag_started() && !panning && self.selection_start.is_none() && self.state.resizing_node.is_none() && self.state.dragging_group.is_none() && self.state.resizing_group.is_none() && !self.state.linking_drag {
+                        let clicked_id = self.state.node_at(world_pos);
+                        match clicked_id {
+                            Some(id) if !self.state.nodes[&id].drag_handle(camera_zoom).contains(world_pos) => {
+                                // The body belongs to the node's widgets; pressing there only selects.
+                                if !self.state.nodes[&id].selected { self.state.select_only(id); }
+                            }
+                            Some(id) => {
+                                // Grabbing part of the selection drags all of it; grabbing anything else selects just that node.
+                                if !self.state.nodes[&id].selected { self.state.select_only(id); }
+                                let origins: Vec<(u64, Pos2)> = self.state.selected_ids().into_iter().map(|m| (m, self.state.nodes[&m].position)).collect();
+                                // Raise the whole dragged set, keeping the grabbed node on top.
+                                for (m, _) in origins.iter().filter(|(m, _)| *m != id) { self.state.bring_to_front(*m); }
+                                self.state.bring_to_front(id);
+                                if let Some(anim) = self.layout_animation.as_mut() { for (m, _) in &origins { anim.release(*m); } }
+                                self.state.dragging = Some(NodeDrag::new(id, origins));
+                                self.state.focused_node = Some(id);
+                                self.state.selected_edge = None;
+                            }
+                            None => { for node in self.state.nodes.values_mut() { node.selected = false; } }
+                        }
+                        if let Some(to_id) = clicked_id.filter(|_| !self.state.linking_from.is_empty()) {
+                            let pairs: Vec<(u64, u64)> = std::mem::take(&mut self.state.linking_from).into_iter().filter(|&from_id| from_id != to_id).map(|from_id| (from_id, to_id)).collect();
+                            self.link(&pairs);
                         }
                     }
+                    if response.clicked() {
+                        match self.state.node_at(world_pos) {
+                            Some(to_id) if !self.state.linking_from.is_empty() => {
+                                let pairs: Vec<(u64, u64)> = std::mem::take(&mut self.state.linking_from).into_iter().filter(|&from_id| from_id != to_id).map(|from_id| (from_id, to_id)).collect();
+                                self.link(&pairs);
+                            }
+                            // Shift-click toggles one node and leaves the rest of the selection alone.
+                            Some(id) if ctx.input(|i| i.modifiers.shift) => { if let Some(n) = self.state.nodes.get_mut(&id) { n.selected = !n.selected; } }
+                            Some(id) => { self.state.select_only(id); self.state.focused_node = Some(id); self.state.selected_edge = None; }
+                            None => {
+                                for node in self.state.nodes.values_mut() { node.selected = false; }
+                                self.state.linking_from.clear();
+                            }
+                        }
+                    }
+                    if response.clicked() && !self.state.nodes.values().any(|n| n.bounds().contains(world_pos)) {
+                        let delete_hit = self.state.selected_edge
+                            .and_then(|edge_id| self.state.edges.iter().find(|e| e.id == edge_id))
+                            .and_then(|e| self.state.edge_curve(e, world_to_screen))
+                            .is_some_and(|curve| bezier_point(&curve, 0.5).distance(pointer_pos) <= EDGE_DELETE_RADIUS);
+                        if let (true, Some(edge_id)) = (delete_hit, self.state.selected_edge) { self.state.remove_edge(edge_id); }
+                        else { self.state.selected_edge = self.state.edge_at(pointer_pos, world_to_screen); }
+                    }
                 }
-                self.handle_selection(ctx, canvas_rect, world_to_screen, screen_to_world);
             }
             
-            if response.drag_stopped() { self.state.dragging_node = None; }
-            if let Some(id) = self.state.dragging_node { if let Some(node) = self.state.nodes.get_mut(&id) { node.position += response.drag_delta() / camera_zoom; } }
+            if let Some(id) = self.state.resizing_note.as_ref().map(|a| a.id) {
+                if let Some(a) = self.state.annotations.iter_mut().find(|a| a.id == id) { a.rect.max = a.rect.min + (a.rect.size() + response.drag_delta() / camera_zoom).max(MIN_NOTE_SIZE); }
+            }
+            if let Some(id) = self.state.dragging_note.as_ref().map(|a| a.id) {
+                if let Some(a) = self.state.annotations.iter_mut().find(|a| a.id == id) { a.rect = a.rect.translate(response.drag_delta() / camera_zoom); }
+            }
+            if let Some((id, _)) = self.state.resizing_group {
+                if let Some(g) = self.state.groups.iter_mut().find(|g| g.id == id) { g.rect.max = g.rect.min + (g.rect.size() + response.drag_delta() / camera_zoom).max(MIN_GROUP_SIZE); }
+            }
+            if let Some(drag) = &self.state.dragging_group {
+                let delta = response.drag_delta() / camera_zoom;
+                if let Some(g) = self.state.groups.iter_mut().find(|g| g.id == drag.id) { g.rect = g.rect.translate(delta); }
+                for (m, _) in &drag.members { if let Some(n) = self.state.nodes.get_mut(m) { n.position += delta; n.velocity = Vec2::ZERO; } }
+            }
+            if let Some((id, _)) = self.state.resizing_node {
+                if let Some(node) = self.state.nodes.get_mut(&id) {
+                    let delta = response.drag_delta() / camera_zoom;
+                    node.size = (node.size + if node.auto_size { Vec2::new(delta.x, 0.0) } else { delta }).clamp(MIN_NODE_SIZE, MAX_NODE_SIZE);
+                }
+            }
+            if response.drag_stopped() && self.state.linking_drag {
+                self.state.linking_drag = false;
+                let sources = std::mem::take(&mut self.state.linking_from);
+                if let Some(pos) = response.interact_pointer_pos().or(ctx.input(|i| i.pointer.hover_pos())) {
+                    let target = self.state.topmost(|n| world_to_screen(n.input_port()).distance(pos) <= PORT_HIT_RADIUS).or_else(|| self.state.node_at(screen_to_world(pos)));
+                    if let Some(to_id) = target {
+                        let pairs: Vec<(u64, u64)> = sources.into_iter().filter(|&from_id| from_id != to_id).map(|from_id| (from_id, to_id)).collect();
+                        self.link(&pairs);
+                    }
+                }
+            }
+            if response.drag_stopped() {
+                for before in [self.state.dragging_note.take(), self.state.resizing_note.take()].into_iter().flatten() {
+                    if let Some(after) = self.state.annotations.iter().find(|a| a.id == before.id).filter(|a| a.rect != before.rect).cloned() { self.state.history.push(Command::EditAnnotation { before, after }); }
+                }
+                if let Some((id, before)) = self.state.resizing_group.take() {
+                    if let Some(after) = self.state.groups.iter().find(|g| g.id == id).map(|g| g.rect).filter(|&after| after != before) { self.state.history.push(Command::GroupRect { id, before, after }); }
+                }
+                if let Some(GroupDrag { id, before, members }) = self.state.dragging_group.take() {
+                    if let Some(after) = self.state.groups.iter().find(|g| g.id == id).map(|g| g.rect).filter(|&after| after != before) {
+                        let moves = members.into_iter().filter_map(|(m, from)| self.state.nodes.get(&m).map(|n| (m, from, n.position))).collect();
+                        self.state.history.push(Command::Batch(vec![Command::GroupRect { id, before, after }, Command::MoveNodes(moves)]));
+                    }
+                }
+                if let Some((id, before)) = self.state.resizing_node.take() {
+                    if let Some(after) = self.state.nodes.get(&id).map(|n| n.size).filter(|&after| after != before) { self.state.history.push(Command::ResizeNode { id, before, after }); }
+                }
+                if let Some(drag) = self.state.dragging.take() {
+                    // Alt-dropping a node onto another links them and moves the dropped node out of the way.
+                    let link_to = ctx.input(|i| i.modifiers.alt).then(|| self.state.drop_link_target(&drag)).flatten();
+                    if let Some(to) = link_to { self.state.place_before(drag.anchor, to); }
+                    let mut moves = Vec::new();
+                    for (id, from) in drag.origins {
+                        let Some(node) = self.state.nodes.get_mut(&id) else { continue };
+                        if self.settings.snap_to_grid { node.position = self.settings.snap(node.position); node.velocity = Vec2::ZERO; }
+                        if node.position != from { moves.push((id, from, node.position)); }
+                    }
+                    let (link, rejected) = match link_to { Some(to) => self.state.edge_commands(&[(drag.anchor, to)]), None => (None, Vec::new()) };
+                    self.refuse_links(rejected);
+                    match link {
+                        // The moves are already applied; applying them again along with the new edge makes the drop one undo step.
+                        Some(link) => self.state.execute(Command::Batch(vec![Command::MoveNodes(moves), link])),
+                        None if !moves.is_empty() => self.state.history.push(Command::MoveNodes(moves)),
+                        None => {}
+                    }
+                }
+            }
+            if let Some(drag) = self.state.dragging.as_mut() {
+                // Holding Shift keeps the drag on one axis; letting go resumes free movement from wherever the nodes are.
+                let offset = drag.offset(response.drag_delta() / camera_zoom, ctx.input(|i| i.modifiers.shift));
+                for (id, origin) in &drag.origins { if let Some(node) = self.state.nodes.get_mut(id) { node.position = *origin + offset; } }
+            }
+            // Alignment snapping would fight nodes the simulation is still pushing around.
+            let mut guides = Vec::new();
+            let settling = |drag: &NodeDrag| self.settings.physics_enabled && self.state.nodes.values().any(|n| !n.pinned && !drag.contains(n.id) && n.velocity.length() > SNAP_REST_SPEED);
+            if let Some(drag) = self.state.dragging.clone().filter(|drag| self.settings.align_guides && !settling(drag)) {
+                let visible = Rect::from_min_max(screen_to_world(canvas_rect.min), screen_to_world(canvas_rect.max));
+                let (shift, lines) = self.state.alignment_snap(drag.anchor, |id| drag.contains(id), visible, ALIGN_SNAP_DISTANCE / camera_zoom);
+                for (id, _) in &drag.origins { if let Some(node) = self.state.nodes.get_mut(id) { node.position += shift; } }
+                guides = lines;
+            }
+            if self.settings.show_grid { draw_background_grid(&painter, &theme, canvas_rect, world_to_screen, screen_to_world, camera_zoom); }
+            self.draw_groups(ui, &painter, world_to_screen);
+            self.draw_annotations(ui, &painter, world_to_screen);
+            let hovered_edge = ctx.input(|i| i.pointer.hover_pos()).filter(|p| response.hovered() && canvas_rect.contains(*p)).and_then(|p| self.state.edge_at(p, world_to_screen));
+            let lineage = self.hovered_lineage(ctx, canvas_rect, screen_to_world);
+            let hovered_ends = hovered_edge.and_then(|id| self.state.edges.iter().find(|e| e.id == id)).map(|e| (e.from, e.to));
+            if let Some((from, to)) = hovered_ends.filter(|_| self.editing_edge_label.is_none()) {
+                if let (Some(a), Some(b)) = (self.state.nodes.get(&from), self.state.nodes.get(&to)) {
+                    egui::show_tooltip_at_pointer(ctx, response.layer_id, egui::Id::new("edge_tooltip"), |ui| { ui.label(format!("{} → {}", node_summary(a), node_summary(b))); });
+                }
+            }
+            let edges_started = Instant::now();
             for edge in &self.state.edges {
-                if let (Some(n1), Some(n2)) = (self.state.nodes.get(&edge.from), self.state.nodes.get(&edge.to)) {
-                    let p1 = world_to_screen(n1.position + Vec2::new(n1.size.x, n1.size.y / 2.0));
-                    let p2 = world_to_screen(n2.position + Vec2::new(0.0, n2.size.y / 2.0));
-                    let cp_dist = (p2.x - p1.x).abs() * 0.5;
-                    let c1 = p1 + Vec2::new(cp_dist, 0.0);
-                    let c2 = p2 - Vec2::new(cp_dist, 0.0);
-                    painter.add(egui::Shape::CubicBezier(egui::epaint::CubicBezierShape { points: [p1, c1, c2, p2], closed: false, fill: Color32::TRANSPARENT, stroke: Stroke::new(2.0, Color32::from_gray(80)).into() }));
+                if let Some(points) = self.state.edge_curve(edge, world_to_screen) {
+                    let mut edge_painter = painter.clone();
+                    if lineage.as_ref().is_some_and(|l| !(l.contains(&edge.from) && l.contains(&edge.to))) { edge_painter.multiply_opacity(DIM_OPACITY); }
+                    // The side a Branch node didn't take is drawn faded.
+                    if !self.state.edge_active(edge) { edge_painter.multiply_opacity(DIM_OPACITY); }
+                    let selected = self.state.selected_edge == Some(edge.id);
+                    let stroke = if selected { Stroke::new(3.0, theme.selection) } else if hovered_edge == Some(edge.id) || self.sidebar_edge == Some(edge.id) { Stroke::new(3.5, theme.edge_hover) } else { Stroke::new(2.0, theme.edge) };
+                    edge_painter.add(egui::Shape::CubicBezier(egui::epaint::CubicBezierShape { points, closed: false, fill: Color32::TRANSPARENT, stroke: stroke.into() }));
+                    edge_painter.add(egui::Shape::convex_polygon(arrowhead(&points, (10.0 * camera_zoom).clamp(4.0, 12.0)).to_vec(), stroke.color, Stroke::NONE));
+                    // Labels fade out with the same zoom cutoff that hides node bodies.
+                    let label_alpha = ((camera_zoom - 0.3) / 0.1).clamp(0.0, 1.0);
+                    if let Some(label) = edge.display_label().filter(|_| label_alpha > 0.0 && self.editing_edge_label.as_ref().map(|(id, _)| *id) != Some(edge.id)) {
+                        let mid = bezier_point(&points, 0.5);
+                        let galley = edge_painter.layout_no_wrap(label, egui::FontId::proportional(12.0), theme.text.gamma_multiply(label_alpha));
+                        let pill = Rect::from_center_size(mid - Vec2::new(0.0, 14.0), galley.size() + Vec2::new(12.0, 4.0));
+                        edge_painter.rect(pill, pill.height() / 2.0, theme.node_fill.gamma_multiply(label_alpha), Stroke::new(1.0, theme.node_border.gamma_multiply(label_alpha)));
+                        edge_painter.galley(pill.center() - galley.size() / 2.0, galley, theme.text);
+                    }
+                    if selected {
+                        let mid = bezier_point(&points, 0.5);
+                        edge_painter.circle(mid, EDGE_DELETE_RADIUS, theme.node_fill, Stroke::new(1.0, theme.selection));
+                        edge_painter.text(mid, egui::Align2::CENTER_CENTER, "✕", egui::FontId::proportional(10.0), theme.text);
+                    }
                 }
             }
+            self.perf.record(Phase::Edges, edges_started);
+            let nodes_started = Instant::now();
+            let drop_target = self.state.dragging.as_ref().filter(|_| ctx.input(|i| i.modifiers.alt)).and_then(|drag| self.state.drop_link_target(drag));
             let mut foxit_request = None;
-            let node_ids: Vec<u64> = self.state.nodes.keys().copied().collect();
-            for id in node_ids {
+            let presenting = self.presentation.as_ref().and_then(|p| p.current());
+            for id in self.state.ordered_ids() {
                 let node = &self.state.nodes[&id];
                 let screen_pos = world_to_screen(node.position);
                 let screen_size = node.size * camera_zoom;
                 let node_rect = Rect::from_min_size(screen_pos, screen_size);
                 if !canvas_rect.intersects(node_rect) { continue; }
-                let frame = Frame::none().fill(Color32::from_gray(30)).rounding(Rounding::same(8.0)).stroke(Stroke::new(1.0, if node.selected { Color32::from_rgb(0, 200, 255) } else { Color32::from_gray(60) })).inner_margin(Margin::same(12.0));
+                if self.state.focused_node == Some(id) {
+                    painter.rect_stroke(node_rect.expand(5.0), 12.0, Stroke::new(1.5, theme.selection.gamma_multiply(0.7)));
+                }
+                if drop_target == Some(id) {
+                    painter.rect_stroke(node_rect.expand(4.0), 12.0, Stroke::new(5.0, theme.selection.gamma_multiply(0.6)));
+                }
+                if hovered_ends.is_some_and(|(from, to)| from == id || to == id) {
+                    painter.rect_stroke(node_rect.expand(3.0), 10.0, Stroke::new(4.0, node.data.accent_color().gamma_multiply(0.45)));
+                }
+                let dim = lineage.as_ref().is_some_and(|l| !l.contains(&id)) || presenting.is_some_and(|p| p != id);
+                if camera_zoom < LOD_DETAIL_ZOOM {
+                    let mut lod_painter = painter.clone();
+                    if dim { lod_painter.multiply_opacity(DIM_OPACITY); }
+                    paint_node_lod(&lod_painter, &theme, node, node_rect, camera_zoom, ctx.input(|i| i.time));
+                    paint_status_badge(&lod_painter, &theme, node, node_rect, ctx.input(|i| i.time));
+                    if camera_zoom >= LOD_BLOCK_ZOOM {
+                        for port in [world_to_screen(node.input_port()), world_to_screen(node.output_port())] { painter.circle(port, PORT_RADIUS * 0.75, theme.handle, Stroke::new(1.0, theme.canvas_bg)); }
+                    }
+                    continue;
+                }
+                // Busy, finished and failed nodes tint their border; selection then moves to a ring just outside it.
+                let status = node.status();
+                let border = if status == NodeStatus::Idle { Stroke::new(1.0, if node.selected { theme.selection } else { theme.node_border }) } else { Stroke::new(2.0, status.color(&theme, ctx.input(|i| i.time))) };
+                if node.selected && status != NodeStatus::Idle { painter.rect_stroke(node_rect.expand(3.0), 10.0, Stroke::new(1.5, theme.selection)); }
+                let frame = Frame::none().fill(if node.data.kind() == NodeKind::Note { notes::note_fill(&theme) } else { theme.node_fill }).rounding(Rounding::same(8.0)).stroke(border).inner_margin(Margin::same(12.0));
+                let collapsed = node.collapsed;
+                let mut toggle_collapse = false;
+                let mut toggle_pin = false;
+                let raw_markdown = node.raw_markdown;
+                let mut toggle_raw = false;
+                let focus_body = self.pending_text_focus == Some(id) && !collapsed;
+                if focus_body { self.pending_text_focus = None; }
+                let pinned = node.pinned;
+                let auto_size = node.auto_size && !collapsed;
+                // Auto-sized results claim their room up front; otherwise the scroll area would shrink to the current height and the node could never grow.
+                let result_scroll = |fixed: f32| if auto_size { egui::ScrollArea::vertical().max_height(AUTO_SIZE_RESULT_HEIGHT).min_scrolled_height(AUTO_SIZE_RESULT_HEIGHT) } else { egui::ScrollArea::vertical().max_height(fixed) };
+                let error = node.error.clone();
+                let retrying = self.retries.get(&id).map(|r| r.attempt);
+                let cached = node.cached;
+                let refresh_left = self.refresh_due.get(&id).map(|due| due.saturating_duration_since(Instant::now()));
+                let version_count = node.versions.len();
+                let view = self.version_views.get(&id);
+                let viewing = view.map(|v| v.index);
+                let viewed_text = view.and_then(|v| node.versions.get(v.index)).and_then(|v| v.text.clone());
+                let viewed_texture = view.and_then(|v| v.texture.clone());
+                let viewed_seed = view.map(|v| node.versions.get(v.index).and_then(|v| v.seed));
+                let queued = node.queued;
+                let mut version_action = None;
+                let mut promote_variant = None;
+                let mut retry = false;
+                let mut evaluate = false;
+                let mut refresh = false;
+                let mut reschedule = false;
+                let title = node.display_title().to_string();
+                let mut title_edit = None;
                 let mut node_data = node.data.clone();
                 let mut node_data_changed = false;
                 let mut trigger_research = None;
                 let mut trigger_visualize = None;
                 let mut trigger_agnostic_ai = None;
-                ui.put(node_rect, |ui: &mut egui::Ui| {
+                let mut trigger_tts = None;
+                let mut trigger_translate = None;
+                let mut trigger_fetch = None;
+                let mut trigger_transcribe = None;
+                let mut pick_audio = false;
+                let mut refresh_models = false;
+                let mut play = None;
+                let mut parse = false;
+                let play_position = self.player.position(id);
+                let mut open_preview = false;
+                let mut content_height = 0.0;
+                // Widgets are created header → fields → buttons, which is also the order Tab walks through them.
+                let named = |r: egui::Response, typ: egui::WidgetType, what: &str| node_widget(r, typ, id, &title, what);
+                let node_response = ui.put(node_rect, |ui: &mut egui::Ui| {
+                    if dim { ui.multiply_opacity(DIM_OPACITY); }
+                    if presenting.is_some() { ui.disable(); }
                     frame.show(ui, |ui| {
-                        ui.vertical(|ui| {
-                            let (title, icon) = match &node_data { NodeData::Concept { .. } => ("Concept", "🧠"), NodeData::YouComResearch { .. } => ("You.com Research", "🌐"), NodeData::AgnosticAI { .. } => ("Agnostic AI", "🤖"), NodeData::Visual { .. } => ("AI Visualizer", "🎨"), NodeData::FoxitExport { .. } => ("Foxit Export", "📄") };
-                            ui.horizontal(|ui| { ui.label(icon); ui.heading(title); });
-                            if camera_zoom < 0.4 { return; }
+                        content_height = ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                if named(ui.small_button(if collapsed { "▸" } else { "▾" }), egui::WidgetType::Button, if collapsed { "expand button" } else { "collapse button" }).on_hover_text(if collapsed { "Expand" } else { "Collapse" }).clicked() { toggle_collapse = true; }
+                                ui.label(node_data.kind().icon());
+                                if named(ui.add(egui::Button::new(egui::RichText::new("📌").color(if pinned { theme.text } else { theme.handle })).small().frame(false)), egui::WidgetType::Button, if pinned { "unpin button" } else { "pin button" }).on_hover_text(if pinned { "Unpin" } else { "Pin in place" }).clicked() { toggle_pin = true; }
+                                match self.editing_title.as_mut().filter(|(edit_id, _)| *edit_id == id) {
+                                    Some((_, draft)) => {
+                                        let r = named(ui.add(egui::TextEdit::singleline(draft).font(egui::TextStyle::Heading).desired_width(ui.available_width())), egui::WidgetType::TextEdit, "title");
+                                        if r.lost_focus() { title_edit = Some(!ui.input(|i| i.key_pressed(egui::Key::Escape))); }
+                                        else if !r.has_focus() { r.request_focus(); }
+                                    }
+                                    None => {
+                                        if named(ui.add(egui::Label::new(egui::RichText::new(&title).heading()).sense(Sense::click()).selectable(false)), egui::WidgetType::Label, "title, double-click to rename").on_hover_text("Double-click to rename").double_clicked() { self.editing_title = Some((id, title.clone())); }
+                                    }
+                                }
+                            });
+                            if collapsed { return; }
                             ui.separator();
+                            if let Some(err) = &error {
+                                ui.colored_label(ui.visuals().error_fg_color, format!("✗ {}", err));
+                                ui.horizontal(|ui| {
+                                    if named(ui.button("🔄 Retry"), egui::WidgetType::Button, "Retry button").clicked() { retry = true; }
+                                    if let Some(attempt) = retrying { ui.spinner(); ui.weak(format!("retrying {}/{}…", attempt, MAX_RETRIES)); }
+                                });
+                            }
+                            if cached && !node_data.is_loading() {
+                                ui.horizontal(|ui| {
+                                    ui.weak("💾 cached").on_hover_text("Reused from an identical earlier request");
+                                    if named(ui.small_button("⟳ Force refresh"), egui::WidgetType::Button, "Force refresh button").on_hover_text("Ask the server again, skipping the cache").clicked() { refresh = true; }
+                                });
+                            }
                             match &mut node_data {
-                                NodeData::Concept { text } => { if ui.text_edit_multiline(text).changed() { node_data_changed = true; } }
-                                NodeData::YouComResearch { query, result, is_loading } => {
-                                    ui.add(egui::TextEdit::singleline(query));
-                                    if *is_loading { ui.spinner(); }
-                                    else if let Some(res) = result { egui::ScrollArea::vertical().max_height(100.0).show(ui, |ui| { ui.small(res); }); }
+                                NodeData::Concept { text } => {
+                                    let r = named(ui.text_edit_multiline(text), egui::WidgetType::TextEdit, "text");
+                                    if focus_body { r.request_focus(); }
+                                    if r.changed() { node_data_changed = true; }
+                                }
+                                NodeData::YouComResearch { query, result, is_loading, refresh: interval } => {
+                                    let r = named(ui.add(egui::TextEdit::singleline(query)), egui::WidgetType::TextEdit, "query");
+                                    if focus_body { r.request_focus(); }
+                                    if r.changed() { node_data_changed = true; }
+                                    if *is_loading { busy_indicator(ui, queued); }
+                                    else if let Some(res) = viewed_text.as_ref().or(result.as_ref()) { toggle_raw |= markdown::result_view(ui, id, res, raw_markdown, result_scroll(100.0)); }
                                     else {
                                         ui.horizontal(|ui| {
-                                            if ui.button("🌐 Search").clicked() { *is_loading = true; node_data_changed = true; trigger_research = Some(query.clone()); }
-                                            if ui.button("🔗 Link").clicked() {
-                                                let mut parent_text = None;
-                                                for edge in &self.state.edges { if edge.to == id { if let Some(parent) = self.state.nodes.get(&edge.from) { if let NodeData::Concept { text } = &parent.data { parent_text = Some(text.clone()); break; } } } }
-                                                if let Some(txt) = parent_text { *query = txt; node_data_changed = true; }
-                                            }
+                                            if named(ui.button("🌐 Search"), egui::WidgetType::Button, "Search button").clicked() { *is_loading = true; node_data_changed = true; trigger_research = Some(query.clone()); }
+                                            if let Some(txt) = link::link_parent_button(ui, &self.state, id, &named) { *query = txt; node_data_changed = true; }
                                         });
                                     }
+                                    if refresh::refresh_row(ui, id, interval, refresh_left) { node_data_changed = true; reschedule = true; }
                                 }
-                                NodeData::AgnosticAI { model, prompt, result, is_loading } => {
-                                    ui.label("Model:"); if ui.text_edit_singleline(model).changed() { node_data_changed = true; }
-                                    ui.label("Prompt:"); if ui.text_edit_multiline(prompt).changed() { node_data_changed = true; }
-                                    ui.horizontal(|ui| {
-                                        if ui.button("🤖 Generate").clicked() { *is_loading = true; node_data_changed = true; trigger_agnostic_ai = Some((model.clone(), prompt.clone())); }
-                                        if ui.button("🔗 Link").clicked() {
-                                            let mut parent_text = None;
-                                            for edge in &self.state.edges { if edge.to == id { if let Some(parent) = self.state.nodes.get(&edge.from) { match &parent.data { NodeData::YouComResearch { result: Some(res), .. } => parent_text = Some(res.clone()), NodeData::Concept { text } => parent_text = Some(text.clone()), _ => {} } } } }
-                                            if let Some(txt) = parent_text { *prompt = txt; node_data_changed = true; }
+                                NodeData::AgnosticAI { model, prompt, result, is_loading, auto_run, provider } => {
+                                    if local_ai::provider_picker(ui, id, provider, model, &self.local_models) { node_data_changed = true; }
+                                    match provider {
+                                        local_ai::Provider::OpenRouter => { ui.label("Model:"); if named(ui.text_edit_singleline(model), egui::WidgetType::TextEdit, "model").changed() { node_data_changed = true; } }
+                                        local_ai::Provider::Ollama => {
+                                            let (changed, refresh) = local_ai::local_model_picker(ui, id, model, &self.local_models);
+                                            node_data_changed |= changed;
+                                            refresh_models |= refresh;
                                         }
+                                    }
+                                    ui.label("Prompt:");
+                                    let r = named(ui.text_edit_multiline(prompt), egui::WidgetType::TextEdit, "prompt");
+                                    if focus_body { r.request_focus(); }
+                                    if r.changed() { node_data_changed = true; }
+                                    ui.horizontal(|ui| {
+                                        if templates::prompt_tools(ui, prompt) { node_data_changed = true; }
+                                        if named(ui.button("🤖 Generate"), egui::WidgetType::Button, "Generate button").clicked() { *is_loading = true; node_data_changed = true; trigger_agnostic_ai = Some((model.clone(), prompt.clone())); }
+                                        if let Some(txt) = link::link_parent_button(ui, &self.state, id, &named) { *prompt = txt; node_data_changed = true; }
                                     });
-                                    if *is_loading { ui.spinner(); }
-                                    else if let Some(res) = result { egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| { ui.small(res); }); }
+                                    if ui.checkbox(auto_run, "⚡ Auto-run").on_hover_text(AUTO_RUN_HINT).changed() { node_data_changed = true; }
+                                    if *is_loading { busy_indicator(ui, queued); }
+                                    else if let Some(res) = viewed_text.as_ref().or(result.as_ref()) { toggle_raw |= markdown::result_view(ui, id, res, raw_markdown, result_scroll(150.0)); }
                                 }
-                                NodeData::Visual { prompt, texture, is_loading } => {
-                                    ui.add(egui::TextEdit::multiline(prompt).hint_text("Describe..."));
+                                NodeData::Visual { prompt, texture, is_loading, variant_count, variants, use_parent_image, auto_run, seed, image_seed, .. } => {
+                                    let r = named(ui.add(egui::TextEdit::multiline(prompt).hint_text("Describe...")), egui::WidgetType::TextEdit, "image prompt");
+                                    if focus_body { r.request_focus(); }
+                                    if r.changed() { node_data_changed = true; }
                                     ui.horizontal(|ui| {
-                                        if ui.button("🎨 Generate").clicked() { *is_loading = true; node_data_changed = true; trigger_visualize = Some(prompt.clone()); }
-                                        if ui.button("🔗 Link").clicked() {
-                                            let mut parent_text = None;
-                                            for edge in &self.state.edges { if edge.to == id { if let Some(parent) = self.state.nodes.get(&edge.from) { match &parent.data { NodeData::AgnosticAI { result: Some(res), .. } => parent_text = Some(res.clone()), NodeData::YouComResearch { result: Some(res), .. } => parent_text = Some(res.clone()), _ => {} } } } }
-                                            if let Some(txt) = parent_text { *prompt = txt; node_data_changed = true; }
-                                        }
+                                        if templates::prompt_tools(ui, prompt) { node_data_changed = true; }
+                                        if named(ui.button("🎨 Generate"), egui::WidgetType::Button, "Generate image button").clicked() { *is_loading = true; node_data_changed = true; trigger_visualize = Some(prompt.clone()); }
+                                        if ui.add(egui::DragValue::new(variant_count).range(1..=MAX_VARIANTS).prefix("×")).on_hover_text("Images per Generate").changed() { node_data_changed = true; }
+                                        if let Some(txt) = link::link_parent_button(ui, &self.state, id, &named) { *prompt = txt; node_data_changed = true; }
                                     });
-                                    if *is_loading { ui.spinner(); } else if let Some(tex) = texture { 
+                                    let has_parent_image = self.state.img2img_source(id).is_some();
+                                    if has_parent_image || *use_parent_image {
+                                        let hint = if has_parent_image { "Start from the linked image, or the first linked character's portrait" } else { "No parent image yet; only the prompt will be sent" };
+                                        if ui.checkbox(use_parent_image, "🖼 Use parent image").on_hover_text(hint).changed() { node_data_changed = true; }
+                                    }
+                                    let cast: Vec<String> = self.state.character_parents(id).iter().filter_map(|c| self.state.nodes[c].data.own_title().map(str::to_string)).collect();
+                                    if !cast.is_empty() { ui.weak(format!("🎭 With {}", cast.join(", "))).on_hover_text("Their descriptions go ahead of the prompt when it's sent"); }
+                                    if ui.checkbox(auto_run, "⚡ Auto-run").on_hover_text(AUTO_RUN_HINT).changed() { node_data_changed = true; }
+                                    if seeds::seed_row(ui, seed, viewed_seed.unwrap_or(*image_seed)) { node_data_changed = true; }
+                                    if *is_loading { busy_indicator(ui, queued); } else if let Some(tex) = viewed_texture.as_ref().or(texture.as_ref()) {
                                         // Scale to fit card width
                                         let max_w = ui.available_width();
                                         let img_size = tex.size_vec2();
                                         let aspect = img_size.y / img_size.x;
                                         let display_size = Vec2::new(max_w, max_w * aspect);
-                                        ui.image(egui::load::SizedTexture::new(tex.id(), display_size));
+                                        if named(ui.add(egui::Image::new(egui::load::SizedTexture::new(tex.id(), display_size)).sense(Sense::click())), egui::WidgetType::ImageButton, "image, click to preview").on_hover_text("Click to preview").clicked() { open_preview = true; }
                                     }
+                                    if !variants.is_empty() { promote_variant = variants::variant_strip(ui, variants, texture.as_ref().map(|t| t.id())); }
                                 }
-                                NodeData::FoxitExport { status, is_loading } => {
+                                NodeData::FoxitExport { status, is_loading, include_everything } => {
                                     ui.label(format!("Status: {}", status));
-                                    if *is_loading { ui.spinner(); }
-                                    else if ui.button("Generate PDF").clicked() { *is_loading = true; node_data_changed = true; foxit_request = Some(id); }
+                                    if ui.checkbox(include_everything, "Include everything").on_hover_text("Export every node on the board, not just the ones linked into this node").changed() { node_data_changed = true; }
+                                    if *is_loading { busy_indicator(ui, queued); }
+                                    else if named(ui.button("Generate PDF"), egui::WidgetType::Button, "Generate PDF button").clicked() { *is_loading = true; node_data_changed = true; foxit_request = Some(id); }
+                                }
+                                NodeData::Merge { separator, excluded, output } => {
+                                    let parents = self.state.text_parents(id);
+                                    if parents.is_empty() { ui.weak("Link nodes into this one to merge their output"); }
+                                    for parent in parents {
+                                        let p = &self.state.nodes[&parent];
+                                        let mut included = !excluded.contains(&parent);
+                                        let label = format!("{} #{} {}", p.data.kind().icon(), parent, truncate(p.display_title(), 24));
+                                        if ui.checkbox(&mut included, label).on_hover_ui(|ui| node_preview_ui(ui, p)).changed() {
+                                            if included { excluded.retain(|&e| e != parent); } else { excluded.push(parent); }
+                                            node_data_changed = true;
+                                        }
+                                    }
+                                    ui.horizontal(|ui| {
+                                        ui.label("Separator:");
+                                        if named(ui.add(egui::TextEdit::singleline(separator).desired_width(80.0)), egui::WidgetType::TextEdit, "separator").on_hover_text("\\n for a new line, \\t for a tab").changed() { node_data_changed = true; }
+                                    });
+                                    if output.is_empty() { ui.weak("Nothing to merge yet"); } else { result_scroll(150.0).show(ui, |ui| { ui.small(output.as_str()); }); }
+                                }
+                                NodeData::Transform { steps, output, error } => {
+                                    if transform::transform_ui(ui, id, steps) { node_data_changed = true; }
+                                    if let Some(err) = error { ui.colored_label(ui.visuals().error_fg_color, format!("✗ {}", err)); }
+                                    else if !output.is_empty() { result_scroll(150.0).show(ui, |ui| { ui.small(output.as_str()); }); }
+                                    else if self.state.parent_output(id).is_none() { ui.weak("Link a text node into this one to transform its output"); }
+                                    else { ui.weak("The steps left nothing"); }
+                                }
+                                NodeData::MarkdownView { text } => {
+                                    if text.trim().is_empty() { ui.weak("Link a text node into this one to render its output"); }
+                                    else { result_scroll(280.0).show(ui, |ui| { markdown::render_capped(ui, egui::Id::new(("markdown_more", id)), text, ui.style().text_styles[&egui::TextStyle::Body].size); }); }
+                                }
+                                NodeData::Transcribe { file_name, audio, transcript, is_loading } => {
+                                    ui.horizontal(|ui| {
+                                        if named(ui.add_enabled(!*is_loading, egui::Button::new("📂 Open")), egui::WidgetType::Button, "Open recording button").on_hover_text("Pick a recording, or drop one on this node").clicked() { pick_audio = true; }
+                                        match audio { Some(clip) => ui.weak(transcribe::clip_summary(file_name, clip)), None => ui.weak("No recording yet") };
+                                    });
+                                    if *is_loading { busy_indicator(ui, queued); }
+                                    else if let Some(clip) = audio.as_ref() {
+                                        if named(ui.button(if transcript.is_empty() { "📝 Transcribe" } else { "📝 Transcribe again" }), egui::WidgetType::Button, "Transcribe button").clicked() { *is_loading = true; node_data_changed = true; trigger_transcribe = Some((file_name.clone(), clip.clone())); }
+                                    }
+                                    match &viewed_text {
+                                        Some(earlier) => { result_scroll(150.0).show(ui, |ui| { ui.small(earlier); }); }
+                                        None => {
+                                            let r = result_scroll(150.0).show(ui, |ui| named(ui.add(egui::TextEdit::multiline(transcript).hint_text("Transcript; edit it before it flows on").desired_width(f32::INFINITY)), egui::WidgetType::TextEdit, "transcript")).inner;
+                                            if focus_body { r.request_focus(); }
+                                            if r.changed() { node_data_changed = true; }
+                                        }
+                                    }
+                                }
+                                NodeData::Compare { picked, .. } => match self.state.compare_pair(id) {
+                                    None => { ui.colored_label(ui.visuals().error_fg_color, format!("Link exactly two text nodes into this one ({} now)", self.state.text_parents(id).len())); }
+                                    Some((a, b)) => {
+                                        ui.columns(2, |columns| {
+                                            for (ui, (side, parent)) in columns.iter_mut().zip([("A", a), ("B", b)]) {
+                                                let p = &self.state.nodes[&parent];
+                                                ui.strong(format!("{} {} #{}", side, p.data.kind().icon(), parent)).on_hover_text(p.display_title());
+                                                if ui.add(egui::Button::new(format!("Pick {}", side)).selected(*picked == Some(parent))).clicked() { *picked = Some(parent); node_data_changed = true; }
+                                                egui::ScrollArea::vertical().id_salt(("compare", id, side)).max_height(if auto_size { AUTO_SIZE_RESULT_HEIGHT } else { 150.0 }).show(ui, |ui| {
+                                                    match p.data.output() { Some(text) => { ui.small(text); } None => { ui.weak("(no result yet)"); } }
+                                                });
+                                            }
+                                        });
+                                    }
+                                },
+                                NodeData::Select { chosen, .. } => { if select::candidate_list(ui, &self.state, id, chosen) { node_data_changed = true; } }
+                                NodeData::Note { title, body, pass_on } => { if notes::note_ui(ui, id, title, body, pass_on, focus_body) { node_data_changed = true; } }
+                                NodeData::Audio { voice, text, audio, is_loading } => {
+                                    egui::ComboBox::from_id_salt(("voice", id)).selected_text(format!("🗣 {}", voice)).width(110.0).show_ui(ui, |ui| {
+                                        for option in audio::VOICES { if ui.selectable_value(voice, option.to_string(), option).changed() { node_data_changed = true; } }
+                                    });
+                                    let r = named(ui.add(egui::TextEdit::multiline(text).hint_text("Narration text").desired_rows(3)), egui::WidgetType::TextEdit, "narration");
+                                    if focus_body { r.request_focus(); }
+                                    if r.changed() { node_data_changed = true; }
+                                    if *is_loading { busy_indicator(ui, queued); }
+                                    else {
+                                        ui.horizontal(|ui| {
+                                            if named(ui.add_enabled(!text.trim().is_empty(), egui::Button::new("🔊 Generate")), egui::WidgetType::Button, "Generate audio button").clicked() { *is_loading = true; node_data_changed = true; trigger_tts = Some((voice.clone(), text.clone())); }
+                                            let upstream = self.state.upstream_ai_result(id);
+                                            if named(ui.add_enabled(upstream.is_some(), egui::Button::new("🔗 Link")), egui::WidgetType::Button, "Link parent button").on_hover_text("Narrate the nearest upstream AI result").clicked() {
+                                                if let Some(script) = upstream { *text = script; node_data_changed = true; }
+                                            }
+                                        });
+                                    }
+                                    if let Some(clip) = audio.as_ref() {
+                                        ui.horizontal(|ui| {
+                                            match play_position {
+                                                Some(at) => {
+                                                    if named(ui.button("⏹ Stop"), egui::WidgetType::Button, "Stop button").clicked() { play = Some(false); }
+                                                    ui.label(format!("{} / {}", refresh::countdown(at), audio::duration_label(clip)));
+                                                }
+                                                None => {
+                                                    if named(ui.button("▶ Play"), egui::WidgetType::Button, "Play button").clicked() { play = Some(true); }
+                                                    ui.label(audio::duration_label(clip));
+                                                }
+                                            }
+                                        });
+                                    }
+                                }
+                                NodeData::WebFetch { url, result, is_loading } => {
+                                    let r = named(ui.add(egui::TextEdit::singleline(url).hint_text("https://…")), egui::WidgetType::TextEdit, "URL");
+                                    if focus_body { r.request_focus(); }
+                                    if r.changed() { node_data_changed = true; }
+                                    if *is_loading { busy_indicator(ui, queued); }
+                                    else {
+                                        ui.horizontal(|ui| {
+                                            if named(ui.add_enabled(webfetch::looks_fetchable(url), egui::Button::new("📥 Fetch")), egui::WidgetType::Button, "Fetch button").clicked() { *is_loading = true; node_data_changed = true; trigger_fetch = Some(url.trim().to_string()); }
+                                            let link = self.state.parent_output(id).as_deref().and_then(webfetch::first_url);
+                                            if named(ui.add_enabled(link.is_some(), egui::Button::new("🔗 Link")), egui::WidgetType::Button, "Link parent button").on_hover_text("Use the first link in the parents' text").clicked() {
+                                                if let Some(link) = link { *url = link; node_data_changed = true; }
+                                            }
+                                        });
+                                        if let Some(res) = viewed_text.as_ref().or(result.as_ref()) { result_scroll(150.0).show(ui, |ui| { ui.small(res); }); }
+                                    }
+                                }
+                                NodeData::Translate { target_lang, text, result, is_loading } => {
+                                    if translate::language_picker(ui, id, target_lang) { node_data_changed = true; }
+                                    let r = named(ui.add(egui::TextEdit::multiline(text).hint_text("Text to translate").desired_rows(3)), egui::WidgetType::TextEdit, "text to translate");
+                                    if focus_body { r.request_focus(); }
+                                    if r.changed() { node_data_changed = true; }
+                                    if *is_loading { busy_indicator(ui, queued); }
+                                    else {
+                                        ui.horizontal(|ui| {
+                                            let ready = !text.trim().is_empty() && !target_lang.trim().is_empty();
+                                            if named(ui.add_enabled(ready, egui::Button::new("🔤 Translate")), egui::WidgetType::Button, "Translate button").clicked() { *is_loading = true; node_data_changed = true; trigger_translate = Some((target_lang.clone(), text.clone())); }
+                                            if let Some(txt) = link::link_parent_button(ui, &self.state, id, &named) { *text = txt; node_data_changed = true; }
+                                        });
+                                        if let Some(res) = viewed_text.as_ref().or(result.as_ref()) { result_scroll(150.0).show(ui, |ui| { ui.small(res); }); }
+                                    }
+                                }
+                                data @ NodeData::Frame { .. } => { if frame::frame_ui(ui, &self.state, id, data, focus_body) { node_data_changed = true; } }
+                                NodeData::Character { name, description, reference_image } => { if character::character_ui(ui, &self.state, id, name, description, reference_image, focus_body) { node_data_changed = true; } }
+                                NodeData::Script { heading, location, time_of_day, action, dialogue, is_loading, .. } => {
+                                    if script::script_ui(ui, id, heading, location, time_of_day, action, dialogue) { node_data_changed = true; }
+                                    if *is_loading { busy_indicator(ui, queued); }
+                                    else if named(ui.add_enabled(self.state.parent_output(id).is_some(), egui::Button::new("🪄 Parse from parent")), egui::WidgetType::Button, "Parse button").on_hover_text("Have the AI fill in the scene from the parents' text").clicked() { parse = true; }
+                                }
+                                NodeData::Branch { condition, use_ai, model, outcome, input, is_loading } => {
+                                    ui.label(if *use_ai { "Question:" } else { "Contains keyword:" });
+                                    let r = named(ui.text_edit_singleline(condition), egui::WidgetType::TextEdit, "condition");
+                                    if focus_body { r.request_focus(); }
+                                    if r.changed() { node_data_changed = true; }
+                                    if ui.checkbox(use_ai, "🤖 Ask AI").on_hover_text("Ask the model a yes/no question about the parents' text instead of looking for a keyword").changed() { node_data_changed = true; }
+                                    if *use_ai { ui.label("Model:"); if named(ui.text_edit_singleline(model), egui::WidgetType::TextEdit, "model").changed() { node_data_changed = true; } }
+                                    if *is_loading { busy_indicator(ui, queued); } else {
+                                        ui.horizontal(|ui| {
+                                            if named(ui.button("▶ Evaluate"), egui::WidgetType::Button, "Evaluate button").clicked() { evaluate = true; }
+                                            let color = match outcome { Some(true) => STATUS_DONE, Some(false) => STATUS_FAILED, None => theme.muted_text };
+                                            ui.colored_label(color, format!("→ {}", branch::outcome_text(*outcome)));
+                                        });
+                                        if outcome.is_some() && !input.is_empty() { ui.weak(truncate(&input.replace('\n', " "), 80)).on_hover_text("Text the condition was tested on"); }
+                                    }
                                 }
                             }
-                        });
+                            if version_count > 0 && !node_data.is_loading() { version_action = versions::version_navigator(ui, version_count, viewing); }
+                        }).response.rect.height();
                     }).response
                 });
-                if node_data_changed { if let Some(n) = self.state.nodes.get_mut(&id) { n.data = node_data; } }
+                // Presses on the node's own widgets never reach the canvas response, so select from here too.
+                if node_response.contains_pointer() && ctx.input(|i| i.pointer.primary_pressed() && !i.modifiers.shift) && !self.state.nodes[&id].selected { self.state.select_only(id); }
+                if auto_size {
+                    // Widgets aren't scaled with zoom, so the content's screen height maps back to world units through it.
+                    let target = ((content_height + 2.0 * frame.inner_margin.top) / camera_zoom).clamp(MIN_NODE_SIZE.y, AUTO_SIZE_MAX_HEIGHT);
+                    if let Some(n) = self.state.nodes.get_mut(&id) {
+                        let gap = target - n.size.y;
+                        if gap.abs() > 0.5 { n.size.y += gap * AUTO_SIZE_EASE; ctx.request_repaint(); } else { n.size.y = target; }
+                    }
+                }
+                if open_preview { self.image_preview = Some(ImagePreview { id, offset: Vec2::ZERO, zoom: 1.0 }); }
+                if toggle_collapse { if let Some(n) = self.state.nodes.get_mut(&id) { n.toggle_collapsed(); } }
+                if toggle_pin { if let Some(n) = self.state.nodes.get_mut(&id) { n.pinned = !n.pinned; n.velocity = Vec2::ZERO; } }
+                if toggle_raw { if let Some(n) = self.state.nodes.get_mut(&id) { n.raw_markdown = !n.raw_markdown; } }
+                if let Some(commit) = title_edit {
+                    // Escape cancels; Enter or clicking elsewhere commits.
+                    if let (true, Some((_, draft))) = (commit, self.editing_title.take()) { self.state.set_node_title(id, Some(draft.trim().to_string()).filter(|t| !t.is_empty())); }
+                    self.editing_title = None;
+                }
+                if let Some(n) = self.state.nodes.get(&id) {
+                    let hover = ctx.input(|i| i.pointer.hover_pos());
+                    for port in [world_to_screen(n.input_port()), world_to_screen(n.output_port())] {
+                        let hot = hover.is_some_and(|h| h.distance(port) <= PORT_HIT_RADIUS);
+                        ui.painter().circle(port, PORT_RADIUS, if hot { theme.selection } else { theme.handle }, Stroke::new(1.0, theme.canvas_bg));
+                    }
+                    let mut badge_painter = ui.painter().clone();
+                    if dim { badge_painter.multiply_opacity(DIM_OPACITY); }
+                    paint_status_badge(&badge_painter, &theme, n, node_rect, ctx.input(|i| i.time));
+                }
+                if !collapsed {
+                    let grip = resize_handle_rect(node_rect).shrink(3.0);
+                    for k in 1..=3 {
+                        let d = k as f32 * grip.width() / 3.0;
+                        ui.painter().line_segment([Pos2::new(grip.right() - d, grip.bottom()), Pos2::new(grip.right(), grip.bottom() - d)], Stroke::new(1.0, theme.handle));
+                    }
+                }
+                if node_data_changed {
+                    self.stale_check = true;
+                    // Firing a request only flips `is_loading`; that isn't something the user would want to undo.
+                    let is_trigger = trigger_research.is_some() || trigger_visualize.is_some() || trigger_agnostic_ai.is_some() || trigger_tts.is_some() || trigger_translate.is_some() || trigger_fetch.is_some() || trigger_transcribe.is_some() || foxit_request == Some(id);
+                    if let Some(n) = self.state.nodes.get_mut(&id) {
+                        let before = std::mem::replace(&mut n.data, node_data);
+                        if is_trigger { n.error = None; self.retries.remove(&id); }
+                        else { let after = n.data.clone(); self.state.history.push(Command::EditData { id, before, after }); }
+                    }
+                }
                 if let Some(q) = trigger_research { self.trigger_research(id, q, ctx.clone()); }
                 if let Some(p) = trigger_visualize { self.trigger_visualize(id, p, ctx.clone()); }
                 if let Some((m, p)) = trigger_agnostic_ai { self.trigger_agnostic_ai(id, m, p, ctx.clone()); }
+                if let Some((v, t)) = trigger_tts { self.trigger_tts(id, v, t, ctx.clone()); }
+                if let Some((l, t)) = trigger_translate { self.trigger_translate(id, l, t, ctx.clone()); }
+                if let Some(url) = trigger_fetch { self.trigger_fetch(id, url, ctx.clone()); }
+                if let Some((name, clip)) = trigger_transcribe { self.trigger_transcribe(id, name, clip, ctx.clone()); }
+                if pick_audio { self.pick_audio_file(id, ctx); }
+                if refresh_models { self.refresh_local_models(ctx); }
+                match play {
+                    Some(true) => {
+                        let started = match self.state.nodes.get(&id).map(|n| &n.data) { Some(NodeData::Audio { audio: Some(clip), .. }) => self.player.play(id, clip), _ => Ok(()) };
+                        if let Err(err) = started { self.toast(err); }
+                    }
+                    Some(false) => self.player.stop(),
+                    None => {}
+                }
+                if retry || evaluate || parse { self.retry_now(id, ctx); }
+                if refresh { self.force_refresh(id, ctx); }
+                if reschedule { self.reschedule_refresh(id); }
+                if let Some(action) = version_action { self.apply_version_action(id, action, ctx); }
+                if let Some(index) = promote_variant { self.version_views.remove(&id); if let Some(n) = self.state.nodes.get_mut(&id) { n.promote_variant(index); } }
             }
-            if let Some(export_id) = foxit_request {
-                let mut all_text = String::new();
-                for n in self.state.nodes.values() { match &n.data { NodeData::Concept { text } => all_text.push_str(&format!("Concept: {}\n\n", text)), NodeData::YouComResearch { query, result, .. } => all_text.push_str(&format!("Research ({}): {}\n\n", query, result.as_deref().unwrap_or("None"))), NodeData::AgnosticAI { model, prompt, result, .. } => all_text.push_str(&format!("AI ({}, {}): {}\n\n", model, prompt, result.as_deref().unwrap_or("None"))), _ => {} } }
-                self.trigger_foxit(export_id, all_text, ctx.clone());
+            self.perf.record(Phase::Nodes, nodes_started);
+            if let Some(drag) = self.state.dragging.as_ref().filter(|_| self.settings.snap_to_grid) {
+                for node in drag.origins.iter().filter_map(|(id, _)| self.state.nodes.get(id)) {
+                    let ghost = Rect::from_min_size(world_to_screen(self.settings.snap(node.position)), node.size * camera_zoom);
+                    painter.rect_stroke(ghost, 8.0, Stroke::new(1.5, theme.selection.gamma_multiply(0.5)));
+                }
+            }
+            for [a, b] in guides { painter.line_segment([world_to_screen(a), world_to_screen(b)], Stroke::new(1.0, theme.selection)); }
+            self.draw_edge_label_editor(ctx, world_to_screen);
+            self.draw_node_palette(ctx, world_to_screen);
+            self.draw_hover_preview(ui, ctx, screen_to_world);
+            // While linking, say up front why the node under the pointer won't accept the link.
+            let link_refusal = ctx.input(|i| i.pointer.hover_pos()).filter(|&p| !self.state.linking_from.is_empty() && canvas_rect.contains(p)).and_then(|pointer| {
+                let target = self.state.topmost(|n| world_to_screen(n.input_port()).distance(pointer) <= PORT_HIT_RADIUS).or_else(|| self.state.node_at(screen_to_world(pointer)))?;
+                self.state.linking_from.iter().filter(|&&from| from != target).find_map(|&from| self.state.link_mismatch(from, target))
+            });
+            if let Some(reason) = &link_refusal { egui::show_tooltip_at_pointer(ctx, ui.layer_id(), egui::Id::new("link_refusal"), |ui| { ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", reason)); }); }
+            if let (true, Some(from), Some(pointer)) = (self.state.linking_drag, self.state.linking_from.first().and_then(|id| self.state.nodes.get(id)), ctx.input(|i| i.pointer.hover_pos())) {
+                let points = link_curve(world_to_screen(from.output_port()), pointer);
+                let color = if link_refusal.is_some() { ui.visuals().error_fg_color } else { theme.selection };
+                painter.add(egui::Shape::CubicBezier(egui::epaint::CubicBezierShape { points, closed: false, fill: Color32::TRANSPARENT, stroke: Stroke::new(2.0, color).into() }));
             }
+            if self.app_state == AppState::Editing && self.presentation.is_none() {
+                self.draw_minimap(ui, canvas_rect);
+                self.draw_zoom_indicator(ui, canvas_rect);
+                // The performance panel lives in the sidebar; keep a readout visible while it's collapsed.
+                if self.settings.sidebar_collapsed { painter.text(canvas_rect.left_bottom() + Vec2::new(10.0, -10.0), egui::Align2::LEFT_BOTTOM, format!("{:.0} FPS", self.perf.fps()), egui::FontId::monospace(11.0), theme.muted_text); }
+            }
+            if let Some(export_id) = foxit_request { self.trigger_foxit(export_id, self.export_text(export_id), ctx.clone()); }
         });
+        if self.app_state == AppState::Editing { self.draw_image_preview(ctx); self.draw_request_preview(ctx); self.draw_validation(ctx); self.draw_presentation(ctx); self.draw_toasts(ctx); }
         if self.intro_animation > 0.0 { self.draw_intro_screen(ctx); }
         let elapsed = start_time.elapsed().as_secs_f32() * 1000.0;
-        self.frame_times.push(elapsed);
-        if self.frame_times.len() > 60 { self.frame_times.remove(0); }
+        self.perf.record_frame(elapsed);
         ctx.request_repaint();
     }
 }
 
+/// Longest error body from the server shown on a node; anything longer is likely a whole error page.
+const MAX_ERROR_DETAIL: usize = 300;
+
+/// "503 Service Unavailable", followed by the server's explanation when it sent a short one.
+fn response_error(response: &ehttp::Response) -> String {
+    let status = format!("{} {}", response.status, response.status_text);
+    match response.text().map(str::trim).filter(|t| !t.is_empty() && t.len() <= MAX_ERROR_DETAIL) { Some(detail) => format!("{}: {}", status, detail), None => status }
+}
+
+/// The message for a finished node request: `on_ok`'s for a 2xx response, otherwise an `Error` for the node, with the server's
+/// explanation when it sent a short one.
+fn reply(node_id: u64, result: ehttp::Result<ehttp::Response>, on_ok: impl FnOnce(ehttp::Response) -> AppMessage) -> AppMessage {
+    match result {
+        Ok(response) if response.ok => on_ok(response),
+        Ok(response) => AppMessage::Error(node_id, response_error(&response)),
+        Err(err) => AppMessage::Error(node_id, err),
+    }
+}
+
+/// Decodes PNG/JPEG bytes into the texture shown on Visual node `id`.
+fn load_node_texture(ctx: &egui::Context, id: u64, bytes: &[u8]) -> Option<egui::TextureHandle> {
+    load_texture(ctx, format!("node-image-{}", id), bytes)
+}
+
+/// Texture for image `bytes` shown on node `id` outside its own data, decoded once and kept in egui's memory until the bytes change.
+fn cached_texture(ctx: &egui::Context, id: u64, bytes: &[u8]) -> Option<egui::TextureHandle> {
+    let key = egui::Id::new(("cached_texture", id));
+    let stamp = egui::Id::new(bytes).value();
+    if let Some((cached, texture)) = ctx.data(|d| d.get_temp::<(u64, egui::TextureHandle)>(key)) { if cached == stamp { return Some(texture); } }
+    let texture = load_texture(ctx, format!("cached-{}", id), bytes)?;
+    ctx.data_mut(|d| d.insert_temp(key, (stamp, texture.clone())));
+    Some(texture)
+}
+
+fn load_texture(ctx: &egui::Context, name: String, bytes: &[u8]) -> Option<egui::TextureHandle> {
+    let image = image::load_from_memory(bytes).ok()?;
+    let size = [image.width() as usize, image.height() as usize];
+    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, image.to_rgba8().as_raw());
+    Some(ctx.load_texture(name, color_image, egui::TextureOptions::LINEAR))
+}
+
+/// Saves `bytes` for the user: a browser download on wasm, a file in the working directory on native.
+#[cfg(target_arch = "wasm32")]
+fn download_bytes(file_name: &str, mime: &str, bytes: &[u8]) -> Result<(), String> {
+    let parts = js_sys::Array::new();
+    parts.push(&js_sys::Uint8Array::from(bytes));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).map_err(|_| "the browser couldn't hold the file".to_string())?;
+    let anchor = web_sys::window().and_then(|w| w.document()).and_then(|d| d.create_element("a").ok()).ok_or("there's no page to download from")?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(|_| "the browser couldn't link the file".to_string())?;
+    let anchor = anchor.unchecked_into::<web_sys::HtmlAnchorElement>();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    let _ = web_sys::Url::revoke_object_url(&url);
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn download_bytes(file_name: &str, _mime: &str, bytes: &[u8]) -> Result<(), String> {
+    std::fs::write(file_name, bytes).map_err(|e| e.to_string())
+}
+
 fn main() {
     #[cfg(not(target_arch = "wasm32"))]
     {
@@ -0,0 +1,215 @@
+use eframe::egui::{self, text::LayoutJob, Color32, FontId, Stroke, TextFormat};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// Characters rendered before a "Show more" button; each click reveals this many more.
+pub const RENDER_CHARS: usize = 4000;
+
+/// How a run of inline text is set.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style { pub strong: bool, pub emphasis: bool, pub code: bool, pub strike: bool, pub link: bool }
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockKind {
+    Paragraph,
+    Heading(u8),
+    Code,
+    /// A list item, `depth` 0 at the outermost list; the marker is "•" or "3.".
+    Item { depth: usize, marker: String },
+    Rule,
+}
+
+/// One block of the document with its inline runs; `quoted` inside a block quote.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block { pub kind: BlockKind, pub quoted: bool, pub spans: Vec<(String, Style)> }
+
+impl Block {
+    pub fn text(&self) -> String { self.spans.iter().map(|(t, _)| t.as_str()).collect() }
+}
+
+/// `text` read as CommonMark (plus tables, strikethrough and task lists) and flattened into blocks that can be drawn one label each.
+pub fn parse(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    let (mut style, mut quotes, mut lists) = (Style::default(), 0usize, Vec::<Option<u64>>::new());
+    let flush = |current: &mut Option<Block>, blocks: &mut Vec<Block>| {
+        if let Some(mut block) = current.take() {
+            if let Some((last, _)) = block.spans.last_mut().filter(|_| block.kind == BlockKind::Code) { *last = last.trim_end_matches('\n').to_string(); }
+            if !block.text().trim().is_empty() { blocks.push(block); }
+        }
+    };
+    let start = |current: &mut Option<Block>, blocks: &mut Vec<Block>, kind: BlockKind, quoted: bool| {
+        flush(current, blocks);
+        *current = Some(Block { kind, quoted, spans: Vec::new() });
+    };
+    for event in Parser::new_ext(text, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS) {
+        match event {
+            // Loose list items wrap their text in a paragraph; it stays part of the item.
+            Event::Start(Tag::Paragraph) if current.as_ref().is_some_and(|b| matches!(b.kind, BlockKind::Item { .. }) && b.spans.is_empty()) => {}
+            Event::Start(Tag::Paragraph | Tag::TableHead | Tag::TableRow) => start(&mut current, &mut blocks, BlockKind::Paragraph, quotes > 0),
+            Event::Start(Tag::Heading { level, .. }) => start(&mut current, &mut blocks, BlockKind::Heading(heading_level(level)), quotes > 0),
+            Event::Start(Tag::CodeBlock(_)) => start(&mut current, &mut blocks, BlockKind::Code, quotes > 0),
+            Event::Start(Tag::BlockQuote(_)) => { flush(&mut current, &mut blocks); quotes += 1; }
+            Event::Start(Tag::List(first)) => { flush(&mut current, &mut blocks); lists.push(first); }
+            Event::Start(Tag::Item) => {
+                let marker = match lists.last_mut() { Some(Some(n)) => { *n += 1; format!("{}.", *n - 1) } _ => "•".to_string() };
+                start(&mut current, &mut blocks, BlockKind::Item { depth: lists.len().saturating_sub(1), marker }, quotes > 0);
+            }
+            Event::Start(Tag::TableCell) => { if let Some(block) = current.as_mut().filter(|b| !b.spans.is_empty()) { block.spans.push((" │ ".to_string(), Style::default())); } }
+            Event::Start(Tag::Emphasis) => style.emphasis = true,
+            Event::Start(Tag::Strong) => style.strong = true,
+            Event::Start(Tag::Strikethrough) => style.strike = true,
+            Event::Start(Tag::Link { .. }) => style.link = true,
+            Event::Start(Tag::Image { .. }) => { style.link = true; push(&mut current, "🖼 ", style, quotes > 0); }
+            Event::End(TagEnd::Emphasis) => style.emphasis = false,
+            Event::End(TagEnd::Strong) => style.strong = false,
+            Event::End(TagEnd::Strikethrough) => style.strike = false,
+            Event::End(TagEnd::Link | TagEnd::Image) => style.link = false,
+            Event::End(TagEnd::BlockQuote(_)) => { flush(&mut current, &mut blocks); quotes = quotes.saturating_sub(1); }
+            Event::End(TagEnd::List(_)) => { flush(&mut current, &mut blocks); lists.pop(); }
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::Item | TagEnd::TableHead | TagEnd::TableRow) => flush(&mut current, &mut blocks),
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => push(&mut current, &text, style, quotes > 0),
+            Event::Code(text) => push(&mut current, &text, Style { code: true, ..style }, quotes > 0),
+            Event::SoftBreak => push(&mut current, " ", style, quotes > 0),
+            Event::HardBreak => push(&mut current, "\n", style, quotes > 0),
+            Event::TaskListMarker(done) => push(&mut current, if done { "☑ " } else { "☐ " }, style, quotes > 0),
+            Event::Rule => { flush(&mut current, &mut blocks); blocks.push(Block { kind: BlockKind::Rule, quoted: quotes > 0, spans: Vec::new() }); }
+            _ => {}
+        }
+    }
+    flush(&mut current, &mut blocks);
+    blocks
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level { HeadingLevel::H1 => 1, HeadingLevel::H2 => 2, HeadingLevel::H3 => 3, HeadingLevel::H4 => 4, HeadingLevel::H5 => 5, HeadingLevel::H6 => 6 }
+}
+
+/// Adds a run to the open block, opening a paragraph for text that arrives outside one (tight table cells, stray HTML).
+fn push(current: &mut Option<Block>, text: &str, style: Style, quoted: bool) {
+    let block = current.get_or_insert_with(|| Block { kind: BlockKind::Paragraph, quoted, spans: Vec::new() });
+    match block.spans.last_mut() {
+        Some((last, last_style)) if *last_style == style => last.push_str(text),
+        _ => block.spans.push((text.to_string(), style)),
+    }
+}
+
+/// The start of `text` holding at most `limit` characters, cut at a paragraph or line break when one is near, and how many characters
+/// were left out.
+pub fn capped(text: &str, limit: usize) -> (&str, usize) {
+    let Some((end, _)) = text.char_indices().nth(limit) else { return (text, 0) };
+    let head = &text[..end];
+    let cut = [head.rfind("\n\n"), head.rfind('\n')].into_iter().flatten().find(|&at| at >= end / 2).unwrap_or(end);
+    (&text[..cut], text[cut..].chars().count())
+}
+
+/// Draws `text` as markdown in the current theme's colors, with body text at `size` points.
+pub fn render(ui: &mut egui::Ui, text: &str, size: f32) {
+    let visuals = ui.visuals().clone();
+    for block in parse(text) {
+        let color = if block.quoted { visuals.weak_text_color() } else { visuals.text_color() };
+        let job = |font_size: f32, heading: bool| {
+            let mut job = LayoutJob::default();
+            for (text, style) in &block.spans {
+                let code = style.code || block.kind == BlockKind::Code;
+                job.append(text, 0.0, TextFormat {
+                    font_id: if code { FontId::monospace(font_size * 0.95) } else { FontId::proportional(font_size) },
+                    color: if style.link { visuals.hyperlink_color } else if style.strong || heading { visuals.strong_text_color() } else { color },
+                    background: if style.code && block.kind != BlockKind::Code { visuals.code_bg_color } else { Color32::TRANSPARENT },
+                    italics: style.emphasis,
+                    underline: if style.link { Stroke::new(1.0, visuals.hyperlink_color) } else { Stroke::NONE },
+                    strikethrough: if style.strike { Stroke::new(1.0, color) } else { Stroke::NONE },
+                    ..Default::default()
+                });
+            }
+            job
+        };
+        ui.horizontal_top(|ui| {
+            if block.quoted { ui.label(egui::RichText::new("▌").color(visuals.weak_text_color())); }
+            ui.vertical(|ui| match &block.kind {
+                BlockKind::Paragraph => { ui.add(egui::Label::new(job(size, false)).wrap()); }
+                BlockKind::Heading(level) => {
+                    ui.add_space(size * 0.3);
+                    ui.add(egui::Label::new(job(size * [1.5, 1.3, 1.15].get(*level as usize - 1).copied().unwrap_or(1.0), true)).wrap());
+                }
+                BlockKind::Code => {
+                    egui::Frame::none().fill(visuals.code_bg_color).rounding(4.0).inner_margin(6.0).show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.add(egui::Label::new(job(size, false)).wrap());
+                    });
+                }
+                BlockKind::Item { depth, marker } => {
+                    ui.horizontal_top(|ui| {
+                        ui.add_space(12.0 * *depth as f32);
+                        ui.label(egui::RichText::new(marker).size(size).color(visuals.weak_text_color()));
+                        ui.add(egui::Label::new(job(size, false)).wrap());
+                    });
+                }
+                BlockKind::Rule => { ui.separator(); }
+            });
+        });
+    }
+}
+
+/// `render` for the first `RENDER_CHARS` of a long document, with a button under it revealing more. The reveal is remembered under `id`.
+pub fn render_capped(ui: &mut egui::Ui, id: egui::Id, text: &str, size: f32) {
+    let limit = ui.data(|d| d.get_temp::<usize>(id)).unwrap_or(RENDER_CHARS);
+    let (shown, hidden) = capped(text, limit);
+    render(ui, shown, size);
+    if hidden > 0 {
+        ui.horizontal(|ui| {
+            if ui.small_button("⬇ Show more").clicked() { ui.data_mut(|d| d.insert_temp(id, limit + RENDER_CHARS)); }
+            ui.weak(format!("{} more characters", hidden));
+        });
+    }
+}
+
+/// A node's result with a button switching between the rendered and the raw markdown, inside `scroll`. Returns true when the
+/// button was clicked.
+pub fn result_view(ui: &mut egui::Ui, node: u64, text: &str, raw: bool, scroll: egui::ScrollArea) -> bool {
+    let toggled = ui.horizontal(|ui| {
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.small_button(if raw { "✨ Rendered" } else { "📝 Raw" }).on_hover_text(if raw { "Show the result formatted as markdown" } else { "Show the result as the model wrote it" }).clicked()
+        }).inner
+    }).inner;
+    scroll.show(ui, |ui| {
+        if raw { ui.small(text); } else { render_capped(ui, egui::Id::new(("markdown_more", node)), text, ui.style().text_styles[&egui::TextStyle::Body].size); }
+    });
+    toggled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_flattens_into_styled_blocks() {
+        let blocks = parse("# Scene **one**\n\nThe *dock* at `dawn`.\n\n- gulls\n  1. wheel\n  2. dive\n- fog\n\n> quiet\n\n---\n```\nlet x = 1;\n```");
+        let kinds: Vec<&BlockKind> = blocks.iter().map(|b| &b.kind).collect();
+        assert_eq!(kinds, [
+            &BlockKind::Heading(1),
+            &BlockKind::Paragraph,
+            &BlockKind::Item { depth: 0, marker: "•".to_string() },
+            &BlockKind::Item { depth: 1, marker: "1.".to_string() },
+            &BlockKind::Item { depth: 1, marker: "2.".to_string() },
+            &BlockKind::Item { depth: 0, marker: "•".to_string() },
+            &BlockKind::Paragraph,
+            &BlockKind::Rule,
+            &BlockKind::Code,
+        ]);
+        assert_eq!(blocks[0].spans, [("Scene ".to_string(), Style::default()), ("one".to_string(), Style { strong: true, ..Default::default() })]);
+        assert_eq!(blocks[1].spans[1], ("dock".to_string(), Style { emphasis: true, ..Default::default() }));
+        assert_eq!(blocks[1].spans[3], ("dawn".to_string(), Style { code: true, ..Default::default() }));
+        assert!(blocks[6].quoted && !blocks[1].quoted);
+        assert_eq!(blocks[8].text(), "let x = 1;");
+    }
+
+    #[test]
+    fn long_documents_are_cut_at_a_break() {
+        assert_eq!(capped("short", 10), ("short", 0));
+        let text = format!("{}\n\n{}", "a".repeat(60), "b".repeat(60));
+        assert_eq!(capped(&text, 100), (&text[..60], 62));
+        let accents = "é".repeat(30);
+        let (shown, hidden) = capped(&accents, 10);
+        assert_eq!((shown.chars().count(), hidden), (10, 20));
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{script, transform, CanvasState, NodeData};
+use std::collections::HashSet;
+
+pub fn default_separator() -> String { "\\n\\n".to_string() }
+
+/// The separator as typed, with `\n` and `\t` turned into a newline and a tab.
+pub fn unescape(separator: &str) -> String {
+    separator.replace("\\n", "\n").replace("\\t", "\t")
+}
+
+impl CanvasState {
+    /// Parents of `id` that hand text downstream, once each in link order, leaving out branches not taken.
+    pub fn text_parents(&self, id: u64) -> Vec<u64> {
+        let mut seen = HashSet::new();
+        self.edges.iter().filter(|e| e.to == id && self.edge_active(e) && seen.insert(e.from)).filter(|e| self.nodes.get(&e.from).is_some_and(|n| n.data.produces_text())).map(|e| e.from).collect()
+    }
+
+    /// What Merge node `id` hands downstream: the checked parents' output in link order, joined by its separator.
+    fn merged_text(&self, id: u64, separator: &str, excluded: &[u64]) -> String {
+        let parts: Vec<&str> = self.text_parents(id).into_iter().filter(|p| !excluded.contains(p)).filter_map(|p| self.nodes[&p].data.output()).collect();
+        parts.join(&unescape(separator))
+    }
+
+    /// Brings every Merge, Compare, Select, Script, Transform and Markdown View node's output up to date, upstream ones first so chains of them settle in one pass.
+    pub fn recompute_outputs(&mut self) {
+        if !self.nodes.values().any(|n| matches!(n.data, NodeData::Merge { .. } | NodeData::Compare { .. } | NodeData::Select { .. } | NodeData::Script { .. } | NodeData::Transform { .. } | NodeData::MarkdownView { .. })) { return; }
+        for id in self.presentation_order() {
+            // The flag is whether a Compare node is paired, or whether a Select node chose an image.
+            let (mut failure, mut transformed) = (None, None);
+            let (text, flag) = match self.nodes.get(&id).map(|n| &n.data) {
+                Some(NodeData::Merge { separator, excluded, .. }) => (self.merged_text(id, separator, excluded), true),
+                Some(NodeData::Compare { picked, .. }) => (self.compared_text(id, *picked), self.compare_pair(id).is_some()),
+                Some(NodeData::Select { chosen, .. }) => self.selected_output(id, *chosen),
+                Some(NodeData::Script { heading, location, time_of_day, action, dialogue, .. }) => (script::screenplay(heading, location, time_of_day, action, dialogue), true),
+                // Rerunning the steps recompiles their patterns, so the last result is kept until the input or the steps change.
+                Some(NodeData::Transform { steps, .. }) => match self.parent_output(id) {
+                    Some(input) => {
+                        let key = transform::fingerprint(steps, &input);
+                        let result = match self.transform_cache.get(&id) {
+                            Some((cached, result)) if *cached == key => result.clone(),
+                            _ => transformed.insert((key, transform::run(steps, &input))).1.clone(),
+                        };
+                        match result { Ok(text) => (text, true), Err(err) => { failure = Some(err); (String::new(), false) } }
+                    }
+                    None => (String::new(), true),
+                },
+                Some(NodeData::MarkdownView { .. }) => (self.parent_output(id).unwrap_or_default(), true),
+                _ => continue,
+            };
+            if let Some(entry) = transformed { self.transform_cache.insert(id, entry); }
+            match self.nodes.get_mut(&id).map(|n| &mut n.data) {
+                Some(NodeData::Merge { output, .. }) => *output = text,
+                Some(NodeData::Compare { output, paired, .. }) => { *output = text; *paired = flag; }
+                Some(NodeData::Select { output, chosen_image, .. }) => { *output = text; *chosen_image = flag; }
+                Some(NodeData::Script { output, .. }) => *output = text,
+                Some(NodeData::Transform { output, error, .. }) => { *output = text; *error = failure; }
+                Some(NodeData::MarkdownView { text: shown }) => *shown = text,
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::theme::Theme;
+use crate::NodeData;
+use eframe::egui::{self, Color32};
+
+/// Notes sit back from the working nodes: their body is drawn partway towards the canvas color.
+pub fn note_fill(theme: &Theme) -> Color32 {
+    theme.node_fill.lerp_to_gamma(theme.canvas_bg, 0.5)
+}
+
+impl NodeData {
+    /// A Note node's own title or a Character's name, when it has one.
+    pub fn own_title(&self) -> Option<&str> {
+        match self { Self::Note { title, .. } | Self::Character { name: title, .. } => Some(title.as_str()).filter(|t| !t.trim().is_empty()), _ => None }
+    }
+}
+
+fn starts_block(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('#') || line.starts_with("- ") || line.starts_with("* ") || line.split_once(". ").is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Lays out a note body the way markdown would: single line breaks flow together, a blank line starts a new paragraph, and a line
+/// ending in two spaces or a backslash breaks where it is. List items and headings always start their own line.
+pub fn render_body(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut separator = "";
+    for line in body.lines() {
+        if line.trim().is_empty() { if !out.is_empty() { separator = "\n\n"; } continue; }
+        out.push_str(if separator == " " && starts_block(line) { "\n" } else { separator });
+        let text = if separator == " " { line.trim_start() } else { line };
+        let (text, hard) = match text.trim_end().strip_suffix('\\') { Some(t) => (t, true), None => (text.trim_end(), line.ends_with("  ")) };
+        out.push_str(text);
+        separator = if hard { "\n" } else { " " };
+    }
+    out
+}
+
+/// Title, body and "📤 Pass on" checkbox of a Note node. The body reads as rendered text until clicked or "✏ Edit" is pressed; which of
+/// the two is shown is view state, kept in egui's memory. Returns true when anything was edited.
+pub fn note_ui(ui: &mut egui::Ui, id: u64, title: &mut String, body: &mut String, pass_on: &mut bool, focus_body: bool) -> bool {
+    let edit_id = egui::Id::new(("note_edit", id));
+    let mut editing = focus_body || body.is_empty() || ui.data(|d| d.get_temp(edit_id)).unwrap_or(false);
+    let mut changed = ui.add(egui::TextEdit::singleline(title).hint_text("Title").desired_width(f32::INFINITY)).changed();
+    if editing {
+        let r = ui.add(egui::TextEdit::multiline(body).hint_text("Write anything; a blank line starts a new paragraph").desired_width(f32::INFINITY));
+        if focus_body { r.request_focus(); }
+        changed |= r.changed();
+        if !body.is_empty() && ui.small_button("✔ Done").clicked() { editing = false; }
+    } else {
+        let r = ui.add(egui::Label::new(render_body(body)).wrap().sense(egui::Sense::click())).on_hover_text("Click to edit");
+        if r.clicked() || ui.small_button("✏ Edit").clicked() { editing = true; }
+    }
+    changed |= ui.checkbox(pass_on, "📤 Pass on").on_hover_text("Include this note in Link Parent and in PDF exports; notes are left out otherwise").changed();
+    ui.data_mut(|d| d.insert_temp(edit_id, editing));
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CanvasState, Edge, Node, NodeKind};
+
+    #[test]
+    fn line_breaks_follow_markdown() {
+        assert_eq!(render_body("Scene one\ncontinues here\n\n\nNew part  \nforced break\\\nand more"), "Scene one continues here\n\nNew part\nforced break\nand more");
+        assert_eq!(render_body("Shots:\n- wide\n- close\n1. first\ntail"), "Shots:\n- wide\n- close\n1. first tail");
+        assert_eq!(render_body("\n\nlead\n\n"), "lead");
+    }
+
+    #[test]
+    fn only_notes_passed_on_reach_children() {
+        let mut state = CanvasState::default();
+        let note = |pass_on| NodeData::Note { title: "Idea".to_string(), body: "keep the red door".to_string(), pass_on };
+        state.nodes.insert(1, Node::new(1, Default::default(), note(false)));
+        state.nodes.insert(2, Node::new(2, Default::default(), NodeKind::AgnosticAI.default_data()));
+        state.edges.push(Edge { id: 3, from: 1, to: 2, label: None, role: None });
+        assert!(state.link_mismatch(1, 2).is_none());
+        assert_eq!(state.parent_output(2), None);
+        state.nodes.get_mut(&1).unwrap().data = note(true);
+        assert_eq!(state.parent_output(2).as_deref(), Some("keep the red door"));
+        assert_eq!(state.nodes[&1].display_title(), "Idea");
+        let saved: NodeData = serde_json::from_str(r#"{"Note":{"title":"Idea","body":"b","pinned":true}}"#).unwrap();
+        assert!(matches!(saved, NodeData::Note { pass_on: true, .. }), "boards saved with the old field name keep their notes passed on");
+    }
+}
@@ -0,0 +1,70 @@
+use eframe::egui::{self, Color32, Pos2, Sense, Stroke, Vec2};
+use std::collections::VecDeque;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Number of frames kept for the sparkline and the FPS average.
+const HISTORY: usize = 120;
+/// Weight of the newest sample in the smoothed phase timings.
+const SMOOTHING: f32 = 0.1;
+
+/// Where a frame's time goes, smoothed so the numbers are readable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase { Physics, Edges, Nodes }
+
+#[derive(Default)]
+pub struct PerfStats {
+    frame_ms: VecDeque<f32>,
+    phase_ms: [f32; 3],
+}
+
+impl PerfStats {
+    pub fn record_frame(&mut self, ms: f32) {
+        self.frame_ms.push_back(ms);
+        if self.frame_ms.len() > HISTORY { self.frame_ms.pop_front(); }
+    }
+
+    /// Folds the time since `started` into `phase`'s average.
+    pub fn record(&mut self, phase: Phase, started: Instant) {
+        let ms = started.elapsed().as_secs_f32() * 1000.0;
+        let slot = &mut self.phase_ms[phase as usize];
+        *slot += (ms - *slot) * SMOOTHING;
+    }
+
+    pub fn fps(&self) -> f32 {
+        1000.0 / (self.frame_ms.iter().sum::<f32>() / self.frame_ms.len().max(1) as f32)
+    }
+
+    /// Collapsible panel with a frame-time sparkline, per-phase timings and graph counts.
+    pub fn show(&self, ui: &mut egui::Ui, nodes: usize, edges: usize, in_flight: usize) {
+        egui::CollapsingHeader::new(format!("📈 Performance · {:.0} FPS", self.fps())).id_salt("performance").show(ui, |ui| {
+            let (rect, _) = ui.allocate_exact_size(Vec2::new(ui.available_width(), 40.0), Sense::hover());
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+            // Scale to the worst recent frame, but never below a 60 FPS budget so a smooth run reads as flat.
+            let max = self.frame_ms.iter().copied().fold(1000.0 / 60.0, f32::max);
+            let step = rect.width() / (HISTORY - 1) as f32;
+            let points: Vec<Pos2> = self.frame_ms.iter().enumerate().map(|(i, ms)| Pos2::new(rect.left() + i as f32 * step, rect.bottom() - ms / max * rect.height())).collect();
+            let budget = rect.bottom() - (1000.0 / 60.0) / max * rect.height();
+            painter.hline(rect.x_range(), budget, Stroke::new(1.0, Color32::from_rgb(60, 150, 60).gamma_multiply(0.6)));
+            painter.add(egui::Shape::line(points, Stroke::new(1.0, ui.visuals().text_color())));
+            egui::Grid::new("perf_grid").num_columns(2).show(ui, |ui| {
+                for (label, ms) in [("Physics", self.phase_ms[Phase::Physics as usize]), ("Edges", self.phase_ms[Phase::Edges as usize]), ("Nodes", self.phase_ms[Phase::Nodes as usize])] {
+                    ui.label(label);
+                    ui.monospace(format!("{:.2} ms", ms));
+                    ui.end_row();
+                }
+                ui.label("Nodes / edges");
+                ui.monospace(format!("{} / {}", nodes, edges));
+                ui.end_row();
+                ui.label("Requests in flight");
+                ui.monospace(in_flight.to_string());
+                ui.end_row();
+            });
+        });
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{CanvasState, Instant, Node, NodeData};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::Duration;
+
+/// A "Run Pipeline" in progress: nodes are started one at a time in dependency order, each once the previous one has answered.
+pub struct PipelineRun {
+    pub order: Vec<u64>,
+    /// Index into `order` of the next node to look at.
+    pub next: usize,
+    /// Node whose request is in flight.
+    pub current: Option<u64>,
+    pub started: Instant,
+    /// When the node in flight was started.
+    pub current_started: Option<Instant>,
+    /// Nodes that have answered, in order, with how long each took.
+    pub done: Vec<(u64, Duration)>,
+}
+
+impl PipelineRun {
+    pub fn new(order: Vec<u64>) -> Self { Self { order, next: 0, current: None, started: Instant::now(), current_started: None, done: Vec::new() } }
+
+    /// What's left in the sidebar once the run is over; `failure` says why it stopped early.
+    pub fn summary(&self, failure: Option<String>) -> PipelineSummary {
+        PipelineSummary { total: self.started.elapsed(), completed: self.done.len(), planned: self.order.len(), failure }
+    }
+}
+
+/// The line a finished run leaves in the sidebar until dismissed.
+pub struct PipelineSummary {
+    pub total: Duration,
+    pub completed: usize,
+    pub planned: usize,
+    pub failure: Option<String>,
+}
+
+impl PipelineSummary {
+    pub fn text(&self) -> String {
+        match &self.failure {
+            None => format!("✔ Ran {} nodes in {:.1}s, no failures", self.completed, self.total.as_secs_f32()),
+            Some(why) => format!("⚠ Stopped after {}/{} nodes in {:.1}s: {}", self.completed, self.planned, self.total.as_secs_f32(), why),
+        }
+    }
+}
+
+impl NodeData {
+    /// Text this node hands to its children when the pipeline runs.
+    pub fn output(&self) -> Option<&str> {
+        match self {
+            Self::Concept { text } | Self::Merge { output: text, .. } | Self::Compare { output: text, .. } | Self::Branch { input: text, .. } | Self::Select { output: text, .. } | Self::Script { output: text, .. } | Self::Transform { output: text, .. } | Self::Frame { caption: text, .. } | Self::Transcribe { transcript: text, .. } => Some(text.as_str()),
+            Self::YouComResearch { result, .. } | Self::AgnosticAI { result, .. } | Self::Translate { result, .. } | Self::WebFetch { result, .. } => result.as_deref(),
+            Self::Note { body, pass_on: true, .. } => Some(body.as_str()),
+            Self::Visual { .. } | Self::FoxitExport { .. } | Self::Audio { .. } | Self::Note { .. } | Self::Character { .. } | Self::MarkdownView { .. } => None,
+        }.filter(|t| !t.trim().is_empty())
+    }
+
+    /// Whether children can read this node's output: Link Parent, templates, Merge inputs and pipeline runs. Notes only once passed on.
+    pub fn produces_text(&self) -> bool {
+        matches!(self, Self::Concept { .. } | Self::YouComResearch { .. } | Self::AgnosticAI { .. } | Self::Merge { .. } | Self::Compare { .. } | Self::Branch { .. } | Self::Select { .. } | Self::Script { .. } | Self::Translate { .. } | Self::WebFetch { .. } | Self::Transform { .. } | Self::Frame { .. } | Self::Transcribe { .. } | Self::Note { pass_on: true, .. })
+    }
+}
+
+/// True when adding `from → to` to `edges` would close a loop: a self-link, or `to` already reaching `from`.
+pub fn creates_cycle(edges: impl Iterator<Item = (u64, u64)>, from: u64, to: u64) -> bool {
+    if from == to { return true; }
+    let edges: Vec<(u64, u64)> = edges.collect();
+    let mut stack = vec![to];
+    let mut seen = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if id == from { return true; }
+        if seen.insert(id) { stack.extend(edges.iter().filter(|&&(a, _)| a == id).map(|&(_, b)| b)); }
+    }
+    false
+}
+
+impl CanvasState {
+    pub fn would_create_cycle(&self, from: u64, to: u64) -> bool {
+        creates_cycle(self.edges.iter().map(|e| (e.from, e.to)), from, to)
+    }
+
+    /// Every node, parents before children with ties broken by id, or the nodes a cycle kept from being ordered.
+    pub fn pipeline_order(&self) -> Result<Vec<u64>, Vec<u64>> {
+        self.order_of(&self.nodes.keys().copied().collect())
+    }
+
+    /// Every node `id` depends on, directly or through other nodes.
+    pub fn ancestors(&self, id: u64) -> HashSet<u64> {
+        let mut found = HashSet::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            for edge in self.edges.iter().filter(|e| e.to == current && self.nodes.contains_key(&e.from)) {
+                if edge.from != id && found.insert(edge.from) { stack.push(edge.from); }
+            }
+        }
+        found
+    }
+
+    /// Every node that depends on `id`, directly or through other nodes.
+    pub fn descendants(&self, id: u64) -> HashSet<u64> {
+        let mut found = HashSet::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            for edge in self.edges.iter().filter(|e| e.from == current && self.nodes.contains_key(&e.to)) {
+                if edge.to != id && found.insert(edge.to) { stack.push(edge.to); }
+            }
+        }
+        found
+    }
+
+    /// `ancestors(id)` ordered parents first, the way a pipeline run would reach them.
+    pub fn upstream_order(&self, id: u64) -> Result<Vec<u64>, Vec<u64>> {
+        self.order_of(&self.ancestors(id))
+    }
+
+    /// `ids` sorted so every node comes after its parents among them, ties broken by id.
+    fn order_of(&self, ids: &HashSet<u64>) -> Result<Vec<u64>, Vec<u64>> {
+        let (order, stuck) = self.parents_first(ids);
+        if stuck.is_empty() { Ok(order) } else { Err(stuck) }
+    }
+
+    /// The nodes among `ids` that can be ordered parents first, lowest id first among those ready, and then the ones left waiting
+    /// on a cycle, in id order.
+    pub fn parents_first(&self, ids: &HashSet<u64>) -> (Vec<u64>, Vec<u64>) {
+        let ids: HashSet<u64> = ids.iter().copied().filter(|id| self.nodes.contains_key(id)).collect();
+        let edges: Vec<(u64, u64)> = self.edges.iter().filter(|e| ids.contains(&e.from) && ids.contains(&e.to)).map(|e| (e.from, e.to)).collect();
+        let mut in_degree: HashMap<u64, usize> = ids.iter().map(|&id| (id, 0)).collect();
+        for &(_, to) in &edges { *in_degree.get_mut(&to).unwrap() += 1; }
+        let mut ready: BTreeSet<u64> = in_degree.iter().filter(|(_, &d)| d == 0).map(|(&id, _)| id).collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(id) = ready.pop_first() {
+            order.push(id);
+            for &(_, child) in edges.iter().filter(|&&(from, _)| from == id) {
+                let d = in_degree.get_mut(&child).unwrap();
+                *d -= 1;
+                if *d == 0 { ready.insert(child); }
+            }
+        }
+        let mut stuck: Vec<u64> = in_degree.into_iter().filter(|&(_, d)| d > 0).map(|(id, _)| id).collect();
+        stuck.sort();
+        (order, stuck)
+    }
+
+    /// Raw bytes of the first image a parent hands `id` (a Visual node's, or one a Select node chose), in link order.
+    pub fn parent_image(&self, id: u64) -> Option<&[u8]> {
+        self.edges.iter().filter(|e| e.to == id && self.edge_active(e)).find_map(|e| self.image_output(e.from))
+    }
+
+    /// Text handed to node `id` by "Link Parent" and pipeline runs. Several text-producing parents are joined in edge order, each under a
+    /// "— from Research #3 —" line, with a placeholder for any still waiting on a result; a lone parent passes its output through unchanged,
+    /// or that labelled placeholder while it has none.
+    pub fn parent_output(&self, id: u64) -> Option<String> {
+        let parents: Vec<&Node> = self.text_parents(id).iter().map(|p| &self.nodes[p]).collect();
+        let labelled = |p: &Node| format!("— from {} #{} —\n{}", p.data.kind().title(), p.id, p.data.output().unwrap_or("(no result yet)"));
+        match parents[..] {
+            [] => None,
+            [only] => Some(only.data.output().map_or_else(|| labelled(only), str::to_string)),
+            _ => Some(parents.iter().map(|p| labelled(p)).collect::<Vec<_>>().join("\n\n")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CanvasState, NodeKind};
+
+    fn graph(pairs: &[(u64, u64)]) -> CanvasState { CanvasState::graph(&[], pairs) }
+
+    fn graph_with_nodes(ids: &[u64], pairs: &[(u64, u64)]) -> CanvasState {
+        CanvasState::graph(&ids.iter().map(|&id| (id, NodeKind::Concept)).collect::<Vec<_>>(), pairs)
+    }
+
+    #[test]
+    fn self_link_is_a_cycle() {
+        assert!(graph(&[]).would_create_cycle(1, 1));
+    }
+
+    #[test]
+    fn closing_a_long_chain_is_a_cycle() {
+        let chain: Vec<(u64, u64)> = (1..20).map(|i| (i, i + 1)).collect();
+        let state = graph(&chain);
+        assert!(state.would_create_cycle(20, 1));
+        assert!(state.would_create_cycle(12, 5));
+        assert!(!state.would_create_cycle(1, 20));
+        assert!(!state.would_create_cycle(20, 21));
+    }
+
+    #[test]
+    fn diamond_allows_shortcuts_but_not_back_edges() {
+        // 1 → 2 → 4 and 1 → 3 → 4
+        let state = graph(&[(1, 2), (1, 3), (2, 4), (3, 4)]);
+        assert!(!state.would_create_cycle(1, 4));
+        assert!(!state.would_create_cycle(2, 3));
+        assert!(state.would_create_cycle(4, 1));
+        assert!(state.would_create_cycle(4, 2));
+    }
+
+    #[test]
+    fn batch_rejects_pairs_that_close_a_loop_among_themselves() {
+        let mut state = graph(&[]);
+        state.next_id = 1;
+        let rejected = state.add_edges(&[(1, 2), (2, 3), (3, 1)]);
+        assert_eq!(rejected, vec![(3, 1)]);
+        assert_eq!(state.edges.len(), 2);
+    }
+
+    #[test]
+    fn upstream_of_a_diamond_lists_each_ancestor_once_parents_first() {
+        // 1 → 2 → 4 → 5 and 1 → 3 → 4
+        let state = graph_with_nodes(&[1, 2, 3, 4, 5], &[(1, 2), (1, 3), (2, 4), (3, 4), (4, 5)]);
+        assert_eq!(state.upstream_order(4), Ok(vec![1, 2, 3]));
+        assert_eq!(state.upstream_order(5), Ok(vec![1, 2, 3, 4]));
+        assert_eq!(state.upstream_order(1), Ok(vec![]));
+    }
+
+    #[test]
+    fn upstream_leaves_out_disconnected_and_downstream_nodes() {
+        // 1 → 2 → 3, 2 → 4, and an unrelated 5 → 6
+        let state = graph_with_nodes(&[1, 2, 3, 4, 5, 6], &[(1, 2), (2, 3), (2, 4), (5, 6)]);
+        assert_eq!(state.upstream_order(3), Ok(vec![1, 2]));
+        assert_eq!(state.upstream_order(6), Ok(vec![5]));
+        assert_eq!(state.pipeline_order(), Ok(vec![1, 2, 3, 4, 5, 6]));
+    }
+}
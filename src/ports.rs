@@ -0,0 +1,112 @@
+use crate::{CanvasState, NodeData};
+
+/// What flows along an edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortKind { Text, Image, Character }
+
+impl PortKind {
+    pub fn label(self) -> &'static str {
+        match self { Self::Text => "text", Self::Image => "an image", Self::Character => "a character" }
+    }
+}
+
+impl NodeData {
+    /// What this node hands to its children, if anything.
+    pub fn output_kind(&self) -> Option<PortKind> {
+        match self {
+            // A note that isn't passed on can still be linked; it just hands nothing over.
+            Self::Concept { .. } | Self::YouComResearch { .. } | Self::AgnosticAI { .. } | Self::Merge { .. } | Self::Compare { .. } | Self::Branch { .. } | Self::Note { .. } | Self::Script { .. } | Self::Translate { .. } | Self::WebFetch { .. } | Self::Transform { .. } | Self::Frame { .. } | Self::Transcribe { .. } => Some(PortKind::Text),
+            Self::Visual { .. } | Self::Select { chosen_image: true, .. } => Some(PortKind::Image),
+            Self::Select { .. } => Some(PortKind::Text),
+            Self::Character { .. } => Some(PortKind::Character),
+            Self::FoxitExport { .. } | Self::Audio { .. } | Self::MarkdownView { .. } => None,
+        }
+    }
+
+    /// What this node accepts from its parents.
+    pub fn input_kinds(&self) -> &'static [PortKind] {
+        match self {
+            Self::Concept { .. } | Self::Note { .. } | Self::Transcribe { .. } => &[],
+            // A linked image can be taken as the portrait.
+            Self::Character { .. } => &[PortKind::Image],
+            Self::YouComResearch { .. } | Self::AgnosticAI { .. } | Self::FoxitExport { .. } | Self::Merge { .. } | Self::Compare { .. } | Self::Branch { .. } | Self::Audio { .. } | Self::Script { .. } | Self::Translate { .. } | Self::WebFetch { .. } | Self::Transform { .. } | Self::MarkdownView { .. } => &[PortKind::Text],
+            // An image parent is the starting point for image-to-image.
+            Self::Visual { .. } => &[PortKind::Text, PortKind::Image, PortKind::Character],
+            // A Visual parent's image fills a frame without an upload of its own; text parents can fill the caption.
+            Self::Frame { .. } => &[PortKind::Image, PortKind::Text],
+            Self::Select { .. } => &[PortKind::Text, PortKind::Image],
+        }
+    }
+}
+
+/// Why `from → to` makes no sense, or `None` when `to` accepts what `from` produces.
+pub fn port_mismatch(from: &NodeData, to: &NodeData) -> Option<String> {
+    let (from_name, to_name) = (from.kind().title(), to.kind().title());
+    let Some(out) = from.output_kind() else { return Some(format!("{} has no output to link from", from_name)) };
+    let accepted = to.input_kinds();
+    if accepted.is_empty() { return Some(format!("{} takes no input", to_name)); }
+    if accepted.contains(&out) { return None; }
+    Some(format!("{} produces {}, but {} only accepts {}", from_name, out.label(), to_name, accepted.iter().map(|k| k.label()).collect::<Vec<_>>().join(" or ")))
+}
+
+impl CanvasState {
+    /// `port_mismatch` for two nodes on this canvas; unknown ids aren't judged.
+    pub fn link_mismatch(&self, from: u64, to: u64) -> Option<String> {
+        port_mismatch(&self.nodes.get(&from)?.data, &self.nodes.get(&to)?.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeKind::{self, *};
+
+    fn allowed(from: NodeKind, to: NodeKind) -> bool {
+        port_mismatch(&from.default_data(), &to.default_data()).is_none()
+    }
+
+    #[test]
+    fn validation_matrix() {
+        // Rows are sources, columns targets, in `NodeKind::ALL` order: Concept, Research, AI, Visual, Foxit, Merge, Compare, Branch, Select, Note, Audio, Script, Character, Translate, WebFetch, Transform, Frame, MarkdownView, Transcribe.
+        let expected = [
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, false, false, true, false, false, false, false, true, false, false, false, true, false, false, false, true, false, false],
+            [false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, false, false, true, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+            [false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false],
+            [false, true, true, true, true, true, true, true, true, false, true, true, false, true, true, true, true, true, false],
+        ];
+        for (from, row) in NodeKind::ALL.into_iter().zip(expected) {
+            for (to, ok) in NodeKind::ALL.into_iter().zip(row) { assert_eq!(allowed(from, to), ok, "{:?} → {:?}", from, to); }
+        }
+    }
+
+    #[test]
+    fn mismatch_explains_both_ends() {
+        let msg = port_mismatch(&Visual.default_data(), &Research.default_data()).unwrap();
+        assert!(msg.contains("AI Visualizer produces an image") && msg.contains("You.com Research only accepts text"), "{}", msg);
+        assert!(port_mismatch(&FoxitExport.default_data(), &Concept.default_data()).unwrap().contains("no output"));
+    }
+
+    #[test]
+    fn batch_skips_incompatible_pairs() {
+        let mut state = CanvasState { next_id: 10, ..Default::default() };
+        for (id, kind) in [(1, Concept), (2, Visual), (3, Research)] { state.nodes.insert(id, crate::Node::new(id, Default::default(), kind.default_data())); }
+        let rejected = state.add_edges(&[(1, 3), (2, 3), (3, 1)]);
+        assert_eq!(rejected, vec![(2, 3), (3, 1)]);
+        assert_eq!(state.edges.iter().map(|e| (e.from, e.to)).collect::<Vec<_>>(), vec![(1, 3)]);
+    }
+}
@@ -0,0 +1,24 @@
+use crate::CanvasState;
+use eframe::egui::Vec2;
+
+/// Read-only walkthrough of the pipeline, one node per step.
+pub struct Presentation {
+    pub steps: Vec<u64>,
+    pub index: usize,
+    /// Camera offset and zoom to return to when the presentation ends.
+    pub restore: (Vec2, f32),
+}
+
+impl Presentation {
+    pub fn current(&self) -> Option<u64> { self.steps.get(self.index).copied() }
+}
+
+impl CanvasState {
+    /// Parents before children, lowest id first among nodes that are ready, so separate components interleave by id.
+    /// Nodes left waiting on a cycle follow in id order.
+    pub fn presentation_order(&self) -> Vec<u64> {
+        let (mut order, stuck) = self.parents_first(&self.nodes.keys().copied().collect());
+        order.extend(stuck);
+        order
+    }
+}
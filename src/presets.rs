@@ -0,0 +1,160 @@
+use crate::history::Command;
+use crate::refresh::RefreshInterval;
+use crate::{CanvasState, Edge, Node, NodeData, NodeKind, StoryBoardApp};
+use eframe::egui::{self, Pos2, Vec2};
+use std::collections::HashMap;
+
+/// eframe storage key of the templates saved from selections.
+pub const TEMPLATES_KEY: &str = "storyboard_templates";
+
+/// A reusable piece of pipeline: nodes with what was typed into them but no results, positioned around (0, 0), and the edges
+/// between them.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct PipelineTemplate {
+    pub name: String,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl PipelineTemplate {
+    /// Copies `nodes` and the edges running between them, dropping results and centering the group on (0, 0).
+    pub fn capture(name: impl Into<String>, nodes: &[&Node], edges: &[Edge]) -> Self {
+        let center = nodes.iter().map(|n| n.bounds()).reduce(|a, b| a.union(b)).map_or(Vec2::ZERO, |r| r.center().to_vec2());
+        let nodes: Vec<Node> = nodes.iter().map(|n| {
+            let mut node = Node::new(n.id, n.position - center, n.data.clone());
+            node.data.clear_result();
+            if let NodeData::Visual { image_seed, request_seeds, .. } = &mut node.data { *image_seed = None; request_seeds.clear(); }
+            if let NodeData::Transcribe { file_name, audio, .. } = &mut node.data { file_name.clear(); *audio = None; }
+            Node { size: n.size, collapsed: n.collapsed, expanded_size: n.expanded_size, title: n.title.clone(), pinned: n.pinned, auto_size: n.auto_size, raw_markdown: n.raw_markdown, ..node }
+        }).collect();
+        let edges = edges.iter().filter(|e| nodes.iter().any(|n| n.id == e.from) && nodes.iter().any(|n| n.id == e.to)).cloned().collect();
+        Self { name: name.into(), nodes, edges }
+    }
+}
+
+/// Templates that ship with the app; the first is the scene a new session opens with.
+pub fn builtin_templates() -> Vec<PipelineTemplate> {
+    let concept = |id, x, y, text: &str| Node::new(id, Pos2::new(x, y), NodeData::Concept { text: text.to_string() });
+    let ai = |id, x, y, prompt: &str| Node::new(id, Pos2::new(x, y), NodeData::AgnosticAI { model: "google/gemini-flash-1.5".to_string(), prompt: prompt.to_string(), result: None, is_loading: false, auto_run: false, provider: Default::default() });
+    let visual = |id, x, y, prompt: &str| {
+        let mut data = NodeKind::Visual.default_data();
+        if let NodeData::Visual { prompt: p, .. } = &mut data { *p = prompt.to_string(); }
+        Node::new(id, Pos2::new(x, y), data)
+    };
+    let edge = |id, from, to, label: Option<&str>| Edge { id, from, to, label: label.map(str::to_string), role: None };
+    let demo = [
+        concept(1, -450.0, 0.0, "Mars Colony Documentary"),
+        Node::new(2, Pos2::new(-150.0, -150.0), NodeData::YouComResearch { query: "Mars colony life".to_string(), result: None, is_loading: false, refresh: RefreshInterval::Off }),
+        ai(3, 150.0, -150.0, "Write script based on Mars research"),
+        visual(4, 450.0, 0.0, "Mars base interior"),
+        Node::new(5, Pos2::new(0.0, 250.0), NodeData::FoxitExport { status: "Ready".to_string(), is_loading: false, include_everything: false }),
+    ];
+    let demo_edges = [edge(6, 1, 2, None), edge(7, 2, 3, None), edge(8, 3, 4, Some("script draft")), edge(9, 3, 5, None)];
+    let chain = [concept(1, -350.0, 0.0, "A lighthouse keeper's last night"), ai(2, -50.0, 0.0, "Describe the key scene of this story in one vivid paragraph"), visual(3, 300.0, 0.0, "")];
+    let chain_edges = [edge(4, 1, 2, None), edge(5, 2, 3, None)];
+    vec![
+        PipelineTemplate::capture("Mars Colony Documentary", &demo.iter().collect::<Vec<_>>(), &demo_edges),
+        PipelineTemplate::capture("Concept → AI → Visual", &chain.iter().collect::<Vec<_>>(), &chain_edges),
+    ]
+}
+
+impl CanvasState {
+    /// Adds a copy of `template` centered on `center` as one undo step, with fresh ids, and selects the new nodes.
+    pub fn instantiate(&mut self, template: &PipelineTemplate, center: Pos2) -> Vec<u64> {
+        let mut ids = HashMap::new();
+        let mut cmds = Vec::new();
+        for node in &template.nodes {
+            let id = self.next_id;
+            self.next_id += 1;
+            ids.insert(node.id, id);
+            cmds.push(Command::AddNode(Node { id, position: center + node.position.to_vec2(), selected: true, ..node.clone() }));
+        }
+        for edge in &template.edges {
+            let (Some(&from), Some(&to)) = (ids.get(&edge.from), ids.get(&edge.to)) else { continue };
+            cmds.push(Command::AddEdge(Edge { id: self.next_id, from, to, ..edge.clone() }));
+            self.next_id += 1;
+        }
+        if cmds.is_empty() { return Vec::new(); }
+        for node in self.nodes.values_mut() { node.selected = false; }
+        self.execute(Command::Batch(cmds));
+        let mut added: Vec<u64> = ids.into_values().collect();
+        added.sort();
+        for &id in &added { self.bring_to_front(id); }
+        added
+    }
+}
+
+impl StoryBoardApp {
+    /// Every template the sidebar offers: the built-in ones, then those saved from selections.
+    pub(crate) fn all_templates(&self) -> Vec<PipelineTemplate> {
+        builtin_templates().into_iter().chain(self.templates.iter().cloned()).collect()
+    }
+
+    /// "Templates" sidebar section: one button per template, added at the camera center, and saving the selection as a new one.
+    pub(crate) fn templates_section(&mut self, ui: &mut egui::Ui) {
+        let builtin = builtin_templates().len();
+        let (mut add, mut delete) = (None, None);
+        egui::CollapsingHeader::new("Templates").id_salt("templates").show(ui, |ui| {
+            for (index, template) in self.all_templates().iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.button(format!("📋 {}", template.name)).on_hover_text(format!("Add these {} nodes at the view center", template.nodes.len())).clicked() { add = Some(index); }
+                    if index >= builtin && ui.small_button("🗑").on_hover_text("Delete template").clicked() { delete = Some(index - builtin); }
+                });
+            }
+            let selected = self.state.selected_ids();
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.template_name).hint_text("Template name").desired_width(110.0));
+                let name = self.template_name.trim().to_string();
+                let save = ui.add_enabled(!selected.is_empty() && !name.is_empty(), egui::Button::new("💾 Save selection as template"));
+                if save.on_hover_text("Keeps the selected nodes' texts and prompts and the links between them, not their results").clicked() {
+                    let nodes: Vec<&Node> = selected.iter().filter_map(|id| self.state.nodes.get(id)).collect();
+                    let template = PipelineTemplate::capture(name.clone(), &nodes, &self.state.edges);
+                    self.templates.retain(|t| t.name != name);
+                    self.templates.push(template);
+                    self.template_name.clear();
+                    self.toast(format!("Saved template \"{}\"", name));
+                }
+            });
+        });
+        if let Some(index) = delete { self.templates.remove(index); }
+        if let Some(template) = add.and_then(|index| self.all_templates().into_iter().nth(index)) {
+            self.state.instantiate(&template, self.state.camera_offset.to_pos2());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captured_templates_keep_prompts_and_inner_links_but_not_results() {
+        let mut state = CanvasState::default();
+        let mut research = NodeKind::Research.default_data();
+        if let NodeData::YouComResearch { query, result, .. } = &mut research { *query = "tides".to_string(); *result = Some("found".to_string()); }
+        state.nodes.insert(1, Node::new(1, Pos2::new(0.0, 0.0), research));
+        state.nodes.insert(2, Node::new(2, Pos2::new(400.0, 100.0), NodeKind::AgnosticAI.default_data()));
+        state.nodes.insert(3, Node::new(3, Pos2::new(800.0, 0.0), NodeKind::Visual.default_data()));
+        state.edges = vec![Edge { id: 10, from: 1, to: 2, label: Some("notes".to_string()), role: None }, Edge { id: 11, from: 2, to: 3, label: None, role: None }];
+        let template = PipelineTemplate::capture("pair", &[&state.nodes[&1], &state.nodes[&2]], &state.edges);
+        assert_eq!(template.edges.len(), 1, "the link out to the unselected node is left behind");
+        assert!(matches!(&template.nodes[0].data, NodeData::YouComResearch { query, result: None, .. } if query == "tides"));
+        assert_eq!(template.nodes[1].position - template.nodes[0].position, Vec2::new(400.0, 100.0));
+    }
+
+    #[test]
+    fn instances_get_fresh_ids_around_the_given_center() {
+        let template = &builtin_templates()[1];
+        let mut state = CanvasState { next_id: 50, ..Default::default() };
+        let first = state.instantiate(template, Pos2::new(1000.0, 0.0));
+        let second = state.instantiate(template, Pos2::new(1000.0, 0.0));
+        assert_eq!(first.len(), 3);
+        assert!(first.iter().all(|id| !second.contains(id)));
+        assert_eq!(state.edges.len(), 4);
+        assert!(state.edges.iter().all(|e| state.nodes.contains_key(&e.from) && state.nodes.contains_key(&e.to)));
+        assert_eq!(state.nodes[&second[1]].position - state.nodes[&second[0]].position, template.nodes[1].position - template.nodes[0].position);
+        assert_eq!(state.selected_ids(), second);
+        state.undo();
+        assert_eq!(state.nodes.len(), 3);
+    }
+}
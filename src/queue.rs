@@ -0,0 +1,87 @@
+use crate::runlog::LogEntry;
+use crate::{AppMessage, Instant, NodeData, NodeKind, StoryBoardApp, Variant};
+use eframe::egui;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+
+/// What to make of a response once it lands; runs on the fetch thread.
+pub type OnResult = Box<dyn FnOnce(ehttp::Result<ehttp::Response>) -> Option<AppMessage> + Send>;
+
+pub fn default_max_concurrent() -> usize { 2 }
+
+/// A request waiting for a free slot.
+pub struct QueuedRequest {
+    /// Node the answer is for; `None` for requests like the HTML report.
+    pub node: Option<u64>,
+    pub request: ehttp::Request,
+    pub on_result: OnResult,
+    /// `cache::request_key` of node requests; a successful answer is stored under it.
+    pub cache_key: Option<u64>,
+}
+
+/// Spinner for a node whose request is out, or a "queued" label while it waits for a slot.
+pub fn busy_indicator(ui: &mut egui::Ui, queued: bool) {
+    if queued { ui.weak("⏳ Queued").on_hover_text("Waiting for a free request slot"); } else { ui.spinner(); }
+}
+
+impl StoryBoardApp {
+    /// Starts queued requests, oldest first, while fewer than `settings.max_concurrent` are in flight. Requests for nodes deleted
+    /// while they waited are dropped.
+    pub(crate) fn pump_requests(&mut self, ctx: &egui::Context) {
+        while self.in_flight.load(Ordering::Relaxed) < self.settings.max_concurrent.max(1) {
+            let Some(queued) = self.request_queue.pop_front() else { break };
+            let mut logged = None;
+            if let Some(id) = queued.node {
+                let still_waiting = self.request_queue.iter().any(|q| q.node == Some(id));
+                let pipeline = self.pipeline.as_ref().is_some_and(|run| run.order[..run.next].contains(&id));
+                let Some(node) = self.node_mut(id) else { continue };
+                node.queued = still_waiting;
+                node.cached = false;
+                logged = Some((id, node.data.kind(), pipeline));
+            }
+            self.dispatch(queued, logged, ctx.clone());
+        }
+    }
+
+    /// Sends the request; when `logged` names the node it's for, an execution log entry goes to the app ahead of the result.
+    fn dispatch(&self, queued: QueuedRequest, logged: Option<(u64, NodeKind, bool)>, ctx: egui::Context) {
+        let tx = self.http_tx.clone();
+        let in_flight = self.in_flight.clone();
+        let on_result = queued.on_result;
+        let endpoint = queued.request.url.clone();
+        let cache_key = queued.cache_key;
+        let (at, started) = (crate::runlog::now(), Instant::now());
+        in_flight.fetch_add(1, Ordering::Relaxed);
+        ehttp::fetch(queued.request, move |result| {
+            if let Some((node, kind, pipeline)) = logged {
+                let (bytes, error) = match &result {
+                    Ok(r) if r.ok => (r.bytes.len(), None),
+                    Ok(r) => (r.bytes.len(), Some(format!("{} {}", r.status, r.status_text))),
+                    Err(err) => (0, Some(err.clone())),
+                };
+                let _ = tx.send(AppMessage::Logged(LogEntry { at, node, kind, endpoint, duration: started.elapsed(), bytes, error, pipeline, cached: false }));
+            }
+            if let (Some(key), Ok(response)) = (cache_key, &result) { if response.ok { let _ = tx.send(AppMessage::CacheStore(key, response.clone())); } }
+            if let Some(msg) = on_result(result) { let _ = tx.send(msg); }
+            in_flight.fetch_sub(1, Ordering::Relaxed);
+            ctx.request_repaint();
+        });
+    }
+
+    /// "Stop all": drops every queued request, ends the pipeline run and pending retries, and puts the nodes that were waiting back to
+    /// idle. Requests already sent can't be called back and still land.
+    pub(crate) fn stop_all(&mut self) {
+        let waiting: HashSet<u64> = self.request_queue.drain(..).filter_map(|q| q.node).collect();
+        if self.pipeline.is_some() { self.finish_pipeline(Some("stopped".to_string())); }
+        self.retries.clear();
+        for id in waiting {
+            let Some(node) = self.node_mut(id) else { continue };
+            node.queued = false;
+            if let NodeData::Visual { variants, .. } = &mut node.data {
+                // A variant already sent still fills its slot if it answers.
+                for v in variants.iter_mut().filter(|v| matches!(v, Variant::Pending)) { *v = Variant::Failed("Cancelled".to_string()); }
+            }
+            if let Some(flag) = node.data.loading_flag() { *flag = false; }
+        }
+    }
+}
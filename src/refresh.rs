@@ -0,0 +1,99 @@
+use crate::{Instant, NodeData, StoryBoardApp};
+use eframe::egui;
+use std::time::Duration;
+
+/// A gap between frames longer than this means the window wasn't being drawn (a hidden browser tab); countdowns don't run during it.
+const PAUSE_GAP: Duration = Duration::from_secs(5);
+
+/// How often a Research node re-runs its query on its own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum RefreshInterval {
+    #[default]
+    Off,
+    FiveMinutes,
+    HalfHour,
+    Hour,
+}
+
+impl RefreshInterval {
+    pub const ALL: [RefreshInterval; 4] = [Self::Off, Self::FiveMinutes, Self::HalfHour, Self::Hour];
+
+    pub fn label(self) -> &'static str {
+        match self { Self::Off => "Off", Self::FiveMinutes => "5 min", Self::HalfHour => "30 min", Self::Hour => "1 hour" }
+    }
+
+    pub fn duration(self) -> Option<Duration> {
+        match self { Self::Off => None, Self::FiveMinutes => Some(Duration::from_secs(300)), Self::HalfHour => Some(Duration::from_secs(1800)), Self::Hour => Some(Duration::from_secs(3600)) }
+    }
+}
+
+/// "4:05" for a countdown.
+pub fn countdown(left: Duration) -> String {
+    let secs = left.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn tab_hidden() -> bool {
+    web_sys::window().and_then(|w| w.document()).is_some_and(|d| d.hidden())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn tab_hidden() -> bool { false }
+
+/// Refresh row on a Research node: the interval picker and the time left. Returns true when the interval changed.
+pub fn refresh_row(ui: &mut egui::Ui, id: u64, interval: &mut RefreshInterval, left: Option<Duration>) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(("refresh", id)).selected_text(format!("🔄 {}", interval.label())).width(90.0).show_ui(ui, |ui| {
+            for option in RefreshInterval::ALL { changed |= ui.selectable_value(interval, option, option.label()).changed(); }
+        }).response.on_hover_text("Re-run the query on a schedule");
+        if let Some(left) = left.filter(|_| *interval != RefreshInterval::Off) { ui.weak(format!("next in {}", countdown(left))); }
+    });
+    changed
+}
+
+impl StoryBoardApp {
+    /// Restarts node `id`'s countdown from its full interval, or drops it when refreshing is off.
+    pub(crate) fn reschedule_refresh(&mut self, id: u64) {
+        let every = match self.state.nodes.get(&id).map(|n| &n.data) { Some(NodeData::YouComResearch { refresh, .. }) => refresh.duration(), _ => None };
+        match every {
+            Some(every) => { self.refresh_due.insert(id, Instant::now() + every); }
+            None => { self.refresh_due.remove(&id); }
+        }
+    }
+
+    /// Re-runs Research nodes on the active board whose refresh interval has elapsed, unless they're already busy. Countdowns stand
+    /// still while the tab is hidden.
+    pub(crate) fn step_refresh(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        let gap = now - std::mem::replace(&mut self.refresh_clock, now);
+        if tab_hidden() || gap > PAUSE_GAP { for due in self.refresh_due.values_mut() { *due += gap; } }
+        let scheduled: Vec<(u64, Duration, bool)> = self.state.nodes.values().filter_map(|n| match &n.data {
+            NodeData::YouComResearch { refresh, is_loading, .. } => refresh.duration().map(|every| (n.id, every, *is_loading || n.queued)),
+            _ => None,
+        }).collect();
+        self.refresh_due.retain(|id, _| scheduled.iter().any(|(s, _, _)| s == id));
+        for (id, every, busy) in scheduled {
+            let due = *self.refresh_due.entry(id).or_insert(now + every);
+            if due > now || busy || tab_hidden() { continue; }
+            self.refresh_due.insert(id, now + every);
+            // A cached answer would defeat the point of re-querying.
+            self.force_refresh(id, ctx);
+        }
+        if !self.refresh_due.is_empty() { ctx.request_repaint_after(Duration::from_secs(1)); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_and_countdown() {
+        assert_eq!(RefreshInterval::Off.duration(), None);
+        assert_eq!(RefreshInterval::HalfHour.duration(), Some(Duration::from_secs(1800)));
+        assert_eq!(countdown(Duration::from_secs(245)), "4:05");
+        assert_eq!(countdown(Duration::from_secs(3600)), "60:00");
+    }
+}
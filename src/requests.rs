@@ -0,0 +1,141 @@
+use crate::{branch, script, seeds, translate, NodeData, StoryBoardApp};
+use base64::{engine::general_purpose, Engine as _};
+use eframe::egui;
+use serde_json::{json, Value};
+
+/// Image and audio strings longer than this are shortened in the preview window.
+const PREVIEW_IMAGE_CHARS: usize = 80;
+
+pub fn research_body(query: &str) -> Value {
+    json!({"query": query})
+}
+
+/// `prompt` is sent as given; render templates first.
+pub fn agnostic_ai_body(model: &str, prompt: &str) -> Value {
+    json!({"model": model, "prompt": prompt})
+}
+
+/// One image request; `image` is only sent for image-to-image.
+pub fn visualize_body(prompt: &str, seed: Option<u64>, image: Option<&[u8]>) -> Value {
+    let mut body = json!({"prompt": prompt, "image": image.map(|bytes| general_purpose::STANDARD.encode(bytes))});
+    if let Some(seed) = seed { body["seed"] = seed.into(); }
+    body
+}
+
+pub fn foxit_body(all_text: &str) -> Value {
+    json!({"all_node_text": all_text})
+}
+
+pub fn tts_body(voice: &str, text: &str) -> Value {
+    json!({"voice": voice, "text": text})
+}
+
+pub fn fetch_url_body(url: &str) -> Value {
+    json!({"url": url})
+}
+
+/// The recording goes as base64; the file name tells Whisper its format.
+pub fn transcribe_body(file_name: &str, audio: &[u8]) -> Value {
+    json!({"file_name": file_name, "audio": general_purpose::STANDARD.encode(audio)})
+}
+
+/// A request as node `id` would send it now: endpoint, JSON body, and how many copies go out (Visual variants differ only by seed).
+pub struct PlannedRequest {
+    pub endpoint: &'static str,
+    pub body: Value,
+    pub copies: usize,
+    /// The seed shown is an example; each send picks its own.
+    pub random_seed: bool,
+}
+
+impl StoryBoardApp {
+    /// Builds node `id`'s request from its current inputs without sending anything; `None` for nodes that don't call the server.
+    pub(crate) fn plan_request(&self, id: u64) -> Option<PlannedRequest> {
+        let node = self.state.nodes.get(&id)?;
+        let (endpoint, body, copies, random_seed) = match &node.data {
+            NodeData::YouComResearch { query, .. } => ("/api/research", research_body(query), 1, false),
+            NodeData::AgnosticAI { model, prompt, provider, .. } => (provider.endpoint(), agnostic_ai_body(model, &self.state.render_prompt(id, prompt)), 1, false),
+            NodeData::Visual { prompt, variant_count, use_parent_image, seed, .. } => {
+                let copies = (*variant_count).clamp(1, crate::MAX_VARIANTS);
+                let image = self.state.img2img_source(id).filter(|_| *use_parent_image);
+                ("/api/visualize", visualize_body(&self.state.render_prompt(id, prompt), seeds::request_seeds(*seed, 1).first().copied(), image), copies, seed.is_none())
+            }
+            NodeData::FoxitExport { .. } => ("/api/foxit", foxit_body(&self.export_text(id)), 1, false),
+            NodeData::Audio { voice, text, .. } => ("/api/tts", tts_body(voice, text), 1, false),
+            NodeData::Branch { condition, use_ai: true, model, .. } => ("/api/agnostic-ai", agnostic_ai_body(model, &branch::ai_check_prompt(condition, &self.state.parent_output(id).unwrap_or_default())), 1, false),
+            NodeData::WebFetch { url, .. } => ("/api/fetch-url", fetch_url_body(url.trim()), 1, false),
+            NodeData::Translate { target_lang, text, .. } => ("/api/agnostic-ai", agnostic_ai_body(translate::TRANSLATE_MODEL, &translate::translate_prompt(target_lang, text)), 1, false),
+            NodeData::Script { .. } => ("/api/agnostic-ai", agnostic_ai_body(script::PARSE_MODEL, &script::parse_prompt(&self.state.parent_output(id)?)), 1, false),
+            NodeData::Transcribe { file_name, audio: Some(audio), .. } => ("/api/transcribe", transcribe_body(file_name, audio), 1, false),
+            NodeData::Branch { .. } | NodeData::Transcribe { .. } => return None,
+            NodeData::Concept { .. } | NodeData::Merge { .. } | NodeData::Compare { .. } | NodeData::Select { .. } | NodeData::Note { .. } | NodeData::Character { .. } | NodeData::Transform { .. } | NodeData::Frame { .. } | NodeData::MarkdownView { .. } => return None,
+        };
+        Some(PlannedRequest { endpoint, body, copies, random_seed })
+    }
+
+    /// The "Preview request" window: the exact body node `request_preview` would POST, with a button to send it.
+    pub(crate) fn draw_request_preview(&mut self, ctx: &egui::Context) {
+        let Some(id) = self.request_preview else { return };
+        let Some(plan) = self.plan_request(id) else { self.request_preview = None; return };
+        let bytes = serde_json::to_vec(&plan.body).map_or(0, |b| b.len());
+        let mut shown = plan.body.clone();
+        for field in ["image", "audio"] {
+            if let Some(value) = shown.get_mut(field).filter(|v| v.as_str().is_some_and(|s| s.len() > PREVIEW_IMAGE_CHARS)) {
+                let full = value.as_str().unwrap_or_default();
+                *value = format!("{}… ({} characters of base64)", &full[..PREVIEW_IMAGE_CHARS], full.len()).into();
+            }
+        }
+        let text = serde_json::to_string_pretty(&shown).unwrap_or_default();
+        let (mut open, mut send) = (true, false);
+        let loading = self.state.nodes.get(&id).is_some_and(|n| n.data.is_loading());
+        egui::Window::new(format!("🔍 Request from node {}", id)).open(&mut open).collapsible(false).default_width(420.0).show(ctx, |ui| {
+            ui.horizontal(|ui| { ui.strong("POST"); ui.monospace(plan.endpoint); });
+            let seeding = match (plan.copies, plan.random_seed) {
+                (1, false) => String::new(),
+                (1, true) => ", with a new random seed on every send".to_string(),
+                (n, false) => format!(", sent {} times with the seed counting up", n),
+                (n, true) => format!(", sent {} times with a random seed each", n),
+            };
+            ui.weak(format!("{} bytes{}", bytes, seeding));
+            egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut text.as_str()).code_editor().desired_width(f32::INFINITY));
+            });
+            ui.separator();
+            if ui.add_enabled(!loading, egui::Button::new("📤 Send now")).clicked() { send = true; }
+        });
+        if send { self.retry_now(id, ctx); }
+        if send || !open { self.request_preview = None; }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{templates, CanvasState, Edge, Node, NodeKind};
+
+    #[test]
+    fn bodies_match_the_server_fields() {
+        assert_eq!(research_body("mars"), json!({"query": "mars"}));
+        assert_eq!(agnostic_ai_body("m", "p"), json!({"model": "m", "prompt": "p"}));
+        assert_eq!(foxit_body("all"), json!({"all_node_text": "all"}));
+        assert_eq!(tts_body("nova", "Fade in."), json!({"voice": "nova", "text": "Fade in."}));
+        assert_eq!(fetch_url_body("https://example.com"), json!({"url": "https://example.com"}));
+        assert_eq!(transcribe_body("memo.m4a", b"png"), json!({"file_name": "memo.m4a", "audio": "cG5n"}));
+    }
+
+    #[test]
+    fn visualize_sends_seed_and_image_only_when_given() {
+        assert_eq!(visualize_body("a cat", None, None), json!({"prompt": "a cat", "image": null}));
+        assert_eq!(visualize_body("a cat", Some(7), Some(b"png")), json!({"prompt": "a cat", "seed": 7, "image": "cG5n"}));
+    }
+
+    #[test]
+    fn ai_body_carries_the_rendered_template() {
+        let mut state = CanvasState::default();
+        state.nodes.insert(1, Node::new(1, Default::default(), NodeData::Concept { text: "Mars".to_string() }));
+        state.nodes.insert(2, Node::new(2, Default::default(), NodeKind::AgnosticAI.default_data()));
+        state.edges.push(Edge { id: 3, from: 1, to: 2, label: None, role: None });
+        assert!(templates::has_placeholders("Story about {{concept}}"));
+        assert_eq!(agnostic_ai_body("m", &state.render_prompt(2, "Story about {{concept}}")), json!({"model": "m", "prompt": "Story about Mars"}));
+    }
+}
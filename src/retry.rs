@@ -0,0 +1,70 @@
+use crate::{Instant, NodeData, StoryBoardApp};
+use eframe::egui;
+use std::time::Duration;
+
+/// Automatic retries made after a failure before the node is left showing its error.
+pub const MAX_RETRIES: u32 = 3;
+
+/// Automatic retries of one failed node.
+pub struct Retry {
+    /// Retries made or scheduled so far, 1-based.
+    pub attempt: u32,
+    /// When the next attempt fires; `None` while that attempt is in flight.
+    pub due: Option<Instant>,
+}
+
+impl StoryBoardApp {
+    /// Starts node `id`'s request from its current inputs, whichever kind of node it is.
+    pub(crate) fn run_node(&mut self, id: u64, ctx: &egui::Context) {
+        let Some(node) = self.node_mut(id) else { return };
+        let Some(flag) = node.data.loading_flag() else { return };
+        *flag = true;
+        node.error = None;
+        node.skipped = false;
+        match node.data.clone() {
+            NodeData::YouComResearch { query, .. } => self.trigger_research(id, query, ctx.clone()),
+            NodeData::AgnosticAI { model, prompt, .. } => self.trigger_agnostic_ai(id, model, prompt, ctx.clone()),
+            NodeData::Visual { prompt, .. } => self.trigger_visualize(id, prompt, ctx.clone()),
+            NodeData::Branch { .. } => self.evaluate_branch(id, ctx),
+            NodeData::Audio { voice, text, .. } => self.trigger_tts(id, voice, text, ctx.clone()),
+            NodeData::Script { .. } => self.parse_script(id, ctx),
+            NodeData::WebFetch { url, .. } => self.trigger_fetch(id, url.trim().to_string(), ctx.clone()),
+            NodeData::Translate { target_lang, text, .. } => self.trigger_translate(id, target_lang, text, ctx.clone()),
+            NodeData::Transcribe { file_name, audio, .. } => self.trigger_transcribe(id, file_name, audio.unwrap_or_default(), ctx.clone()),
+            _ => self.trigger_foxit(id, self.export_text(id), ctx.clone()),
+        }
+    }
+
+    /// The "Retry" button: starts over with a fresh set of automatic attempts.
+    pub(crate) fn retry_now(&mut self, id: u64, ctx: &egui::Context) {
+        self.retries.remove(&id);
+        self.run_node(id, ctx);
+    }
+
+    /// Called when node `id` fails: schedules the next automatic attempt after 1s, 2s, then 4s, or gives up.
+    pub(crate) fn schedule_retry(&mut self, id: u64) {
+        let attempt = self.retries.get(&id).map_or(0, |r| r.attempt);
+        if !self.settings.auto_retry || attempt >= MAX_RETRIES { self.retries.remove(&id); return; }
+        self.retries.insert(id, Retry { attempt: attempt + 1, due: Some(Instant::now() + Duration::from_secs(1 << attempt)) });
+    }
+
+    /// Fires retries whose backoff has elapsed and wakes the UI up in time for the next one.
+    pub(crate) fn step_retries(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        let mut next_wake: Option<Duration> = None;
+        for (&id, retry) in &self.retries {
+            match retry.due {
+                Some(at) if at <= now => due.push(id),
+                Some(at) => next_wake = Some(next_wake.map_or(at - now, |w| w.min(at - now))),
+                None => {}
+            }
+        }
+        for id in due {
+            if self.node_mut(id).is_none() { self.retries.remove(&id); continue; }
+            if let Some(retry) = self.retries.get_mut(&id) { retry.due = None; }
+            self.run_node(id, ctx);
+        }
+        if let Some(wait) = next_wake { ctx.request_repaint_after(wait); }
+    }
+}
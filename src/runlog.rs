@@ -0,0 +1,124 @@
+use crate::{NodeKind, StoryBoardApp};
+use eframe::egui;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(target_arch = "wasm32")]
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Oldest entries are dropped past this many.
+const MAX_LOG_ENTRIES: usize = 500;
+
+/// One request made for a node, as it finished.
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    /// When the request was sent.
+    pub at: SystemTime,
+    pub node: u64,
+    pub kind: NodeKind,
+    pub endpoint: String,
+    pub duration: Duration,
+    /// Size of the response body; 0 when nothing came back.
+    pub bytes: usize,
+    /// The error text for failed requests.
+    pub error: Option<String>,
+    /// Started by "Run Pipeline" rather than a button on the node.
+    pub pipeline: bool,
+    /// Answered from the response cache without going to the server.
+    pub cached: bool,
+}
+
+/// Wall-clock now, for stamping entries from the fetch thread.
+pub fn now() -> SystemTime { SystemTime::now() }
+
+/// Wall-clock time of day as "HH:MM:SS", in UTC.
+pub fn clock(at: SystemTime) -> String {
+    let secs = at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) % 86_400;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+impl LogEntry {
+    /// The entry as one line of "Copy log" text.
+    pub fn line(&self) -> String {
+        let outcome = match &self.error { Some(err) => format!("error: {}", err), None => "ok".to_string() };
+        format!("{} UTC  #{} {}  POST {}  {} ms  {} bytes  {}{}{}", clock(self.at), self.node, self.kind.title(), self.endpoint, self.duration.as_millis(), self.bytes, outcome, if self.cached { "  (cached)" } else { "" }, if self.pipeline { "  (pipeline)" } else { "" })
+    }
+}
+
+/// Adds `entry`, dropping the oldest past `MAX_LOG_ENTRIES`.
+pub fn push_entry(log: &mut Vec<LogEntry>, entry: LogEntry) {
+    log.push(entry);
+    if log.len() > MAX_LOG_ENTRIES { log.drain(..log.len() - MAX_LOG_ENTRIES); }
+}
+
+impl StoryBoardApp {
+    /// Bottom panel listing every node request made this session, newest last. Clicking a row selects and centers its node.
+    pub(crate) fn draw_run_log(&mut self, ctx: &egui::Context) {
+        let mut focus = None;
+        let collapsed = self.settings.log_collapsed;
+        egui::TopBottomPanel::bottom("run_log").resizable(!collapsed).default_height(160.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.small_button(if collapsed { "▸" } else { "▾" }).on_hover_text(if collapsed { "Show the log" } else { "Hide the log" }).clicked() { self.settings.log_collapsed = !collapsed; }
+                ui.strong("📜 Execution log");
+                let failed = self.run_log.iter().filter(|e| e.error.is_some()).count();
+                ui.weak(if failed > 0 { format!("{} requests, {} failed", self.run_log.len(), failed) } else { format!("{} requests", self.run_log.len()) });
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.add_enabled(!self.run_log.is_empty(), egui::Button::new("🗑 Clear")).clicked() { self.run_log.clear(); }
+                    if ui.add_enabled(!self.run_log.is_empty(), egui::Button::new("📋 Copy log")).clicked() {
+                        ctx.copy_text(self.run_log.iter().map(LogEntry::line).collect::<Vec<_>>().join("\n"));
+                    }
+                });
+            });
+            if collapsed { return; }
+            ui.separator();
+            if self.run_log.is_empty() { ui.weak("Requests made by nodes and pipeline runs show up here."); return; }
+            egui::ScrollArea::vertical().auto_shrink([false, false]).stick_to_bottom(true).show(ui, |ui| {
+                egui::Grid::new("run_log_grid").striped(true).num_columns(7).show(ui, |ui| {
+                    for header in ["Time (UTC)", "Node", "Endpoint", "Duration", "Size", "Result", ""] { ui.strong(header); }
+                    ui.end_row();
+                    for entry in &self.run_log {
+                        ui.monospace(clock(entry.at));
+                        if ui.link(format!("{} #{} {}", entry.kind.icon(), entry.node, entry.kind.short_label())).on_hover_text("Select and center this node").clicked() { focus = Some(entry.node); }
+                        ui.monospace(&entry.endpoint);
+                        ui.label(format!("{} ms", entry.duration.as_millis()));
+                        ui.label(format!("{} bytes", entry.bytes));
+                        match &entry.error {
+                            Some(err) => { ui.colored_label(ui.visuals().error_fg_color, format!("✖ {}", err)); }
+                            None => { ui.label("✔ ok"); }
+                        }
+                        ui.weak([entry.cached.then_some("cached"), entry.pipeline.then_some("pipeline")].into_iter().flatten().collect::<Vec<_>>().join(", "));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+        if let Some(id) = focus {
+            if self.state.nodes.contains_key(&id) { self.state.select_only(id); self.focus_camera_on(id); } else { self.toast(format!("Node #{} isn't on this board", id)); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(node: u64, error: Option<&str>) -> LogEntry {
+        LogEntry { at: UNIX_EPOCH + Duration::from_secs(86_400 + 3 * 3600 + 4 * 60 + 5), node, kind: NodeKind::Research, endpoint: "/api/research".to_string(), duration: Duration::from_millis(1250), bytes: 42, error: error.map(str::to_string), pipeline: false, cached: false }
+    }
+
+    #[test]
+    fn copied_lines_carry_every_field() {
+        assert_eq!(entry(3, None).line(), "03:04:05 UTC  #3 You.com Research  POST /api/research  1250 ms  42 bytes  ok");
+        assert!(LogEntry { pipeline: true, ..entry(3, Some("502 Bad Gateway")) }.line().ends_with("error: 502 Bad Gateway  (pipeline)"));
+    }
+
+    #[test]
+    fn keeps_the_newest_entries() {
+        let mut log = Vec::new();
+        for node in 0..MAX_LOG_ENTRIES as u64 + 5 { push_entry(&mut log, entry(node, None)); }
+        assert_eq!(log.len(), MAX_LOG_ENTRIES);
+        assert_eq!(log[0].node, 5);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{requests, NodeData, StoryBoardApp};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// Model "Parse from parent" asks to turn text into a scene.
+pub const PARSE_MODEL: &str = "google/gemini-flash-1.5";
+
+/// One spoken line of a Script node.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct DialogueLine {
+    pub character: String,
+    pub line: String,
+}
+
+/// A scene as the AI sends it back; missing fields come out empty.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    pub heading: String,
+    pub location: String,
+    pub time_of_day: String,
+    pub action: String,
+    pub dialogue: Vec<DialogueLine>,
+}
+
+/// Prompt asking the model to restate `text` as a `Scene` in JSON.
+pub fn parse_prompt(text: &str) -> String {
+    format!("Turn the text below into one screenplay scene. Reply with only a JSON object, no commentary, shaped like {{\"heading\": \"INT. or EXT.\", \"location\": \"...\", \"time_of_day\": \"DAY or NIGHT\", \"action\": \"what we see\", \"dialogue\": [{{\"character\": \"NAME\", \"line\": \"what they say\"}}]}}.\n\n---\n{}", text)
+}
+
+/// Reads the model's answer as a `Scene`, tolerating a markdown code fence or chatter around the JSON object.
+pub fn parse_scene(answer: &str) -> Result<Scene, String> {
+    let (Some(start), Some(end)) = (answer.find('{'), answer.rfind('}')) else { return Err("The AI didn't answer with a JSON scene".to_string()) };
+    if end < start { return Err("The AI didn't answer with a JSON scene".to_string()); }
+    let scene: Scene = serde_json::from_str(&answer[start..=end]).map_err(|e| format!("The AI's scene wasn't valid JSON: {}", e))?;
+    if scene == Scene::default() { return Err("The AI's scene came back empty".to_string()); }
+    Ok(scene)
+}
+
+/// Slugline of a scene, e.g. "INT. LIGHTHOUSE - NIGHT"; blank parts are left out.
+pub fn slugline(heading: &str, location: &str, time_of_day: &str) -> String {
+    let place = [heading.trim(), location.trim()].into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ");
+    [place.as_str(), time_of_day.trim()].into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" - ").to_uppercase()
+}
+
+/// The scene laid out as a screenplay: slugline, action, then each character cue indented over their line.
+pub fn screenplay(heading: &str, location: &str, time_of_day: &str, action: &str, dialogue: &[DialogueLine]) -> String {
+    let mut blocks = vec![slugline(heading, location, time_of_day), action.trim().to_string()];
+    for line in dialogue.iter().filter(|d| !d.line.trim().is_empty()) {
+        let speech = line.line.trim().lines().map(|l| format!("          {}", l.trim())).collect::<Vec<_>>().join("\n");
+        blocks.push(format!("                    {}\n{}", line.character.trim().to_uppercase(), speech));
+    }
+    blocks.retain(|b| !b.trim().is_empty());
+    blocks.join("\n\n")
+}
+
+impl NodeData {
+    /// Fills a Script node from the AI's answer to "Parse from parent", leaving it as it was when the answer isn't a scene.
+    pub fn apply_scene(&mut self, answer: &str) -> Result<(), String> {
+        let Self::Script { heading, location, time_of_day, action, dialogue, is_loading, .. } = self else { return Ok(()) };
+        *is_loading = false;
+        let scene = parse_scene(answer)?;
+        (*heading, *location, *time_of_day, *action, *dialogue) = (scene.heading, scene.location, scene.time_of_day, scene.action, scene.dialogue);
+        Ok(())
+    }
+}
+
+/// Slugline fields, action and the dialogue rows of a Script node, with a row added or removed from the buttons. Returns true when
+/// anything was edited.
+pub fn script_ui(ui: &mut egui::Ui, id: u64, heading: &mut String, location: &mut String, time_of_day: &mut String, action: &mut String, dialogue: &mut Vec<DialogueLine>) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui.add(egui::TextEdit::singleline(heading).hint_text("INT.").desired_width(40.0)).changed();
+        changed |= ui.add(egui::TextEdit::singleline(location).hint_text("Location").desired_width(110.0)).changed();
+        changed |= ui.add(egui::TextEdit::singleline(time_of_day).hint_text("DAY").desired_width(50.0)).changed();
+    });
+    changed |= ui.add(egui::TextEdit::multiline(action).hint_text("Action").desired_rows(2).desired_width(f32::INFINITY)).changed();
+    let mut remove = None;
+    for (index, row) in dialogue.iter_mut().enumerate() {
+        ui.push_id((id, index), |ui| {
+            ui.horizontal(|ui| {
+                changed |= ui.add(egui::TextEdit::singleline(&mut row.character).hint_text("Character").desired_width(70.0)).changed();
+                changed |= ui.add(egui::TextEdit::singleline(&mut row.line).hint_text("Line").desired_width(120.0)).changed();
+                if ui.small_button("✖").on_hover_text("Remove line").clicked() { remove = Some(index); }
+            });
+        });
+    }
+    if let Some(index) = remove { dialogue.remove(index); changed = true; }
+    if ui.small_button("➕ Dialogue").clicked() { dialogue.push(DialogueLine::default()); changed = true; }
+    changed
+}
+
+impl StoryBoardApp {
+    /// "Parse from parent": asks the model to restate the parents' text as a scene, which lands as a `TextResponse`.
+    pub(crate) fn parse_script(&mut self, id: u64, ctx: &egui::Context) {
+        let text = self.state.parent_output(id);
+        let Some(node) = self.state.nodes.get_mut(&id) else { return };
+        let Some(text) = text else {
+            if let Some(flag) = node.data.loading_flag() { *flag = false; }
+            node.error = Some("Link a parent with text to parse a scene from".to_string());
+            return;
+        };
+        let body = requests::agnostic_ai_body(PARSE_MODEL, &parse_prompt(&text));
+        self.post_json(Some(id), "/api/agnostic-ai", body, ctx.clone(), move |result| Some(crate::reply(id, result, |r| crate::AppMessage::TextResponse(id, r.text().unwrap_or_default().to_string()))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scenes_parse_from_fenced_json_and_bad_answers_are_refused() {
+        let answer = "Here you go:\n```json\n{\"heading\": \"INT.\", \"location\": \"Lighthouse\", \"time_of_day\": \"night\", \"action\": \"Waves hit the glass.\", \"dialogue\": [{\"character\": \"Ada\", \"line\": \"Not tonight.\"}]}\n```";
+        let scene = parse_scene(answer).unwrap();
+        assert_eq!(slugline(&scene.heading, &scene.location, &scene.time_of_day), "INT. LIGHTHOUSE - NIGHT");
+        assert_eq!(scene.dialogue, vec![DialogueLine { character: "Ada".to_string(), line: "Not tonight.".to_string() }]);
+        assert!(parse_scene("Sorry, I can't do that").is_err());
+        assert!(parse_scene("{\"heading\": 3}").is_err());
+        assert!(parse_scene("{}").is_err());
+    }
+
+    #[test]
+    fn a_failed_parse_keeps_what_was_typed() {
+        let mut data = crate::NodeKind::Script.default_data();
+        if let NodeData::Script { action, is_loading, .. } = &mut data { *action = "kept".to_string(); *is_loading = true; }
+        assert!(data.apply_scene("not json").is_err());
+        assert!(matches!(&data, NodeData::Script { action, is_loading: false, .. } if action == "kept"));
+        data.apply_scene("{\"location\": \"Pier\", \"dialogue\": [{\"character\": \"bo\", \"line\": \"Go.\"}, {\"character\": \"X\", \"line\": \"\"}]}").unwrap();
+        let NodeData::Script { heading, location, time_of_day, action, dialogue, .. } = &data else { unreachable!() };
+        assert_eq!(screenplay(heading, location, time_of_day, action, dialogue), "PIER\n\n                    BO\n          Go.");
+    }
+}
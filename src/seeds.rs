@@ -0,0 +1,38 @@
+use eframe::egui;
+
+/// A fresh seed; kept to 32 bits since that's what the image services accept.
+pub fn random_seed() -> u64 { rand::random::<u32>() as u64 }
+
+/// Seeds for the `count` requests of one Generate click: the locked seed and the ones after it, or fresh random ones.
+pub fn request_seeds(locked: Option<u64>, count: usize) -> Vec<u64> {
+    (0..count as u64).map(|i| locked.map_or_else(random_seed, |seed| seed.wrapping_add(i))).collect()
+}
+
+/// "Seed" row of a Visual node: tick to lock a seed, 🎲 for a new one, 🔒 to keep the seed of the image on show. Returns whether `seed` changed.
+pub fn seed_row(ui: &mut egui::Ui, seed: &mut Option<u64>, shown: Option<u64>) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        let mut locked = seed.is_some();
+        if ui.checkbox(&mut locked, "Seed").on_hover_text("Lock the seed to reproduce an image; unticked uses a random one each time").changed() {
+            *seed = locked.then(|| shown.unwrap_or_else(random_seed));
+            changed = true;
+        }
+        if let Some(value) = seed.as_mut() { if ui.add(egui::DragValue::new(value)).changed() { changed = true; } }
+        if ui.small_button("🎲").on_hover_text("New random seed").clicked() { *seed = Some(random_seed()); changed = true; }
+        if let Some(used) = shown.filter(|used| *seed != Some(*used)) {
+            if ui.small_button(format!("🔒 {}", used)).on_hover_text("Lock the seed this image was made with").clicked() { *seed = Some(used); changed = true; }
+        }
+    });
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locked_seed_counts_up_across_variants() {
+        assert_eq!(request_seeds(Some(41), 3), vec![41, 42, 43]);
+        assert!(request_seeds(None, 4).iter().all(|&s| s <= u32::MAX as u64));
+    }
+}
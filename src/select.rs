@@ -0,0 +1,122 @@
+use crate::ports::PortKind;
+use crate::{truncate, CanvasState, NodeData};
+use eframe::egui::{self, Key, Modifiers, Vec2};
+use std::collections::HashSet;
+
+/// Largest thumbnail of an image candidate in a Select node.
+const THUMBNAIL: Vec2 = Vec2::new(96.0, 64.0);
+
+impl CanvasState {
+    /// Parents of Select node `id` that produce text or an image, once each in link order.
+    pub fn select_candidates(&self, id: u64) -> Vec<u64> {
+        let mut seen = HashSet::new();
+        self.edges.iter().filter(|e| e.to == id && self.edge_active(e) && seen.insert(e.from)).filter(|e| self.nodes.get(&e.from).is_some_and(|n| n.data.produces_text() || n.data.output_kind() == Some(PortKind::Image))).map(|e| e.from).collect()
+    }
+
+    /// The image node `id` hands downstream: a Visual node's own, or the one a Select node chose.
+    pub fn image_output(&self, id: u64) -> Option<&[u8]> {
+        match self.nodes.get(&id).map(|n| &n.data)? {
+            NodeData::Visual { image: Some(bytes), .. } => Some(bytes.as_slice()),
+            NodeData::Select { chosen: Some(chosen), .. } if self.select_candidates(id).contains(chosen) => self.image_output(*chosen),
+            _ => None,
+        }
+    }
+
+    /// What Select node `id` hands downstream: the chosen parent's text, and whether it chose an image instead. Nothing while the
+    /// chosen parent isn't linked in or hasn't produced anything.
+    pub(crate) fn selected_output(&self, id: u64, chosen: Option<u64>) -> (String, bool) {
+        let Some(chosen) = chosen.filter(|c| self.select_candidates(id).contains(c)) else { return (String::new(), false) };
+        let text = self.nodes[&chosen].data.output().unwrap_or_default().to_string();
+        (text, self.image_output(chosen).is_some())
+    }
+}
+
+/// The candidate list of Select node `id`. Clicking a row gives the list keyboard focus: ↑/↓ move the cursor, Enter stars the row
+/// under it and Esc lets go. Returns true when the choice changed.
+pub fn candidate_list(ui: &mut egui::Ui, state: &CanvasState, id: u64, chosen: &mut Option<u64>) -> bool {
+    let candidates = state.select_candidates(id);
+    if candidates.is_empty() { ui.weak("Link nodes into this one to choose between their results"); return false; }
+    let focus_id = egui::Id::new(("select_keys", id));
+    ui.memory_mut(|m| {
+        m.interested_in_focus(focus_id);
+        m.set_focus_lock_filter(focus_id, egui::EventFilter { vertical_arrows: true, escape: true, ..Default::default() });
+    });
+    let focused = ui.memory(|m| m.has_focus(focus_id));
+    // The cursor is view state, kept in egui's memory rather than the node so moving it isn't an edit.
+    let mut cursor: usize = ui.data(|d| d.get_temp(focus_id)).unwrap_or_default();
+    cursor = cursor.min(candidates.len() - 1);
+    let mut changed = false;
+    if focused {
+        let (up, down, enter, escape) = ui.input_mut(|i| (i.consume_key(Modifiers::NONE, Key::ArrowUp), i.consume_key(Modifiers::NONE, Key::ArrowDown), i.consume_key(Modifiers::NONE, Key::Enter), i.consume_key(Modifiers::NONE, Key::Escape)));
+        if up { cursor = cursor.saturating_sub(1); }
+        if down { cursor = (cursor + 1).min(candidates.len() - 1); }
+        if enter { changed |= chosen.replace(candidates[cursor]) != Some(candidates[cursor]); }
+        if escape { ui.memory_mut(|m| m.surrender_focus(focus_id)); }
+    }
+    for (index, &parent) in candidates.iter().enumerate() {
+        let p = &state.nodes[&parent];
+        let highlight = focused && index == cursor;
+        let frame = egui::Frame::none().inner_margin(2.0).rounding(4.0).fill(if highlight { ui.visuals().selection.bg_fill.gamma_multiply(0.4) } else { egui::Color32::TRANSPARENT });
+        let row = frame.show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let star = ui.add(egui::Button::new(if *chosen == Some(parent) { "⭐" } else { "☆" }).frame(false)).on_hover_text("Send this one downstream");
+                if star.clicked() { changed |= chosen.replace(parent) != Some(parent); }
+                let label = ui.add(egui::Label::new(format!("{} #{}", p.data.kind().icon(), parent)).sense(egui::Sense::click())).on_hover_text(p.display_title());
+                if p.data.is_loading() { ui.spinner(); ui.weak("waiting…"); }
+                else if let NodeData::Visual { texture: Some(tex), .. } = &p.data { ui.add(egui::Image::new(tex).max_size(THUMBNAIL)); }
+                else {
+                    match p.data.output() { Some(text) => { ui.small(truncate(&text.replace('\n', " "), 80)); } None => { ui.weak("(no result yet)"); } }
+                }
+                star.clicked() || label.clicked()
+            }).inner
+        });
+        if row.inner || row.response.clicked() { cursor = index; ui.memory_mut(|m| m.request_focus(focus_id)); }
+    }
+    if focused { ui.weak("↑/↓ move · Enter choose · Esc done"); }
+    ui.data_mut(|d| d.insert_temp(focus_id, cursor));
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CanvasState, Node, NodeData, NodeKind};
+
+    fn board(chosen: Option<u64>) -> CanvasState {
+        let mut state = CanvasState::default();
+        state.nodes.insert(1, Node::new(1, Default::default(), NodeData::Concept { text: "first".to_string() }));
+        state.nodes.insert(2, Node::new(2, Default::default(), NodeData::Concept { text: "second".to_string() }));
+        let mut visual = NodeKind::Visual.default_data();
+        if let NodeData::Visual { image, .. } = &mut visual { *image = Some(vec![1, 2, 3]); }
+        state.nodes.insert(3, Node::new(3, Default::default(), visual));
+        let mut select = NodeKind::Select.default_data();
+        if let NodeData::Select { chosen: c, .. } = &mut select { *c = chosen; }
+        state.nodes.insert(9, Node::new(9, Default::default(), select));
+        state.link_all(&[(1, 9), (2, 9), (3, 9)]);
+        state.recompute_outputs();
+        state
+    }
+
+    #[test]
+    fn only_the_chosen_candidate_goes_downstream() {
+        let state = board(Some(2));
+        assert_eq!(state.select_candidates(9), vec![1, 2, 3]);
+        assert_eq!(state.nodes[&9].data.output(), Some("second"));
+        assert!(state.image_output(9).is_none());
+        let mut rejected: Vec<u64> = state.rejected_by_picks().into_iter().collect();
+        rejected.sort();
+        assert_eq!(rejected, vec![1, 3]);
+        assert!(board(None).nodes[&9].data.output().is_none());
+    }
+
+    #[test]
+    fn a_chosen_image_is_handed_on_and_the_pick_survives_relinking() {
+        let state = board(Some(3));
+        assert_eq!(state.image_output(9), Some(&[1u8, 2, 3][..]));
+        assert_eq!(state.nodes[&9].data.output_kind(), Some(crate::ports::PortKind::Image));
+        let mut unlinked = board(Some(2));
+        unlinked.edges.retain(|e| e.from != 2);
+        unlinked.recompute_outputs();
+        assert!(unlinked.nodes[&9].data.output().is_none());
+        assert!(matches!(unlinked.nodes[&9].data, NodeData::Select { chosen: Some(2), .. }), "the pick is kept for when 2 is linked again");
+    }
+}
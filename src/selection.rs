@@ -0,0 +1,112 @@
+use crate::history::Command;
+use crate::{CanvasState, NodeData};
+use eframe::egui;
+
+impl NodeData {
+    /// Drops results, images, picks and export status, keeping what was typed. False when there's nothing to clear.
+    pub fn clear_result(&mut self) -> bool {
+        match self {
+            Self::Concept { .. } | Self::Merge { .. } | Self::Note { .. } | Self::Script { .. } | Self::Character { .. } | Self::Transform { .. } | Self::Frame { .. } | Self::MarkdownView { .. } => return false,
+            Self::Compare { picked, .. } => *picked = None,
+            Self::Select { chosen, .. } => *chosen = None,
+            Self::Branch { outcome, input, .. } => { *outcome = None; input.clear(); }
+            Self::YouComResearch { result, .. } | Self::AgnosticAI { result, .. } | Self::Translate { result, .. } | Self::WebFetch { result, .. } => *result = None,
+            Self::Visual { texture, image, variants, .. } => { *texture = None; *image = None; variants.clear(); }
+            Self::FoxitExport { status, .. } => *status = "Ready".to_string(),
+            Self::Audio { audio, .. } => *audio = None,
+            Self::Transcribe { transcript, .. } => transcript.clear(),
+        }
+        if let Some(flag) = self.loading_flag() { *flag = false; }
+        true
+    }
+
+    /// Whether the fields `clear_result` resets hold the same in both.
+    pub fn same_result(&self, other: &NodeData) -> bool {
+        match (self, other) {
+            (Self::Compare { picked: a, .. }, Self::Compare { picked: b, .. }) => a == b,
+            (Self::Select { chosen: a, .. }, Self::Select { chosen: b, .. }) => a == b,
+            (Self::Branch { outcome: a, input: b, .. }, Self::Branch { outcome: x, input: y, .. }) => a == x && b == y,
+            (Self::YouComResearch { result: a, .. }, Self::YouComResearch { result: b, .. }) | (Self::AgnosticAI { result: a, .. }, Self::AgnosticAI { result: b, .. })
+            | (Self::Translate { result: a, .. }, Self::Translate { result: b, .. }) | (Self::WebFetch { result: a, .. }, Self::WebFetch { result: b, .. }) => a == b,
+            (Self::Visual { image: a, variants: b, .. }, Self::Visual { image: x, variants: y, .. }) => a == x && b.len() == y.len(),
+            (Self::FoxitExport { status: a, .. }, Self::FoxitExport { status: b, .. }) => a == b,
+            (Self::Audio { audio: a, .. }, Self::Audio { audio: b, .. }) => a == b,
+            (Self::Transcribe { transcript: a, .. }, Self::Transcribe { transcript: b, .. }) => a == b,
+            _ => true,
+        }
+    }
+
+    /// Takes `live`'s result fields, the ones `clear_result` resets, when both are the same kind.
+    pub fn adopt_result(&mut self, live: &NodeData) {
+        match (self, live) {
+            (Self::Compare { picked, .. }, Self::Compare { picked: p, .. }) => *picked = *p,
+            (Self::Select { chosen, .. }, Self::Select { chosen: c, .. }) => *chosen = *c,
+            (Self::Branch { outcome, input, .. }, Self::Branch { outcome: o, input: i, .. }) => { *outcome = *o; input.clone_from(i); }
+            (Self::YouComResearch { result, .. }, Self::YouComResearch { result: r, .. }) | (Self::AgnosticAI { result, .. }, Self::AgnosticAI { result: r, .. })
+            | (Self::Translate { result, .. }, Self::Translate { result: r, .. }) | (Self::WebFetch { result, .. }, Self::WebFetch { result: r, .. }) => result.clone_from(r),
+            (Self::Visual { texture, image, variants, image_seed, .. }, Self::Visual { texture: t, image: i, variants: v, image_seed: s, .. }) => { texture.clone_from(t); image.clone_from(i); variants.clone_from(v); *image_seed = *s; }
+            (Self::FoxitExport { status, .. }, Self::FoxitExport { status: s, .. }) => status.clone_from(s),
+            (Self::Audio { audio, .. }, Self::Audio { audio: a, .. }) => audio.clone_from(a),
+            (Self::Transcribe { transcript, .. }, Self::Transcribe { transcript: t, .. }) => transcript.clone_from(t),
+            _ => {}
+        }
+    }
+}
+
+impl CanvasState {
+    /// Pins or unpins the given nodes.
+    pub fn set_pinned(&mut self, ids: &[u64], pinned: bool) {
+        for id in ids { if let Some(n) = self.nodes.get_mut(id) { n.pinned = pinned; n.velocity = egui::Vec2::ZERO; } }
+    }
+
+    /// Resets results, images and export status of the given nodes as a single undo step. Concept nodes have nothing to clear.
+    pub fn clear_results(&mut self, ids: &[u64]) {
+        let mut cmds = Vec::new();
+        for id in ids {
+            let Some(node) = self.nodes.get_mut(id) else { continue };
+            let mut after = node.data.clone();
+            if !after.clear_result() { continue; }
+            node.error = None;
+            cmds.push(Command::EditData { id: *id, before: node.data.clone(), after });
+        }
+        if !cmds.is_empty() { self.execute(Command::Batch(cmds)); }
+    }
+
+    /// Removes every edge touching any of the given nodes as a single undo step.
+    pub fn disconnect_nodes(&mut self, ids: &[u64]) {
+        // Highest index first, so each recorded index is still valid when reverted in reverse order.
+        let mut cmds: Vec<Command> = self.edges.iter().enumerate().filter(|(_, e)| ids.contains(&e.from) || ids.contains(&e.to)).map(|(i, e)| Command::RemoveEdge(i, e.clone())).collect();
+        cmds.reverse();
+        if cmds.is_empty() { return; }
+        self.execute(Command::Batch(cmds));
+        if self.selected_edge.is_some_and(|e| !self.edges.iter().any(|edge| edge.id == e)) { self.selected_edge = None; }
+    }
+}
+
+/// Buttons acting on every node in `ids`, shared by the sidebar and the canvas context menu. Returns true if one was clicked.
+pub fn selection_actions(ui: &mut egui::Ui, state: &mut CanvasState, ids: &[u64]) -> bool {
+    let all_pinned = ids.iter().filter_map(|id| state.nodes.get(id)).all(|n| n.pinned);
+    let delete = ui.button(format!("🗑 Delete {} nodes", ids.len())).clicked();
+    let pin = ui.button(if all_pinned { "📌 Unpin all" } else { "📌 Pin all" }).clicked();
+    let clear = ui.button("🧹 Clear results").on_hover_text("Reset results, images and export status").clicked();
+    let disconnect = ui.button("✂ Disconnect from graph").clicked();
+    if delete { state.remove_nodes(ids); }
+    if pin { state.set_pinned(ids, !all_pinned); }
+    if clear { state.clear_results(ids); }
+    if disconnect { state.disconnect_nodes(ids); }
+    delete || pin || clear || disconnect
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CanvasState, NodeKind};
+
+    #[test]
+    fn clearing_leaves_nodes_without_results_alone() {
+        let mut state = CanvasState::graph(&[(1, NodeKind::Concept), (2, NodeKind::AgnosticAI)], &[]);
+        for id in [1, 2] { state.nodes.get_mut(&id).unwrap().error = Some("✗ failed".to_string()); }
+        state.clear_results(&[1, 2]);
+        assert_eq!(state.nodes[&1].error.as_deref(), Some("✗ failed"), "a concept has nothing to clear, its error included");
+        assert_eq!(state.nodes[&2].error, None);
+    }
+}
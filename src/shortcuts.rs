@@ -0,0 +1,121 @@
+use crate::NodeKind;
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+
+/// Everything that can be bound to a key combo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    AddNode(NodeKind),
+    DeleteSelection,
+    FitView,
+    ZoomToSelection,
+    ZoomIn,
+    ZoomOut,
+    /// 100% zoom centered on the graph.
+    ResetZoom,
+    TogglePhysics,
+    Undo,
+    Redo,
+    ShowShortcuts,
+    FocusNext,
+    FocusPrevious,
+    /// Puts the keyboard into the focused node's main text field.
+    EditFocused,
+    /// Opens the node type palette under the pointer.
+    QuickAdd,
+    /// Starts stepping through the pipeline; inside presentation mode P and Esc leave it.
+    Present,
+    /// Starts "Run Pipeline", or cancels the run in progress.
+    RunPipeline,
+}
+
+impl Action {
+    pub fn label(self) -> String {
+        match self {
+            Self::AddNode(kind) => format!("Add {} node", kind.title()),
+            Self::DeleteSelection => "Delete selection".to_string(),
+            Self::FitView => "Fit view".to_string(),
+            Self::ZoomToSelection => "Zoom to selection".to_string(),
+            Self::ZoomIn => "Zoom in".to_string(),
+            Self::ZoomOut => "Zoom out".to_string(),
+            Self::ResetZoom => "Reset zoom to 100%".to_string(),
+            Self::TogglePhysics => "Toggle physics".to_string(),
+            Self::Undo => "Undo".to_string(),
+            Self::Redo => "Redo".to_string(),
+            Self::ShowShortcuts => "Show keyboard shortcuts".to_string(),
+            Self::FocusNext => "Focus next node".to_string(),
+            Self::FocusPrevious => "Focus previous node".to_string(),
+            Self::EditFocused => "Edit focused node (Esc returns to canvas)".to_string(),
+            Self::QuickAdd => "Quick-add node palette".to_string(),
+            Self::Present => "Present the pipeline (←/→ step, Esc exits)".to_string(),
+            Self::RunPipeline => "Run pipeline / cancel run".to_string(),
+        }
+    }
+}
+
+pub struct Binding {
+    pub shortcut: KeyboardShortcut,
+    pub action: Action,
+}
+
+/// Key combo → action table consulted once per frame by `StoryBoardApp::update`.
+pub struct Shortcuts {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        let mut shortcuts = Self { bindings: Vec::new() };
+        for (key, kind) in [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9].into_iter().zip(NodeKind::ALL) {
+            shortcuts.register(Modifiers::NONE, key, Action::AddNode(kind));
+        }
+        shortcuts.register(Modifiers::NONE, Key::Delete, Action::DeleteSelection);
+        shortcuts.register(Modifiers::NONE, Key::Backspace, Action::DeleteSelection);
+        shortcuts.register(Modifiers::NONE, Key::F, Action::FitView);
+        shortcuts.register(Modifiers::NONE, Key::Z, Action::ZoomToSelection);
+        shortcuts.register(Modifiers::NONE, Key::Plus, Action::ZoomIn);
+        shortcuts.register(Modifiers::NONE, Key::Equals, Action::ZoomIn);
+        shortcuts.register(Modifiers::NONE, Key::Minus, Action::ZoomOut);
+        shortcuts.register(Modifiers::NONE, Key::Num0, Action::ResetZoom);
+        shortcuts.register(Modifiers::SHIFT, Key::P, Action::TogglePhysics);
+        shortcuts.register(Modifiers::COMMAND, Key::Z, Action::Undo);
+        shortcuts.register(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z, Action::Redo);
+        shortcuts.register(Modifiers::NONE, Key::F1, Action::ShowShortcuts);
+        shortcuts.register(Modifiers::NONE, Key::Tab, Action::FocusNext);
+        shortcuts.register(Modifiers::SHIFT, Key::Tab, Action::FocusPrevious);
+        shortcuts.register(Modifiers::NONE, Key::Enter, Action::EditFocused);
+        shortcuts.register(Modifiers::SHIFT, Key::A, Action::QuickAdd);
+        shortcuts.register(Modifiers::NONE, Key::P, Action::Present);
+        shortcuts.register(Modifiers::NONE, Key::F5, Action::RunPipeline);
+        shortcuts
+    }
+}
+
+fn modifier_count(m: Modifiers) -> usize {
+    [m.alt, m.ctrl || m.command, m.shift, m.mac_cmd].iter().filter(|&&b| b).count()
+}
+
+impl Shortcuts {
+    pub fn register(&mut self, modifiers: Modifiers, key: Key, action: Action) {
+        self.bindings.push(Binding { shortcut: KeyboardShortcut::new(modifiers, key), action });
+        // egui matches shortcuts ignoring unlisted Shift/Alt, so the most specific combos must be tried first.
+        self.bindings.sort_by_key(|b| std::cmp::Reverse(modifier_count(b.shortcut.modifiers)));
+    }
+
+    /// Consumes and returns the actions triggered this frame. Does nothing while a text field has keyboard focus.
+    pub fn poll(&self, ctx: &egui::Context) -> Vec<Action> {
+        if ctx.wants_keyboard_input() { return Vec::new(); }
+        ctx.input_mut(|i| self.bindings.iter().filter(|b| i.consume_shortcut(&b.shortcut)).map(|b| b.action).collect())
+    }
+
+    pub fn show_help(&self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new("⌨ Keyboard shortcuts").open(open).collapsible(false).resizable(false).show(ctx, |ui| {
+            egui::Grid::new("shortcut_grid").striped(true).show(ui, |ui| {
+                for binding in &self.bindings {
+                    ui.monospace(ctx.format_shortcut(&binding.shortcut));
+                    ui.label(binding.action.label());
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,74 @@
+use crate::pipeline::PipelineRun;
+use crate::{CanvasState, NodeData, StoryBoardApp};
+use eframe::egui::Color32;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Dot shown on nodes whose inputs changed after they last ran.
+pub const STATUS_STALE: Color32 = Color32::from_rgb(240, 140, 40);
+
+impl NodeData {
+    /// Fingerprint of what this node hands downstream; it changes whenever its children would see something different.
+    pub fn output_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self {
+            Self::Visual { texture, .. } => texture.as_ref().map(|t| t.id()).hash(&mut hasher),
+            _ => self.output().hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+}
+
+impl CanvasState {
+    /// Compares every node's output with the one seen last time and flags everything downstream of those that changed. Nodes that
+    /// can't be run (Concept, Merge, Compare, Select, Note, Character, Transform, Frame, Markdown View) are never stale themselves; a node seen for the first time only records its output.
+    pub fn mark_stale(&mut self) {
+        let mut changed = Vec::new();
+        for node in self.nodes.values_mut() {
+            let hash = node.data.output_hash();
+            if node.output_hash.replace(hash).is_some_and(|old| old != hash) { changed.push(node.id); }
+        }
+        for id in changed {
+            for child in self.descendants(id) {
+                if let Some(node) = self.nodes.get_mut(&child).filter(|n| !matches!(n.data, NodeData::Concept { .. } | NodeData::Merge { .. } | NodeData::Compare { .. } | NodeData::Select { .. } | NodeData::Note { .. } | NodeData::Character { .. } | NodeData::Transform { .. } | NodeData::Frame { .. } | NodeData::MarkdownView { .. })) { node.stale = true; }
+            }
+        }
+    }
+
+    pub fn stale_count(&self) -> usize {
+        self.nodes.values().filter(|n| n.stale).count()
+    }
+}
+
+impl StoryBoardApp {
+    /// "Re-run stale": a pipeline run over just the stale nodes, parents first.
+    pub(crate) fn start_stale_run(&mut self) {
+        match self.state.pipeline_order() {
+            Ok(order) => {
+                let order: Vec<u64> = order.into_iter().filter(|id| self.state.nodes[id].stale).collect();
+                for id in &order { if let Some(node) = self.state.nodes.get_mut(id) { node.queued = true; } }
+                self.pipeline_summary = None;
+                self.pipeline = Some(PipelineRun::new(order));
+            }
+            Err(stuck) => self.toast(format!("Can't re-run stale nodes: nodes {} form a cycle", stuck.iter().map(|id| format!("#{}", id)).collect::<Vec<_>>().join(", "))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CanvasState, NodeData, NodeKind};
+
+    #[test]
+    fn changed_output_flags_runnable_descendants_only() {
+        // Concept 1 → Research 2 → AI 3, and an unrelated AI 4
+        let mut state = CanvasState::graph(&[(1, NodeKind::Concept), (2, NodeKind::Research), (3, NodeKind::AgnosticAI), (4, NodeKind::AgnosticAI)], &[(1, 2), (2, 3)]);
+        state.mark_stale();
+        assert_eq!(state.stale_count(), 0, "the first look only records outputs");
+        if let NodeData::Concept { text } = &mut state.nodes.get_mut(&1).unwrap().data { *text = "A new idea".to_string(); }
+        state.mark_stale();
+        let mut stale: Vec<u64> = state.nodes.values().filter(|n| n.stale).map(|n| n.id).collect();
+        stale.sort();
+        assert_eq!(stale, vec![2, 3]);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{CanvasState, NodeData};
+use eframe::egui;
+use std::collections::{HashSet, VecDeque};
+
+/// Placeholders offered by the prompt editors' insert menu.
+pub const PLACEHOLDERS: [(&str, &str); 3] = [
+    ("{{parent}}", "All parents' output, as Link Parent would insert it"),
+    ("{{parents[0]}}", "Output of one parent, counted in link order from 0"),
+    ("{{concept}}", "Text of the nearest upstream Concept node"),
+];
+
+/// What a prompt's placeholders are filled from.
+#[derive(Default)]
+pub struct TemplateInputs {
+    pub parent: Option<String>,
+    /// Each text-producing parent's output in link order; `None` while it has no result.
+    pub parents: Vec<Option<String>>,
+    pub concept: Option<String>,
+}
+
+/// Placeholder names found in `template`, without braces or surrounding spaces.
+fn placeholders(template: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut rest = 0;
+    std::iter::from_fn(move || {
+        let start = rest + template[rest..].find("{{")?;
+        let end = start + 2 + template[start + 2..].find("}}")? + 2;
+        rest = end;
+        Some((start, end, template[start + 2..end - 2].trim()))
+    })
+}
+
+fn is_known(name: &str) -> bool {
+    name == "parent" || name == "concept" || name.strip_prefix("parents[").and_then(|n| n.strip_suffix(']')).is_some_and(|n| n.parse::<usize>().is_ok())
+}
+
+pub fn has_placeholders(template: &str) -> bool {
+    placeholders(template).next().is_some()
+}
+
+/// Placeholders in `template` that aren't one of `PLACEHOLDERS`; they're sent as typed.
+pub fn unknown_placeholders(template: &str) -> Vec<String> {
+    placeholders(template).filter(|(_, _, name)| !is_known(name)).map(|(start, end, _)| template[start..end].to_string()).collect()
+}
+
+/// Fills in every placeholder that `inputs` can answer. Unknown ones, and ones with nothing to fill them with, are left as typed.
+pub fn render(template: &str, inputs: &TemplateInputs) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut copied = 0;
+    for (start, end, name) in placeholders(template) {
+        let value = match name {
+            "parent" => inputs.parent.as_deref(),
+            "concept" => inputs.concept.as_deref(),
+            _ => name.strip_prefix("parents[").and_then(|n| n.strip_suffix(']')).and_then(|n| n.parse::<usize>().ok()).and_then(|i| inputs.parents.get(i)).and_then(|p| p.as_deref()),
+        };
+        out.push_str(&template[copied..start]);
+        out.push_str(value.unwrap_or(&template[start..end]));
+        copied = end;
+    }
+    out.push_str(&template[copied..]);
+    out
+}
+
+/// "{ }" menu that appends a placeholder to `prompt`, plus a ⚠ badge when the prompt names one that doesn't exist. Returns true if it edited `prompt`.
+pub fn prompt_tools(ui: &mut egui::Ui, prompt: &mut String) -> bool {
+    let mut changed = false;
+    ui.menu_button("{ }", |ui| {
+        for (placeholder, what) in PLACEHOLDERS {
+            if ui.button(placeholder).on_hover_text(what).clicked() {
+                if !prompt.is_empty() && !prompt.ends_with(char::is_whitespace) { prompt.push(' '); }
+                prompt.push_str(placeholder);
+                changed = true;
+                ui.close_menu();
+            }
+        }
+    }).response.on_hover_text("Insert placeholder");
+    let unknown = unknown_placeholders(prompt);
+    if !unknown.is_empty() {
+        ui.colored_label(ui.visuals().warn_fg_color, "⚠").on_hover_text(format!("Unknown placeholders are sent as typed: {}", unknown.join(", ")));
+    }
+    changed
+}
+
+impl CanvasState {
+    pub fn template_inputs(&self, id: u64) -> TemplateInputs {
+        let parents = self.text_parents(id).iter().map(|p| self.nodes[p].data.output().map(str::to_string)).collect();
+        TemplateInputs { parent: self.parent_output(id), parents, concept: self.upstream_concept(id) }
+    }
+
+    /// Text of the closest Concept node reachable by walking edges backwards from `id`, breadth first.
+    fn upstream_concept(&self, id: u64) -> Option<String> {
+        let mut queue = VecDeque::from([id]);
+        let mut seen = HashSet::from([id]);
+        while let Some(current) = queue.pop_front() {
+            for edge in self.edges.iter().filter(|e| e.to == current) {
+                if !seen.insert(edge.from) { continue; }
+                match self.nodes.get(&edge.from).map(|n| &n.data) {
+                    Some(NodeData::Concept { text }) => return Some(text.clone()),
+                    Some(_) => queue.push_back(edge.from),
+                    None => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// `id`'s prompt as it is sent: placeholders filled from the current graph, behind the descriptions of any linked characters.
+    pub fn render_prompt(&self, id: u64, prompt: &str) -> String {
+        let prompt = if has_placeholders(prompt) { render(prompt, &self.template_inputs(id)) } else { prompt.to_string() };
+        match self.character_sheet(id) { Some(sheet) => format!("{}\n\n{}", sheet, prompt), None => prompt }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> TemplateInputs {
+        TemplateInputs { parent: Some("all".into()), parents: vec![Some("first".into()), None], concept: Some("Mars".into()) }
+    }
+
+    #[test]
+    fn fills_known_placeholders() {
+        assert_eq!(render("Write about {{concept}} using {{ parent }}.", &inputs()), "Write about Mars using all.");
+        assert_eq!(render("{{parents[0]}}{{parents[0]}}", &inputs()), "firstfirst");
+    }
+
+    #[test]
+    fn leaves_unknown_and_unfillable_placeholders_as_typed() {
+        assert_eq!(render("{{shot}} and {{parents[1]}} and {{parents[7]}}", &inputs()), "{{shot}} and {{parents[1]}} and {{parents[7]}}");
+        assert_eq!(render("{{concept}}", &TemplateInputs::default()), "{{concept}}");
+    }
+
+    #[test]
+    fn reports_only_unknown_names() {
+        assert_eq!(unknown_placeholders("{{parent}} {{parents[2]}} {{ Concept }} {{parents[x]}}"), vec!["{{ Concept }}", "{{parents[x]}}"]);
+        assert!(unknown_placeholders("plain prompt").is_empty());
+    }
+
+    #[test]
+    fn unclosed_braces_are_plain_text() {
+        assert!(!has_placeholders("a {{parent"));
+        assert_eq!(render("a {{parent", &inputs()), "a {{parent");
+        assert_eq!(render("}} {{concept}} {{", &inputs()), "}} Mars {{");
+    }
+}
@@ -0,0 +1,79 @@
+use eframe::egui::{self, Color32};
+
+/// Which color preset the canvas and egui widgets use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ThemeKind {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Colors used when painting the canvas.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    pub canvas_bg: Color32,
+    pub grid: Color32,
+    pub node_fill: Color32,
+    pub node_border: Color32,
+    pub selection: Color32,
+    pub edge: Color32,
+    pub edge_hover: Color32,
+    pub text: Color32,
+    pub muted_text: Color32,
+    /// Ports, resize grips and other small handles.
+    pub handle: Color32,
+    /// Backgrounds of overlays drawn on the canvas: minimap, edge label pills.
+    pub overlay: Color32,
+    /// Group frame body and title bar tints.
+    pub group_fill: Color32,
+    pub group_header: Color32,
+}
+
+impl ThemeKind {
+    pub fn toggled(self) -> Self {
+        match self { Self::Dark => Self::Light, Self::Light => Self::Dark }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self { Self::Dark => "🌙 Dark", Self::Light => "☀ Light" }
+    }
+
+    pub fn visuals(self) -> egui::Visuals {
+        match self { Self::Dark => egui::Visuals::dark(), Self::Light => egui::Visuals::light() }
+    }
+
+    pub fn palette(self) -> Theme {
+        match self {
+            Self::Dark => Theme {
+                canvas_bg: Color32::from_rgb(15, 15, 15),
+                grid: Color32::from_gray(48),
+                node_fill: Color32::from_gray(30),
+                node_border: Color32::from_gray(60),
+                selection: Color32::from_rgb(0, 200, 255),
+                edge: Color32::from_gray(80),
+                edge_hover: Color32::from_gray(230),
+                text: Color32::WHITE,
+                muted_text: Color32::from_gray(170),
+                handle: Color32::from_gray(90),
+                overlay: Color32::from_black_alpha(200),
+                group_fill: Color32::from_white_alpha(6),
+                group_header: Color32::from_white_alpha(14),
+            },
+            Self::Light => Theme {
+                canvas_bg: Color32::from_rgb(236, 236, 240),
+                grid: Color32::from_gray(200),
+                node_fill: Color32::from_gray(252),
+                node_border: Color32::from_gray(190),
+                selection: Color32::from_rgb(0, 120, 215),
+                edge: Color32::from_gray(160),
+                edge_hover: Color32::from_gray(40),
+                text: Color32::from_gray(20),
+                muted_text: Color32::from_gray(90),
+                handle: Color32::from_gray(150),
+                overlay: Color32::from_white_alpha(220),
+                group_fill: Color32::from_black_alpha(6),
+                group_header: Color32::from_black_alpha(14),
+            },
+        }
+    }
+}
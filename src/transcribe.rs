@@ -0,0 +1,79 @@
+use crate::history::Command;
+use crate::{audio, AppMessage, NodeData, StoryBoardApp};
+use eframe::egui;
+
+/// File types the Whisper API takes; anything else is refused before upload.
+pub const AUDIO_EXTENSIONS: [&str; 9] = ["mp3", "mp4", "mpeg", "mpga", "m4a", "wav", "webm", "ogg", "flac"];
+/// Whisper's upload limit.
+pub const MAX_AUDIO_BYTES: usize = 25 * 1024 * 1024;
+
+pub fn is_audio_file(name: &str) -> bool {
+    name.rsplit_once('.').is_some_and(|(_, ext)| AUDIO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Why `bytes` can't be sent for transcription, if they can't.
+pub fn check_audio(name: &str, bytes: &[u8]) -> Result<(), String> {
+    if !is_audio_file(name) { return Err(format!("only {} recordings can be transcribed", AUDIO_EXTENSIONS.join(", "))); }
+    if bytes.is_empty() { return Err("the file is empty".to_string()); }
+    if bytes.len() > MAX_AUDIO_BYTES { return Err(format!("it's {}, over the {} limit", size_label(bytes.len()), size_label(MAX_AUDIO_BYTES))); }
+    Ok(())
+}
+
+/// "340 KB", "1.2 MB".
+pub fn size_label(bytes: usize) -> String {
+    if bytes < 1024 * 1024 { format!("{} KB", bytes.div_ceil(1024)) } else { format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0)) }
+}
+
+/// "memo.wav · 0:12 · 340 KB"; the length only for WAV, whose header gives it away.
+pub fn clip_summary(file_name: &str, bytes: &[u8]) -> String {
+    let duration = audio::wav_duration(bytes).map(crate::refresh::countdown);
+    [Some(file_name.to_string()), duration, Some(size_label(bytes.len()))].into_iter().flatten().collect::<Vec<_>>().join(" · ")
+}
+
+/// The recording chosen in the file dialog and its name; `None` when the dialog is cancelled.
+async fn pick_audio() -> Option<(String, Vec<u8>)> {
+    let file = rfd::AsyncFileDialog::new().set_title("Open a recording").add_filter("Audio", &AUDIO_EXTENSIONS).pick_file().await?;
+    Some((file.file_name(), file.read().await))
+}
+
+impl StoryBoardApp {
+    /// Opens the file dialog for Transcribe node `id`: the browser's file input on the web, the system dialog on its own thread natively.
+    /// The chosen file arrives as `AppMessage::AudioFile`.
+    pub(crate) fn pick_audio_file(&self, id: u64, ctx: &egui::Context) {
+        let (tx, ctx) = (self.http_tx.clone(), ctx.clone());
+        let deliver = move |picked: Option<(String, Vec<u8>)>| {
+            if let Some((name, bytes)) = picked { let _ = tx.send(AppMessage::AudioFile(id, name, bytes)); ctx.request_repaint(); }
+        };
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move { deliver(pick_audio().await) });
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || deliver(tokio::runtime::Builder::new_current_thread().enable_all().build().ok().and_then(|runtime| runtime.block_on(pick_audio()))));
+    }
+
+    /// Makes `bytes` the recording of Transcribe node `id` as one undo step, or says why it can't be used.
+    pub(crate) fn attach_audio(&mut self, id: u64, name: String, bytes: Vec<u8>) {
+        if let Err(err) = check_audio(&name, &bytes) { self.toast(format!("⚠ Can't use {}: {}", name, err)); return; }
+        let Some(before) = self.state.nodes.get(&id).map(|n| n.data.clone()) else { return };
+        let mut after = before.clone();
+        let NodeData::Transcribe { file_name, audio, .. } = &mut after else { return };
+        *file_name = name;
+        *audio = Some(bytes.into());
+        self.state.execute(Command::EditData { id, before, after });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recordings_are_checked_before_upload() {
+        assert!(is_audio_file("Memo 3.M4A") && is_audio_file("take.wav") && !is_audio_file("notes.txt") && !is_audio_file("wav"));
+        assert!(check_audio("memo.mp3", &[1, 2, 3]).is_ok());
+        assert_eq!(check_audio("memo.mp3", &[]).unwrap_err(), "the file is empty");
+        assert!(check_audio("cover.png", &[1]).unwrap_err().starts_with("only mp3"));
+        assert_eq!(check_audio("long.wav", &vec![0; MAX_AUDIO_BYTES + 1]).unwrap_err(), "it's 25.0 MB, over the 25.0 MB limit");
+        assert_eq!(size_label(2000), "2 KB");
+        assert_eq!(clip_summary("memo.mp3", &[0; 10]), "memo.mp3 · 1 KB");
+    }
+}
@@ -0,0 +1,211 @@
+use crate::merge::unescape;
+use eframe::egui;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Most steps one Transform node chains.
+pub const MAX_STEPS: usize = 3;
+
+/// One operation of a Transform node, applied to the previous step's text.
+#[derive(Clone, Debug, PartialEq, Hash, Deserialize, Serialize)]
+pub enum Step {
+    /// Every match of `pattern`, one per line; its first group instead of the whole match when it has one.
+    Extract { pattern: String },
+    /// Every match of `pattern` replaced by `with`, which can refer to groups as `$1`.
+    Replace { pattern: String, with: String },
+    /// `template` with `{{text}}` swapped for the text, or `{{json}}` for it as a quoted JSON string.
+    Wrap { template: String },
+    /// The first `words` words, keeping the line breaks between them.
+    Truncate { words: usize },
+    /// The value at a dotted `path` ("scene.lines.0") of the text read as JSON.
+    JsonField { path: String },
+}
+
+impl Step {
+    /// One of each operation, as the step picker offers them.
+    pub fn defaults() -> [Step; 5] {
+        [
+            Step::Extract { pattern: "SCENE:\\s*(?s)(.*)".to_string() },
+            Step::Replace { pattern: String::new(), with: String::new() },
+            Step::Wrap { template: "{\"text\": {{json}}}".to_string() },
+            Step::Truncate { words: 50 },
+            Step::JsonField { path: String::new() },
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self { Self::Extract { .. } => "Regex extract", Self::Replace { .. } => "Regex replace", Self::Wrap { .. } => "Template wrap", Self::Truncate { .. } => "Truncate words", Self::JsonField { .. } => "JSON field" }
+    }
+
+    pub fn apply(&self, text: &str) -> Result<String, String> {
+        match self {
+            Self::Extract { pattern } => {
+                let re = compile(pattern)?;
+                let found: Vec<&str> = re.captures_iter(text).filter_map(|c| c.get(1).or_else(|| c.get(0))).map(|m| m.as_str()).collect();
+                if found.is_empty() { Err("the pattern matched nothing".to_string()) } else { Ok(found.join("\n")) }
+            }
+            Self::Replace { pattern, with } => Ok(compile(pattern)?.replace_all(text, unescape(with).as_str()).into_owned()),
+            Self::Wrap { template } => {
+                if !template.contains("{{text}}") && !template.contains("{{json}}") { return Err("the template needs {{text}} or {{json}}".to_string()); }
+                Ok(unescape(template).replace("{{json}}", &serde_json::Value::from(text).to_string()).replace("{{text}}", text))
+            }
+            Self::Truncate { words } => Ok(first_words(text, *words).to_string()),
+            Self::JsonField { path } => json_field(text, path),
+        }
+    }
+}
+
+/// `pattern` compiled, or the regex crate's complaint about it without the pattern echoed back.
+fn compile(pattern: &str) -> Result<Regex, String> {
+    if pattern.is_empty() { return Err("the pattern is empty".to_string()); }
+    Regex::new(pattern).map_err(|e| format!("invalid regex: {}", e.to_string().lines().last().unwrap_or_default().trim().trim_start_matches("error: ")))
+}
+
+/// `text` up to the end of its `count`th word.
+fn first_words(text: &str, count: usize) -> &str {
+    let mut words = text.split_whitespace();
+    let Some(last) = words.nth(count.saturating_sub(1)).filter(|_| count > 0) else { return if count == 0 { "" } else { text.trim_end() } };
+    // Words are slices of `text`, so the end of the last one kept is an offset into it.
+    &text[..last.as_ptr() as usize - text.as_ptr() as usize + last.len()]
+}
+
+/// The value at `path` in the JSON object or array found in `text`; strings come back without their quotes.
+fn json_field(text: &str, path: &str) -> Result<String, String> {
+    let (Some(start), Some(end)) = (text.find(['{', '[']), text.rfind(['}', ']'])) else { return Err("the text isn't JSON".to_string()) };
+    let value: serde_json::Value = serde_json::from_str(text.get(start..=end).unwrap_or_default()).map_err(|e| format!("the text isn't valid JSON: {}", e))?;
+    let pointer: String = path.split('.').filter(|p| !p.trim().is_empty()).map(|p| format!("/{}", p.trim())).collect();
+    match value.pointer(&pointer) {
+        Some(serde_json::Value::String(s)) => Ok(s.clone()),
+        Some(other) => Ok(serde_json::to_string_pretty(other).unwrap_or_default()),
+        None => Err(format!("no field \"{}\"", path.trim())),
+    }
+}
+
+/// `text` passed through every step in turn; the error names the step that failed.
+pub fn run(steps: &[Step], text: &str) -> Result<String, String> {
+    steps.iter().enumerate().try_fold(text.to_string(), |text, (i, step)| step.apply(&text).map_err(|e| format!("Step {}: {}", i + 1, e)))
+}
+
+/// Fingerprint of a run of `steps` over `text`; a Transform node only reruns when it changes.
+pub fn fingerprint(steps: &[Step], text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (steps, text).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A full-width one-line field for a step setting.
+fn field<'t>(text: &'t mut String, hint: &str) -> egui::TextEdit<'t> {
+    egui::TextEdit::singleline(text).hint_text(hint).desired_width(f32::INFINITY)
+}
+
+/// The step list of a Transform node: an operation picker and its settings per step, plus add and remove buttons. Returns true
+/// when anything was edited.
+pub fn transform_ui(ui: &mut egui::Ui, id: u64, steps: &mut Vec<Step>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    for (index, step) in steps.iter_mut().enumerate() {
+        ui.push_id((id, index), |ui| {
+            ui.horizontal(|ui| {
+                ui.weak(format!("{}.", index + 1));
+                egui::ComboBox::from_id_salt("op").selected_text(step.label()).width(110.0).show_ui(ui, |ui| {
+                    for option in Step::defaults() {
+                        let selected = std::mem::discriminant(&option) == std::mem::discriminant(step);
+                        if ui.selectable_label(selected, option.label()).clicked() && !selected { *step = option; changed = true; }
+                    }
+                });
+                if ui.small_button("✖").on_hover_text("Remove step").clicked() { remove = Some(index); }
+            });
+            changed |= match step {
+                Step::Extract { pattern } => ui.add(field(pattern, "Regex")).changed(),
+                Step::Replace { pattern, with } => ui.add(field(pattern, "Regex")).changed() | ui.add(field(with, "Replacement, $1 for a group")).changed(),
+                Step::Wrap { template } => ui.add(field(template, "Template with {{text}} or {{json}}")).changed(),
+                Step::Truncate { words } => ui.add(egui::DragValue::new(words).range(1..=10_000).suffix(" words")).changed(),
+                Step::JsonField { path } => ui.add(field(path, "Path, e.g. scene.lines.0")).changed(),
+            };
+        });
+    }
+    if let Some(index) = remove { steps.remove(index); changed = true; }
+    if steps.len() < MAX_STEPS && ui.small_button("➕ Step").clicked() { steps.push(Step::defaults()[0].clone()); changed = true; }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(pattern: &str) -> Step { Step::Extract { pattern: pattern.to_string() } }
+
+    #[test]
+    fn extract_takes_the_first_group_of_every_match() {
+        assert_eq!(extract("SCENE:\\s*(?s)(.*)").apply("Notes first.\nSCENE: Dock at dawn.\nGulls.").unwrap(), "Dock at dawn.\nGulls.");
+        assert_eq!(extract("#\\w+").apply("#storm and #dawn").unwrap(), "#storm\n#dawn");
+        assert_eq!(extract("\\d+").apply("no digits").unwrap_err(), "the pattern matched nothing");
+    }
+
+    #[test]
+    fn replace_expands_groups_and_escapes() {
+        let step = Step::Replace { pattern: "(?m)^(INT|EXT)\\. (.*)$".to_string(), with: "[$1] $2\\n".to_string() };
+        assert_eq!(step.apply("INT. Lighthouse\nwaves").unwrap(), "[INT] Lighthouse\n\nwaves");
+        assert_eq!(Step::Replace { pattern: "a".to_string(), with: String::new() }.apply("banana").unwrap(), "bnn");
+    }
+
+    #[test]
+    fn invalid_and_empty_patterns_are_reported() {
+        assert_eq!(extract("(unclosed").apply("x").unwrap_err(), "invalid regex: unclosed group");
+        assert_eq!(Step::Replace { pattern: "[z-a]".to_string(), with: String::new() }.apply("x").unwrap_err(), "invalid regex: invalid character class range, the start must be <= the end");
+        assert_eq!(extract("").apply("x").unwrap_err(), "the pattern is empty");
+    }
+
+    #[test]
+    fn wrap_fills_text_and_json_placeholders() {
+        let wrap = |template: &str| Step::Wrap { template: template.to_string() };
+        assert_eq!(wrap("Shot: {{text}}!").apply("wide").unwrap(), "Shot: wide!");
+        assert_eq!(wrap("{\"scene\": {{json}}}").apply("say \"hi\"\nbye").unwrap(), "{\"scene\": \"say \\\"hi\\\"\\nbye\"}");
+        assert!(wrap("no placeholder").apply("x").is_err());
+    }
+
+    #[test]
+    fn truncate_keeps_line_breaks_between_kept_words() {
+        let truncate = |words| Step::Truncate { words }.apply("  One two\nthree  four five ").unwrap();
+        assert_eq!(truncate(3), "  One two\nthree");
+        assert_eq!(truncate(9), "  One two\nthree  four five");
+        assert_eq!(truncate(0), "");
+    }
+
+    #[test]
+    fn json_field_follows_dotted_paths() {
+        let field = |path: &str, text: &str| Step::JsonField { path: path.to_string() }.apply(text);
+        let answer = "```json\n{\"scene\": {\"title\": \"Dawn\", \"lines\": [\"Go.\", {\"n\": 2}]}}\n```";
+        assert_eq!(field("scene.title", answer).unwrap(), "Dawn");
+        assert_eq!(field("scene.lines.0", answer).unwrap(), "Go.");
+        assert_eq!(field("scene.lines.1", answer).unwrap(), "{\n  \"n\": 2\n}");
+        assert_eq!(field("scene.missing", answer).unwrap_err(), "no field \"scene.missing\"");
+        assert_eq!(field("a", "plain words").unwrap_err(), "the text isn't JSON");
+        assert!(field("a", "{not: json}").unwrap_err().starts_with("the text isn't valid JSON"));
+    }
+
+    #[test]
+    fn steps_chain_and_name_the_one_that_failed() {
+        let steps = [extract("SCENE:\\s*(?s)(.*)"), Step::Truncate { words: 2 }, Step::Wrap { template: "<{{text}}>".to_string() }];
+        assert_eq!(run(&steps, "x SCENE: Dock at dawn.").unwrap(), "<Dock at>");
+        assert_eq!(run(&[], "as is").unwrap(), "as is");
+        assert_eq!(run(&[Step::Truncate { words: 5 }, extract("(")], "text").unwrap_err(), "Step 2: invalid regex: unclosed group");
+    }
+
+    #[test]
+    fn transforms_rerun_only_when_their_input_or_steps_change() {
+        let mut state = crate::CanvasState::graph(&[(1, crate::NodeKind::Concept), (2, crate::NodeKind::Transform)], &[(1, 2)]);
+        if let crate::NodeData::Concept { text } = &mut state.nodes.get_mut(&1).unwrap().data { *text = "x SCENE: Dock at dawn.".to_string(); }
+        state.recompute_outputs();
+        let key = state.transform_cache[&2].0;
+        assert_eq!(state.nodes[&2].data.output(), Some("Dock at dawn."));
+        state.recompute_outputs();
+        assert_eq!(state.transform_cache[&2].0, key);
+        if let crate::NodeData::Transform { steps, .. } = &mut state.nodes.get_mut(&2).unwrap().data { steps.push(Step::Truncate { words: 1 }); }
+        state.recompute_outputs();
+        assert_ne!(state.transform_cache[&2].0, key);
+        assert_eq!(state.nodes[&2].data.output(), Some("Dock"));
+    }
+}
@@ -0,0 +1,44 @@
+use eframe::egui;
+
+/// Model that does the translating.
+pub const TRANSLATE_MODEL: &str = "google/gemini-flash-1.5";
+
+/// Offered in the language dropdown; anything else can be typed in.
+pub const LANGUAGES: [&str; 10] = ["English", "French", "Spanish", "German", "Italian", "Portuguese", "Japanese", "Chinese", "Korean", "Arabic"];
+
+pub fn default_language() -> String { "French".to_string() }
+
+/// Prompt asking for `text` in `language`, with screenplay layout and names left alone.
+pub fn translate_prompt(language: &str, text: &str) -> String {
+    format!("Translate the text below into {}. Keep its line breaks, layout and character names as they are, and reply with only the translation.\n\n---\n{}", language.trim(), text)
+}
+
+/// Language dropdown plus a field for one that isn't listed. Returns true when the language changed.
+pub fn language_picker(ui: &mut egui::Ui, id: u64, language: &mut String) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(("target_lang", id)).selected_text(format!("🌍 {}", language)).width(100.0).show_ui(ui, |ui| {
+            for option in LANGUAGES { changed |= ui.selectable_value(language, option.to_string(), option).changed(); }
+        });
+        changed |= ui.add(egui::TextEdit::singleline(language).hint_text("Other language").desired_width(90.0)).changed();
+    });
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CanvasState, Edge, Node, NodeData, NodeKind};
+
+    #[test]
+    fn translations_hand_their_result_downstream() {
+        let mut state = CanvasState::default();
+        state.nodes.insert(1, Node::new(1, Default::default(), NodeKind::Translate.default_data()));
+        state.nodes.insert(2, Node::new(2, Default::default(), NodeKind::Audio.default_data()));
+        state.edges.push(Edge { id: 3, from: 1, to: 2, label: None, role: None });
+        assert_eq!(state.parent_output(2).as_deref(), Some("— from Translate #1 —\n(no result yet)"), "a placeholder until translated");
+        if let NodeData::Translate { result, .. } = &mut state.nodes.get_mut(&1).unwrap().data { *result = Some("Bonjour".to_string()); }
+        assert_eq!(state.parent_output(2).as_deref(), Some("Bonjour"));
+        assert!(translate_prompt(" French ", "Hi").contains("into French.") && translate_prompt("French", "Hi").ends_with("---\nHi"));
+    }
+}
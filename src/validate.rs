@@ -0,0 +1,156 @@
+use crate::{CanvasState, NodeData, NodeKind, StoryBoardApp};
+use eframe::egui;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Stops "Run Pipeline" until fixed.
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn icon(self) -> &'static str {
+        match self { Self::Error => "⛔", Self::Warning => "⚠" }
+    }
+}
+
+/// What a finding points at on the canvas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subject {
+    Node(u64),
+    Edge(u64),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub subject: Subject,
+    pub message: String,
+}
+
+impl Finding {
+    fn node(severity: Severity, id: u64, message: impl Into<String>) -> Self { Self { severity, subject: Subject::Node(id), message: message.into() } }
+}
+
+/// Sorted node ids, so findings come out in a stable order.
+fn node_ids(state: &CanvasState) -> Vec<u64> {
+    let mut ids: Vec<u64> = state.nodes.keys().copied().collect();
+    ids.sort();
+    ids
+}
+
+fn has_parent(state: &CanvasState, id: u64) -> bool {
+    state.edges.iter().any(|e| e.to == id && state.nodes.contains_key(&e.from))
+}
+
+/// Nodes a pipeline run would execute that nothing feeds: they run on their own text alone. Export nodes are `exports_without_input`'s.
+pub fn orphan_nodes(state: &CanvasState) -> Vec<Finding> {
+    node_ids(state).into_iter().filter(|id| matches!(state.nodes[id].data, NodeData::YouComResearch { .. } | NodeData::AgnosticAI { .. } | NodeData::Visual { .. } | NodeData::Branch { .. } | NodeData::Audio { .. } | NodeData::Translate { .. }) && !has_parent(state, *id))
+        .map(|id| Finding::node(Severity::Warning, id, format!("{} has no parent and runs on its own text only", state.nodes[&id].data.kind().title()))).collect()
+}
+
+/// Export nodes with nothing linked into them, unless they export the whole board anyway.
+pub fn exports_without_input(state: &CanvasState) -> Vec<Finding> {
+    node_ids(state).into_iter().filter(|id| matches!(state.nodes[id].data, NodeData::FoxitExport { include_everything: false, .. }) && !has_parent(state, *id))
+        .map(|id| Finding::node(Severity::Warning, id, "Export has nothing upstream, so the PDF would be empty")).collect()
+}
+
+/// Research and AI nodes still holding the text a new node starts with.
+pub fn placeholder_prompts(state: &CanvasState) -> Vec<Finding> {
+    node_ids(state).into_iter().filter_map(|id| {
+        let data = &state.nodes[&id].data;
+        let kind = data.kind();
+        if !matches!(kind, NodeKind::Research | NodeKind::AgnosticAI) || data.primary_text() != kind.default_data().primary_text() { return None; }
+        Some(Finding::node(Severity::Warning, id, format!("{} still has the placeholder \"{}\"", kind.title(), data.primary_text().unwrap_or_default())))
+    }).collect()
+}
+
+/// Edges whose ends point at deleted nodes.
+pub fn dangling_edges(state: &CanvasState) -> Vec<Finding> {
+    state.edges.iter().filter(|e| !state.nodes.contains_key(&e.from) || !state.nodes.contains_key(&e.to)).map(|e| {
+        let missing = if state.nodes.contains_key(&e.from) { e.to } else { e.from };
+        Finding { severity: Severity::Warning, subject: Subject::Edge(e.id), message: format!("Link {} → {} points at deleted node #{}", e.from, e.to, missing) }
+    }).collect()
+}
+
+/// Nodes that are part of a loop, which no run order can satisfy.
+pub fn cycles(state: &CanvasState) -> Vec<Finding> {
+    let Err(stuck) = state.pipeline_order() else { return Vec::new() };
+    // `stuck` also holds nodes downstream of a loop; only the ones on it are reported.
+    stuck.into_iter().filter(|&id| state.edges.iter().any(|e| e.to == id && (e.from == id || state.ancestors(e.from).contains(&id))))
+        .map(|id| Finding::node(Severity::Error, id, "Part of a cycle, so the pipeline can't be ordered")).collect()
+}
+
+impl CanvasState {
+    /// Every check, errors first.
+    pub fn validate(&self) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = [cycles, dangling_edges, exports_without_input, placeholder_prompts, orphan_nodes].iter().flat_map(|check| check(self)).collect();
+        findings.sort_by_key(|f| f.severity);
+        findings
+    }
+}
+
+impl StoryBoardApp {
+    /// "Graph check" window, re-run every frame while open. Clicking a finding selects and centers what it points at.
+    pub(crate) fn draw_validation(&mut self, ctx: &egui::Context) {
+        if !self.show_validation { return; }
+        let findings = self.state.validate();
+        let (mut open, mut focus) = (true, None);
+        egui::Window::new("🩺 Graph check").open(&mut open).collapsible(false).default_width(360.0).show(ctx, |ui| {
+            if findings.is_empty() { ui.label("✔ No problems found"); return; }
+            let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+            ui.weak(format!("{} problems, {} blocking Run Pipeline", findings.len(), errors));
+            ui.separator();
+            egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                for finding in &findings {
+                    let target = match finding.subject { Subject::Node(id) => format!("#{}", id), Subject::Edge(id) => format!("link {}", id) };
+                    if ui.selectable_label(false, format!("{} {} — {}", finding.severity.icon(), target, finding.message)).on_hover_text("Select and center it").clicked() { focus = Some(finding.subject); }
+                }
+            });
+        });
+        match focus {
+            Some(Subject::Node(id)) => { self.state.select_only(id); self.focus_camera_on(id); }
+            Some(Subject::Edge(id)) => {
+                self.state.selected_edge = Some(id);
+                // A dangling link is centered on whichever end still exists.
+                let ends: Vec<u64> = self.state.edges.iter().filter(|e| e.id == id).flat_map(|e| [e.from, e.to]).filter(|n| self.state.nodes.contains_key(n)).collect();
+                if let Some(center) = ends.iter().map(|n| self.state.nodes[n].bounds().center().to_vec2()).reduce(|a, b| (a + b) / 2.0) { self.animate_camera(center, self.state.camera_zoom.max(crate::READABLE_ZOOM), 0.35); }
+            }
+            None => {}
+        }
+        if !open { self.show_validation = false; }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subjects(findings: Vec<Finding>) -> Vec<Subject> { findings.into_iter().map(|f| f.subject).collect() }
+
+    #[test]
+    fn orphans_and_empty_exports_are_flagged() {
+        let state = CanvasState::graph(&[(1, NodeKind::Concept), (2, NodeKind::AgnosticAI), (3, NodeKind::Visual), (4, NodeKind::FoxitExport), (5, NodeKind::FoxitExport)], &[(1, 2), (2, 5)]);
+        assert_eq!(subjects(orphan_nodes(&state)), vec![Subject::Node(3)]);
+        assert_eq!(subjects(exports_without_input(&state)), vec![Subject::Node(4)]);
+    }
+
+    #[test]
+    fn placeholders_only_until_edited() {
+        let mut state = CanvasState::graph(&[(1, NodeKind::Research), (2, NodeKind::AgnosticAI), (3, NodeKind::Visual)], &[]);
+        assert_eq!(subjects(placeholder_prompts(&state)), vec![Subject::Node(1), Subject::Node(2)]);
+        if let NodeData::AgnosticAI { prompt, .. } = &mut state.nodes.get_mut(&2).unwrap().data { *prompt = "Write a scene".to_string(); }
+        assert_eq!(subjects(placeholder_prompts(&state)), vec![Subject::Node(1)]);
+    }
+
+    #[test]
+    fn dangling_edges_and_cycles() {
+        // 1 → 2 → 3 → 2 is a loop with 4 stuck behind it; edge 104 points at a deleted node 9.
+        let state = CanvasState::graph(&[(1, NodeKind::Concept), (2, NodeKind::AgnosticAI), (3, NodeKind::AgnosticAI), (4, NodeKind::Visual)], &[(1, 2), (2, 3), (3, 2), (3, 4), (4, 9)]);
+        assert_eq!(subjects(cycles(&state)), vec![Subject::Node(2), Subject::Node(3)]);
+        assert_eq!(subjects(dangling_edges(&state)), vec![Subject::Edge(104)]);
+        let findings = state.validate();
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(CanvasState::graph(&[(1, NodeKind::Concept)], &[]).validate().is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{load_texture, Node, NodeData, StoryBoardApp};
+use eframe::egui::{self, Vec2};
+
+/// Most candidate images one Generate click can ask for.
+pub const MAX_VARIANTS: usize = 4;
+const THUMBNAIL_HEIGHT: f32 = 40.0;
+
+pub fn default_variant_count() -> usize { 1 }
+
+/// One slot of a multi-image generation, filled in as its response arrives.
+#[derive(Clone)]
+pub enum Variant {
+    Pending,
+    /// The image, its raw bytes, and the seed it was made with.
+    Ready(egui::TextureHandle, Vec<u8>, Option<u64>),
+    Failed(String),
+}
+
+impl Node {
+    /// Clears the thumbnail strip for a new batch when the node asks for several images; returns how many requests to send.
+    pub fn start_variants(&mut self) -> usize {
+        let NodeData::Visual { variant_count, variants, .. } = &mut self.data else { return 1 };
+        let count = (*variant_count).clamp(1, MAX_VARIANTS);
+        if count > 1 { *variants = vec![Variant::Pending; count]; }
+        count
+    }
+
+    /// Makes variant `index` the node's image; the image it replaces goes to the result history.
+    pub fn promote_variant(&mut self, index: usize) {
+        let Some(Variant::Ready(tex, bytes, seed)) = (match &self.data { NodeData::Visual { variants, .. } => variants.get(index).cloned(), _ => None }) else { return };
+        self.archive_result();
+        if let NodeData::Visual { texture, image, image_seed, .. } = &mut self.data { *texture = Some(tex); *image = Some(bytes); *image_seed = seed; }
+    }
+}
+
+/// Row of candidate thumbnails; the one currently shown on the node is outlined. Returns the index clicked.
+pub fn variant_strip(ui: &mut egui::Ui, variants: &[Variant], current: Option<egui::TextureId>) -> Option<usize> {
+    let mut clicked = None;
+    ui.horizontal_wrapped(|ui| {
+        for (i, variant) in variants.iter().enumerate() {
+            match variant {
+                Variant::Pending => { ui.add_sized(Vec2::splat(THUMBNAIL_HEIGHT), egui::Spinner::new()); }
+                Variant::Ready(tex, _, seed) => {
+                    let size = Vec2::new(THUMBNAIL_HEIGHT * tex.aspect_ratio(), THUMBNAIL_HEIGHT);
+                    let r = ui.add(egui::Image::new(egui::load::SizedTexture::new(tex.id(), size)).sense(egui::Sense::click())).on_hover_text(format!("Variant {}{}: click to use", i + 1, seed.map_or(String::new(), |s| format!(", seed {}", s))));
+                    if current == Some(tex.id()) { ui.painter().rect_stroke(r.rect.expand(1.5), 2.0, egui::Stroke::new(2.0, ui.visuals().selection.stroke.color)); }
+                    if r.clicked() { clicked = Some(i); }
+                }
+                Variant::Failed(err) => { ui.add_sized(Vec2::splat(THUMBNAIL_HEIGHT), egui::Label::new(egui::RichText::new("⚠").color(ui.visuals().error_fg_color))).on_hover_text(format!("Variant {} failed: {}", i + 1, err)); }
+            }
+        }
+    });
+    clicked
+}
+
+impl StoryBoardApp {
+    /// Files the answer for one variant into its slot. The first image to arrive is shown right away; the node stops loading once every slot has answered.
+    pub(crate) fn receive_variant(&mut self, ctx: &egui::Context, id: u64, index: usize, result: Result<Vec<u8>, String>) {
+        let Some(node) = self.node_mut(id) else { return };
+        let seed = match &node.data { NodeData::Visual { request_seeds, .. } => request_seeds.get(index).copied(), _ => None };
+        let slot = match result {
+            Ok(bytes) => match load_texture(ctx, format!("node-image-{}-variant-{}", id, index), &bytes) {
+                Some(tex) => Variant::Ready(tex, bytes, seed),
+                None => Variant::Failed("The server sent an image that couldn't be decoded".to_string()),
+            },
+            Err(err) => Variant::Failed(err),
+        };
+        let NodeData::Visual { variants, is_loading, .. } = &mut node.data else { return };
+        let Some(target) = variants.get_mut(index) else { return };
+        *target = slot;
+        let first_ready = matches!(variants[index], Variant::Ready(..)) && variants.iter().filter(|v| matches!(v, Variant::Ready(..))).count() == 1;
+        if !variants.iter().any(|v| matches!(v, Variant::Pending)) {
+            *is_loading = false;
+            if let [Variant::Failed(err), ..] = &variants[..] {
+                if variants.iter().all(|v| matches!(v, Variant::Failed(_))) { node.error = Some(format!("All {} variants failed: {}", variants.len(), err)); }
+            }
+        }
+        if first_ready { node.promote_variant(index); }
+    }
+}
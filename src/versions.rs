@@ -0,0 +1,106 @@
+use crate::{load_node_texture, load_texture, Node, NodeData, StoryBoardApp};
+use eframe::egui;
+
+/// Earlier results kept per node.
+pub const MAX_VERSIONS: usize = 10;
+/// Budget for one node's earlier results, mostly images; the oldest go first once it's exceeded.
+pub const MAX_VERSION_BYTES: usize = 8 * 1024 * 1024;
+
+/// A result that has since been replaced: text for Research and AI nodes, the raw image for Visual nodes.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ResultVersion {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::base64_bytes")]
+    pub image: Option<Vec<u8>>,
+    /// Seed a Visual image was generated with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl ResultVersion {
+    fn size(&self) -> usize { self.text.as_ref().map_or(0, String::len) + self.image.as_ref().map_or(0, Vec::len) }
+}
+
+/// An earlier version shown in place of the current result, with its image decoded for Visual nodes.
+pub struct VersionView {
+    pub index: usize,
+    pub texture: Option<egui::TextureHandle>,
+}
+
+pub enum VersionAction {
+    /// Show version `index`, or the current result for `None`.
+    View(Option<usize>),
+    Restore(usize),
+}
+
+impl NodeData {
+    fn current_version(&self) -> Option<ResultVersion> {
+        match self {
+            Self::YouComResearch { result: Some(text), .. } | Self::AgnosticAI { result: Some(text), .. } | Self::Translate { result: Some(text), .. } | Self::WebFetch { result: Some(text), .. } => Some(ResultVersion { text: Some(text.clone()), image: None, seed: None }),
+            Self::Visual { image: Some(bytes), image_seed, .. } => Some(ResultVersion { text: None, image: Some(bytes.clone()), seed: *image_seed }),
+            // Kept with any corrections, so transcribing again doesn't lose them.
+            Self::Transcribe { transcript, .. } if !transcript.trim().is_empty() => Some(ResultVersion { text: Some(transcript.clone()), image: None, seed: None }),
+            _ => None,
+        }
+    }
+}
+
+impl Node {
+    /// Files the current result away; call before it gets replaced.
+    pub fn archive_result(&mut self) {
+        let Some(version) = self.data.current_version() else { return };
+        if self.versions.last() == Some(&version) { return; }
+        self.versions.push(version);
+        while self.versions.len() > MAX_VERSIONS || (self.versions.len() > 1 && self.versions.iter().map(ResultVersion::size).sum::<usize>() > MAX_VERSION_BYTES) { self.versions.remove(0); }
+    }
+
+    /// Makes version `index` current again; the result it replaces becomes the newest version. Visual textures are left to the caller.
+    fn restore_version(&mut self, index: usize) {
+        if index >= self.versions.len() { return; }
+        let version = self.versions.remove(index);
+        self.archive_result();
+        match &mut self.data {
+            NodeData::YouComResearch { result, .. } | NodeData::AgnosticAI { result, .. } | NodeData::Translate { result, .. } | NodeData::WebFetch { result, .. } => *result = version.text,
+            NodeData::Visual { image, texture, image_seed, .. } => { *image = version.image; *texture = None; *image_seed = version.seed; }
+            NodeData::Transcribe { transcript, .. } => *transcript = version.text.unwrap_or_default(),
+            _ => {}
+        }
+    }
+}
+
+/// "History ◂ 2/5 ▸" row: older versions to the left, the current result last.
+pub fn version_navigator(ui: &mut egui::Ui, count: usize, viewing: Option<usize>) -> Option<VersionAction> {
+    let total = count + 1;
+    let position = viewing.map_or(total, |i| i + 1);
+    let mut action = None;
+    ui.horizontal(|ui| {
+        ui.weak("History");
+        if ui.add_enabled(position > 1, egui::Button::new("◂").small()).on_hover_text("Older").clicked() { action = Some(VersionAction::View(Some(position - 2))); }
+        ui.label(format!("{}/{}", position, total));
+        if ui.add_enabled(position < total, egui::Button::new("▸").small()).on_hover_text("Newer").clicked() { action = Some(VersionAction::View((position + 1 < total).then_some(position))); }
+        if let Some(index) = viewing {
+            if ui.small_button("↺ Restore").on_hover_text("Make this version current again").clicked() { action = Some(VersionAction::Restore(index)); }
+        }
+    });
+    action
+}
+
+impl StoryBoardApp {
+    pub(crate) fn apply_version_action(&mut self, id: u64, action: VersionAction, ctx: &egui::Context) {
+        match action {
+            VersionAction::View(None) => { self.version_views.remove(&id); }
+            VersionAction::View(Some(index)) => {
+                let Some(version) = self.state.nodes.get(&id).and_then(|n| n.versions.get(index)) else { return };
+                let texture = version.image.as_deref().and_then(|bytes| load_texture(ctx, format!("node-image-{}-version", id), bytes));
+                self.version_views.insert(id, VersionView { index, texture });
+            }
+            VersionAction::Restore(index) => {
+                self.version_views.remove(&id);
+                let Some(node) = self.state.nodes.get_mut(&id) else { return };
+                node.restore_version(index);
+                if let NodeData::Visual { image: Some(bytes), texture, .. } = &mut node.data { *texture = load_node_texture(ctx, id, bytes); }
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+/// The first http(s) link in `text`, without trailing punctuation; how a Web Page node picks its URL out of a parent's text.
+pub fn first_url(text: &str) -> Option<String> {
+    let token = text.split_whitespace().map(|t| t.trim_start_matches(['(', '<', '"', '\''])).find(|t| t.starts_with("http://") || t.starts_with("https://"))?;
+    Some(token.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '>', '"', '\'']).to_string())
+}
+
+/// Worth sending to the server: an http(s) link with something after the scheme.
+pub fn looks_fetchable(url: &str) -> bool {
+    first_url(url).is_some_and(|u| u == url.trim() && u.split_once("://").is_some_and(|(_, rest)| !rest.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_are_found_in_parent_text() {
+        assert_eq!(first_url("Read (https://example.com/mars?id=2). Then summarise.").as_deref(), Some("https://example.com/mars?id=2"));
+        assert_eq!(first_url("no links here, just www.example.com"), None);
+        assert!(looks_fetchable(" https://example.com ") && !looks_fetchable("https://") && !looks_fetchable("see https://example.com"));
+    }
+}